@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use sins_lib::baseline::Fingerprint;
+
+const VERSION: u32 = 1;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct BaselineFile {
+  version: u32,
+  entries: Vec<Fingerprint>,
+}
+
+/// Findings recorded by `--write-baseline`, which `--baseline` suppresses.
+///
+/// Identical findings within the same item share a fingerprint, so each entry
+/// is counted and suppresses at most that many findings.
+pub struct Baseline {
+  remaining: BTreeMap<Fingerprint, usize>,
+}
+
+impl Baseline {
+  pub fn read(path: &Path) -> Result<Self, String> {
+    let error =
+      |e: &dyn std::fmt::Display| format!("{}: {}", path.display(), e);
+    let text = std::fs::read_to_string(path).map_err(|e| error(&e))?;
+    let file: BaselineFile =
+      serde_json::from_str(&text).map_err(|e| error(&e))?;
+    if file.version != VERSION {
+      return Err(error(&format!(
+        "unsupported baseline version {}",
+        file.version
+      )));
+    }
+    let mut remaining = BTreeMap::new();
+    for entry in file.entries {
+      *remaining.entry(entry).or_insert(0) += 1;
+    }
+    Ok(Self { remaining })
+  }
+
+  pub fn write(
+    path: &Path,
+    mut entries: Vec<Fingerprint>,
+  ) -> Result<(), String> {
+    entries.sort();
+    let file = BaselineFile {
+      version: VERSION,
+      entries,
+    };
+    let text =
+      serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+    std::fs::write(path, text + "\n")
+      .map_err(|e| format!("{}: {}", path.display(), e))
+  }
+
+  /// Consumes an entry matching `fingerprint`, returning whether there was
+  /// one left.
+  pub fn suppress(&mut self, fingerprint: &Fingerprint) -> bool {
+    match self.remaining.get_mut(fingerprint) {
+      Some(count) if *count > 0 => {
+        *count -= 1;
+        true
+      }
+      _ => false,
+    }
+  }
+
+  /// Entries that nothing matched, since fixed or changed: those for `files`,
+  /// which were linted, and those for files that no longer exist. `baseline`
+  /// is the baseline's own path, which entries' files are relative to.
+  pub fn stale<'a>(
+    &'a self,
+    baseline: &'a Path,
+    files: &'a HashSet<String>,
+  ) -> impl Iterator<Item = &'a Fingerprint> {
+    let dir = baseline.parent().unwrap_or(Path::new(""));
+    // Files outside the baseline's directory are recorded as given.
+    let exists =
+      move |file: &str| dir.join(file).exists() || Path::new(file).exists();
+    self
+      .remaining
+      .iter()
+      .filter(move |(entry, _)| {
+        files.contains(&entry.file) || !exists(&entry.file)
+      })
+      .flat_map(|(entry, count)| std::iter::repeat_n(entry, *count))
+  }
+}
+
+/// How `path` is recorded in the baseline at `baseline`: relative to the
+/// baseline's directory where possible, so the baseline works from any
+/// working directory.
+pub fn file_key(baseline: &Path, path: &Path) -> String {
+  let absolute = |path: &Path| std::path::absolute(path).ok();
+  let relative = absolute(baseline).zip(absolute(path)).and_then(|(b, p)| {
+    let dir = b.parent()?;
+    Some(p.strip_prefix(dir).ok()?.to_owned())
+  });
+  let path = relative.as_deref().unwrap_or(path);
+  path
+    .components()
+    .map(|component| component.as_os_str().to_string_lossy())
+    .collect::<Vec<_>>()
+    .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_suppress_counts_entries() {
+    let entry = |snippet: &str| Fingerprint {
+      rule: "unwrap_used".into(),
+      file: "src/a.rs".into(),
+      item: "main".into(),
+      snippet: snippet.into(),
+    };
+    let dir = std::env::temp_dir().join("sins-baseline-test-suppress");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("sins-baseline.json");
+    Baseline::write(&path, vec![entry("a.unwrap()"), entry("a.unwrap()")])
+      .unwrap();
+
+    let mut baseline = Baseline::read(&path).unwrap();
+    assert!(baseline.suppress(&entry("a.unwrap()")));
+    assert!(!baseline.suppress(&entry("b.unwrap()")));
+    let files = HashSet::from(["src/a.rs".to_owned()]);
+    assert_eq!(baseline.stale(&path, &files).count(), 1);
+    assert!(baseline.suppress(&entry("a.unwrap()")));
+    assert!(!baseline.suppress(&entry("a.unwrap()")));
+    assert_eq!(baseline.stale(&path, &files).count(), 0);
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn test_stale_entries_of_deleted_files() {
+    let entry = |file: &str| Fingerprint {
+      rule: "unwrap_used".into(),
+      file: file.into(),
+      item: "main".into(),
+      snippet: "a.unwrap()".into(),
+    };
+    let dir = std::env::temp_dir().join("sins-baseline-test-deleted");
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("src/kept.rs"), "").unwrap();
+    let path = dir.join("sins-baseline.json");
+    Baseline::write(&path, vec![entry("src/kept.rs"), entry("src/gone.rs")])
+      .unwrap();
+
+    // Neither file was linted, but only one of them still exists.
+    let baseline = Baseline::read(&path).unwrap();
+    let linted = HashSet::new();
+    let stale: Vec<_> = baseline
+      .stale(&path, &linted)
+      .map(|entry| entry.file.as_str())
+      .collect();
+    assert_eq!(stale, ["src/gone.rs"]);
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn test_file_key() {
+    assert_eq!(
+      file_key(Path::new("sins-baseline.json"), Path::new("./src/a.rs")),
+      "src/a.rs"
+    );
+    assert_eq!(
+      file_key(
+        Path::new("sub/sins-baseline.json"),
+        Path::new("sub/src/a.rs")
+      ),
+      "src/a.rs"
+    );
+  }
+}
@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use sins_lib::{Diagnostic, ParseError};
+
+use crate::config::ResolvedConfig;
+
+pub const DEFAULT_DIR: &str = "target/sins";
+
+pub type LintResult = Result<Vec<Diagnostic>, ParseError>;
+
+/// On-disk cache of lint results, keyed by a hash of the file contents and
+/// the fingerprint of the configuration it was linted with.
+///
+/// Entries are written atomically and never modified, so concurrent runs can
+/// share a cache directory. Any failure to read or write is treated as a miss.
+pub struct Cache {
+  dir: PathBuf,
+}
+
+impl Cache {
+  pub fn new(dir: impl Into<PathBuf>) -> Self {
+    Self { dir: dir.into() }
+  }
+
+  pub fn key(source: &str, config: &ResolvedConfig) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(config.fingerprint.as_bytes());
+    hasher.update(source.as_bytes());
+    hasher.finalize().to_hex().to_string()
+  }
+
+  pub fn get(&self, key: &str) -> Option<LintResult> {
+    let text = std::fs::read_to_string(self.path(key)).ok()?;
+    serde_json::from_str(&text).ok()
+  }
+
+  pub fn put(&self, key: &str, result: &LintResult) {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let tmp = self.dir.join(format!(
+      "{}.{}.{}.tmp",
+      key,
+      std::process::id(),
+      COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let written = std::fs::create_dir_all(&self.dir)
+      .and_then(|_| std::fs::write(&tmp, serde_json::to_vec(result)?))
+      .and_then(|_| std::fs::rename(&tmp, self.path(key)));
+    if written.is_err() {
+      let _ = std::fs::remove_file(&tmp);
+    }
+  }
+
+  fn path(&self, key: &str) -> PathBuf {
+    self.dir.join(format!("{}.json", key))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::Configs;
+
+  #[test]
+  fn test_roundtrip() {
+    let dir = std::env::temp_dir().join("sins-cache-test-roundtrip");
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache = Cache::new(&dir);
+    let config = Configs::default().resolve(&dir.join("main.rs")).unwrap();
+
+    let key = Cache::key("fn main() {}", &config);
+    assert_ne!(key, Cache::key("fn main() { }", &config));
+    assert_eq!(cache.get(&key), None);
+
+    let result: LintResult = Err(ParseError {
+      start: sins_lib::Position { line: 1, column: 3 },
+      message: "expected `(`".into(),
+    });
+    cache.put(&key, &result);
+    assert_eq!(cache.get(&key), Some(result));
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+}
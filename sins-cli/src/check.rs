@@ -0,0 +1,208 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use rayon::prelude::*;
+
+use crate::baseline::Baseline;
+use crate::cache::{Cache, LintResult};
+use crate::config::ResolvedConfig;
+
+#[derive(clap::Args)]
+pub struct CheckArgs {
+  /// Files or directories to lint.
+  #[arg(default_value = ".")]
+  pub paths: Vec<PathBuf>,
+  /// Directory to cache lint results in.
+  #[arg(long, default_value = crate::cache::DEFAULT_DIR)]
+  pub cache_dir: PathBuf,
+  /// Lint every file, ignoring and not updating the cache.
+  #[arg(long)]
+  pub no_cache: bool,
+  /// Lint source read from stdin instead of files.
+  #[arg(long, conflicts_with = "paths")]
+  pub stdin: bool,
+  /// Path to report diagnostics and resolve configuration for when linting
+  /// stdin, as though the source was read from it.
+  #[arg(long, requires = "stdin")]
+  pub stdin_filename: Option<PathBuf>,
+  /// Only report diagnostics on lines added or changed since this git
+  /// revision, skipping files without changes.
+  #[arg(long, value_name = "REV", conflicts_with = "stdin")]
+  pub diff_base: Option<String>,
+  /// With --diff-base, report every diagnostic in changed files rather than
+  /// only those on changed lines.
+  #[arg(long, requires = "diff_base")]
+  pub changed_files_only: bool,
+  /// Record the current findings in this file and exit successfully, for
+  /// later runs to ignore with --baseline.
+  #[arg(long, value_name = "FILE", conflicts_with_all = ["baseline", "diff_base"])]
+  pub write_baseline: Option<PathBuf>,
+  /// Don't report findings recorded in this baseline file, but do report
+  /// baseline entries that no longer match anything.
+  #[arg(long, value_name = "FILE")]
+  pub baseline: Option<PathBuf>,
+}
+
+pub fn run(args: &CheckArgs) -> Result<ExitCode, String> {
+  let mut configs = crate::config::Configs::default();
+  let cache = (!args.no_cache).then(|| Cache::new(&args.cache_dir));
+
+  let changes = match &args.diff_base {
+    Some(base) => Some(crate::git::changes(&args.paths, base)?),
+    None => None,
+  };
+
+  let (files, results) = if args.stdin {
+    let path = args
+      .stdin_filename
+      .clone()
+      .unwrap_or_else(|| PathBuf::from("<stdin>"));
+    let mut source = String::new();
+    std::io::Read::read_to_string(&mut std::io::stdin(), &mut source)
+      .map_err(|e| format!("<stdin>: {}", e))?;
+    let config = configs.resolve(&path)?;
+    let result = lint_cached(&source, &config, cache.as_ref());
+    (vec![path], vec![Ok((source, result))])
+  } else {
+    let mut files =
+      crate::files::collect(&args.paths).map_err(|e| e.to_string())?;
+    if let Some(changes) = &changes {
+      files.retain(|path| changes.contains_file(path));
+    }
+    let configs = files
+      .iter()
+      .map(|path| configs.resolve(path))
+      .collect::<Result<Vec<_>, _>>()?;
+    // Files are linted in parallel but `collect` keeps them in path order, so
+    // output is the same regardless of scheduling.
+    let results: Vec<_> = files
+      .par_iter()
+      .zip(&configs)
+      .map(|(path, config)| {
+        let source = read(path)?;
+        let result = lint_cached(&source, config, cache.as_ref());
+        Ok((source, result))
+      })
+      .collect::<Vec<Result<_, String>>>();
+    (files, results)
+  };
+
+  let mut baseline = match &args.baseline {
+    Some(path) => Some(Baseline::read(path)?),
+    None => None,
+  };
+  let baseline_path = args.baseline.as_ref().or(args.write_baseline.as_ref());
+  let mut recorded = vec![];
+  let mut baselined_files = HashSet::new();
+
+  let mut failed = false;
+  for (path, result) in files.iter().zip(results) {
+    let (source, result) = result?;
+    match result {
+      Ok(mut diagnostics) => {
+        if let Some(baseline_path) = baseline_path {
+          let file = crate::baseline::file_key(baseline_path, path);
+          let fingerprints =
+            sins_lib::baseline::fingerprints(&source, &file, &diagnostics);
+          baselined_files.insert(file);
+          if let Some(baseline) = &mut baseline {
+            diagnostics = diagnostics
+              .into_iter()
+              .zip(&fingerprints)
+              .filter(|(_, fingerprint)| !baseline.suppress(fingerprint))
+              .map(|(diagnostic, _)| diagnostic)
+              .collect();
+          } else {
+            recorded.extend(fingerprints);
+            continue;
+          }
+        }
+        if let (Some(changes), false) = (&changes, args.changed_files_only) {
+          diagnostics
+            .retain(|d| changes.overlaps(path, d.start.line, d.end.line));
+        }
+        failed |= !diagnostics.is_empty();
+        print_diagnostics(path, &diagnostics);
+      }
+      Err(err) => {
+        // Writing a baseline succeeds regardless, recording what parsed.
+        failed |= args.write_baseline.is_none();
+        print_parse_error(path, &err);
+      }
+    }
+  }
+
+  if let Some(path) = &args.write_baseline {
+    let count = recorded.len();
+    Baseline::write(path, recorded)?;
+    eprintln!("wrote {} findings to {}", count, path.display());
+  }
+  if let (Some(baseline), Some(path)) = (&baseline, &args.baseline) {
+    for entry in baseline.stale(path, &baselined_files) {
+      let item = if entry.item.is_empty() {
+        String::new()
+      } else {
+        format!(" in {}", entry.item)
+      };
+      eprintln!(
+        "[{}] stale baseline entry: {}{}: {}",
+        entry.file, entry.rule, item, entry.snippet
+      );
+    }
+  }
+  Ok(if failed {
+    ExitCode::FAILURE
+  } else {
+    ExitCode::SUCCESS
+  })
+}
+
+fn lint_cached(
+  source: &str,
+  config: &ResolvedConfig,
+  cache: Option<&Cache>,
+) -> LintResult {
+  let cache = match cache {
+    Some(cache) => cache,
+    None => return sins_lib::lint(source, config),
+  };
+  let key = Cache::key(source, config);
+  if let Some(result) = cache.get(&key) {
+    return result;
+  }
+  let result = sins_lib::lint(source, config);
+  cache.put(&key, &result);
+  result
+}
+
+pub fn read(path: &Path) -> Result<String, String> {
+  std::fs::read_to_string(path)
+    .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Prints `diagnostics` sorted by position, with 1-indexed columns.
+pub fn print_diagnostics(path: &Path, diagnostics: &[sins_lib::Diagnostic]) {
+  let mut diagnostics: Vec<_> = diagnostics.iter().collect();
+  diagnostics.sort_by_key(|d| (d.start, d.end));
+  for diagnostic in diagnostics {
+    println!(
+      "[{}:{}:{}] {}: {}",
+      path.display(),
+      diagnostic.start.line,
+      diagnostic.start.column + 1,
+      diagnostic.rule,
+      diagnostic.message
+    );
+  }
+}
+
+pub fn print_parse_error(path: &Path, err: &sins_lib::ParseError) {
+  eprintln!(
+    "[{}:{}:{}] parse error: {}",
+    path.display(),
+    err.start.line,
+    err.start.column + 1,
+    err.message
+  );
+}
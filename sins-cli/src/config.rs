@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use sins_lib::config::{Config, FILE_NAME};
+use sins_lib::{RuleEngine, RuleFactory};
+
+use crate::plugin::{Plugin, PluginRule};
+
+/// The configuration governing a file: the nearest `sins.toml` in the file's
+/// directory or its ancestors, or the defaults if there is none.
+#[derive(Debug)]
+pub struct ResolvedConfig {
+  pub config: Config,
+  /// The plugins listed in the config, loaded.
+  pub plugins: Vec<Arc<Plugin>>,
+  /// Hash of the sins version, the rules the config enables (and their
+  /// versions), the config file's contents and the plugins it loads.
+  pub fingerprint: blake3::Hash,
+}
+
+impl ResolvedConfig {
+  fn load(path: Option<&Path>) -> Result<Self, String> {
+    let text = match path {
+      Some(path) => std::fs::read_to_string(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?,
+      None => String::new(),
+    };
+    let config = Config::from_toml(&text).map_err(|e| match path {
+      Some(path) => format!("{}: {}", path.display(), e),
+      None => e.to_string(),
+    })?;
+
+    let dir = path.and_then(Path::parent).unwrap_or(Path::new(""));
+    let plugins = config
+      .plugins
+      .iter()
+      .map(|plugin| Plugin::load(&dir.join(plugin)))
+      .collect::<Result<Vec<_>, _>>()
+      .map_err(|e| match path {
+        Some(path) => format!("{}: {}", path.display(), e),
+        None => e,
+      })?;
+
+    let mut rule_engine = RuleEngine::new();
+    config.add_rules(&mut rule_engine);
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+    for (name, version) in rule_engine.rule_versions() {
+      hasher.update(format!("\0{}@{}", name, version).as_bytes());
+    }
+    hasher.update(b"\0");
+    hasher.update(text.as_bytes());
+    for plugin in &plugins {
+      hasher.update(b"\0");
+      hasher.update(plugin.hash.as_bytes());
+    }
+    Ok(Self {
+      config,
+      plugins,
+      fingerprint: hasher.finalize(),
+    })
+  }
+}
+
+impl RuleFactory for ResolvedConfig {
+  fn add_rules(&self, rule_engine: &mut RuleEngine<'_>) {
+    self.config.add_rules(rule_engine);
+    let disable: Arc<[String]> = self.config.rules.disable.clone().into();
+    for plugin in &self.plugins {
+      rule_engine.add_rule(Box::new(PluginRule {
+        plugin: plugin.clone(),
+        disable: disable.clone(),
+      }));
+    }
+  }
+}
+
+/// Resolves configuration for files, loading each `sins.toml` at most once.
+#[derive(Default)]
+pub struct Configs {
+  by_dir: HashMap<PathBuf, Option<PathBuf>>,
+  loaded: HashMap<Option<PathBuf>, Arc<ResolvedConfig>>,
+}
+
+impl Configs {
+  pub fn resolve(
+    &mut self,
+    file: &Path,
+  ) -> Result<Arc<ResolvedConfig>, String> {
+    let file = std::path::absolute(file).map_err(|e| e.to_string())?;
+    let dir = file.parent().unwrap_or(&file).to_owned();
+    let path = match self.by_dir.get(&dir) {
+      Some(path) => path.clone(),
+      None => {
+        let path = find(&dir);
+        self.by_dir.insert(dir, path.clone());
+        path
+      }
+    };
+    if let Some(resolved) = self.loaded.get(&path) {
+      return Ok(resolved.clone());
+    }
+    let resolved = Arc::new(ResolvedConfig::load(path.as_deref())?);
+    self.loaded.insert(path, resolved.clone());
+    Ok(resolved)
+  }
+}
+
+fn find(dir: &Path) -> Option<PathBuf> {
+  dir
+    .ancestors()
+    .map(|ancestor| ancestor.join(FILE_NAME))
+    .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_resolve_nearest() {
+    let dir = std::env::temp_dir().join("sins-config-test-resolve-nearest");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("a/b")).unwrap();
+    std::fs::write(dir.join(FILE_NAME), "").unwrap();
+    std::fs::write(
+      dir.join("a").join(FILE_NAME),
+      "[rules]\ndisable = [\"absurd_extreme_comparisons\"]\n",
+    )
+    .unwrap();
+
+    let mut configs = Configs::default();
+    let root = configs.resolve(&dir.join("main.rs")).unwrap();
+    let nested = configs.resolve(&dir.join("a/b/lib.rs")).unwrap();
+    assert!(root.config.rules.disable.is_empty());
+    assert_eq!(nested.config.rules.disable, ["absurd_extreme_comparisons"]);
+    assert_ne!(root.fingerprint, nested.fingerprint);
+    assert!(Arc::ptr_eq(
+      &nested,
+      &configs.resolve(&dir.join("a/other.rs")).unwrap()
+    ));
+    // Files needn't exist, e.g. for `--stdin-filename`.
+    assert!(Arc::ptr_eq(
+      &nested,
+      &configs.resolve(&dir.join("a/unsaved/new.rs")).unwrap()
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+}
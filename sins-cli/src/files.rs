@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+/// Expands `paths` into the sorted list of rust source files beneath them,
+/// skipping hidden directories and `target`.
+pub fn collect(paths: &[PathBuf]) -> std::io::Result<Vec<PathBuf>> {
+  let mut files = vec![];
+  for path in paths {
+    if path.is_dir() {
+      walk(path, &mut files)?;
+    } else {
+      std::fs::metadata(path)?;
+      files.push(path.clone());
+    }
+  }
+  files.sort();
+  files.dedup();
+  Ok(files)
+}
+
+/// Whether `collect(roots)` would include `path`, were it to exist.
+pub fn includes(roots: &[PathBuf], path: &Path) -> bool {
+  roots.iter().any(|root| {
+    if root == path {
+      return true;
+    }
+    match path.strip_prefix(root) {
+      Ok(relative) => {
+        let mut dirs = relative.parent().into_iter().flat_map(Path::iter);
+        is_source(path) && !dirs.any(|dir| is_skipped(dir.as_ref()))
+      }
+      Err(_) => false,
+    }
+  })
+}
+
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+  for entry in std::fs::read_dir(dir)? {
+    let path = entry?.path();
+    if path.is_dir() {
+      if !is_skipped(&path) {
+        walk(&path, files)?;
+      }
+    } else if is_source(&path) {
+      files.push(path);
+    }
+  }
+  Ok(())
+}
+
+fn is_source(path: &Path) -> bool {
+  path.extension() == Some("rs".as_ref())
+}
+
+fn is_skipped(dir: &Path) -> bool {
+  let name = dir.file_name().and_then(|name| name.to_str()).unwrap_or("");
+  name.starts_with('.') || name == "target"
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_collect() {
+    let dir = std::env::temp_dir().join("sins-files-test-collect");
+    let _ = std::fs::remove_dir_all(&dir);
+    for file in [
+      "src/main.rs",
+      "src/a/b.rs",
+      "README.md",
+      "target/x.rs",
+      ".git/y.rs",
+    ] {
+      let path = dir.join(file);
+      std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+      std::fs::write(path, "").unwrap();
+    }
+
+    let files = collect(&[dir.clone(), dir.join("src/main.rs")]).unwrap();
+    assert_eq!(files, vec![dir.join("src/a/b.rs"), dir.join("src/main.rs")]);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_includes() {
+    let roots = [PathBuf::from("/a"), PathBuf::from("/b/c.rs")];
+    assert!(includes(&roots, Path::new("/a/src/main.rs")));
+    assert!(includes(&roots, Path::new("/b/c.rs")));
+    assert!(!includes(&roots, Path::new("/b/d.rs")));
+    assert!(!includes(&roots, Path::new("/a/src/main.txt")));
+    assert!(!includes(&roots, Path::new("/a/target/debug/build.rs")));
+    assert!(!includes(&roots, Path::new("/a/.git/x.rs")));
+  }
+}
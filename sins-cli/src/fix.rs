@@ -0,0 +1,127 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use rayon::prelude::*;
+use sins_lib::fix::FixOutcome;
+use sins_lib::ParseError;
+
+use crate::config::ResolvedConfig;
+
+#[derive(clap::Args)]
+pub struct FixArgs {
+  /// Files or directories to fix.
+  #[arg(default_value = ".")]
+  pub paths: Vec<PathBuf>,
+  /// Fix files even if they have uncommitted changes.
+  #[arg(long)]
+  pub allow_dirty: bool,
+  /// Print a unified diff of the fixes instead of writing them.
+  #[arg(long)]
+  pub diff: bool,
+  /// Maximum number of lint and fix rounds per file.
+  #[arg(long, default_value_t = sins_lib::fix::MAX_ITERATIONS)]
+  pub max_iterations: usize,
+}
+
+pub fn run(args: &FixArgs) -> Result<ExitCode, String> {
+  if !args.allow_dirty && !args.diff {
+    check_clean(&args.paths)?;
+  }
+
+  let files = crate::files::collect(&args.paths).map_err(|e| e.to_string())?;
+  let mut configs = crate::config::Configs::default();
+  let configs = files
+    .iter()
+    .map(|path| configs.resolve(path))
+    .collect::<Result<Vec<_>, _>>()?;
+  let results: Vec<_> = files
+    .par_iter()
+    .zip(&configs)
+    .map(|(path, config)| fix_file(path, config, args))
+    .collect();
+
+  let mut failed = false;
+  for (path, result) in files.iter().zip(results) {
+    match result? {
+      Ok((outcome, patch)) => {
+        if let Some(patch) = patch {
+          print!("{}", patch);
+        } else if outcome.applied > 0 {
+          eprintln!("fixed {} ({} applied)", path.display(), outcome.applied);
+        }
+        if !args.diff {
+          failed |= !outcome.remaining.is_empty();
+          crate::check::print_diagnostics(path, &outcome.remaining);
+        }
+      }
+      Err(err) => {
+        failed = true;
+        crate::check::print_parse_error(path, &err);
+      }
+    }
+  }
+  Ok(if failed {
+    ExitCode::FAILURE
+  } else {
+    ExitCode::SUCCESS
+  })
+}
+
+/// Refuses to modify `paths` if they have uncommitted changes, which the
+/// modification would be mixed up with.
+pub fn check_clean(paths: &[PathBuf]) -> Result<(), String> {
+  match crate::git::dirty_files(paths) {
+    Some(dirty) if !dirty.is_empty() => Err(format!(
+      "the working tree has uncommitted changes, commit or stash them or \
+       pass --allow-dirty:\n  {}",
+      dirty.join("\n  ")
+    )),
+    _ => Ok(()),
+  }
+}
+
+/// Fixes a single file, writing it back unless `--diff` was given (in which
+/// case the diff is returned instead).
+fn fix_file(
+  path: &Path,
+  config: &ResolvedConfig,
+  args: &FixArgs,
+) -> Result<Result<(FixOutcome, Option<String>), ParseError>, String> {
+  let source = crate::check::read(path)?;
+  let outcome = match sins_lib::fix::fix(&source, args.max_iterations, config) {
+    Ok(outcome) => outcome,
+    Err(err) => return Ok(Err(err)),
+  };
+  if outcome.source == source {
+    return Ok(Ok((outcome, None)));
+  }
+  if args.diff {
+    let patch = diff(&path.display().to_string(), &source, &outcome.source);
+    return Ok(Ok((outcome, Some(patch))));
+  }
+  std::fs::write(path, &outcome.source)
+    .map_err(|e| format!("{}: {}", path.display(), e))?;
+  Ok(Ok((outcome, None)))
+}
+
+pub fn diff(path: &str, old: &str, new: &str) -> String {
+  similar::TextDiff::from_lines(old, new)
+    .unified_diff()
+    .header(&format!("a/{}", path), &format!("b/{}", path))
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_diff() {
+    let old = "fn main() {\n  a.foo();\n}\n";
+    let new = "fn main() {\n  a.bar();\n}\n";
+    assert_eq!(
+      diff("src/main.rs", old, new),
+      "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1,3 +1,3 @@\n fn main() {\n-  a.foo();\n+  a.bar();\n }\n"
+    );
+  }
+}
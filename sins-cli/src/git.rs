@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Returns the files under `paths` with uncommitted changes (including
+/// untracked files), or `None` if they aren't inside a git work tree.
+pub fn dirty_files(paths: &[PathBuf]) -> Option<Vec<String>> {
+  let (paths, dir) = work_dir(paths)?;
+  let output = Command::new("git")
+    .arg("-C")
+    .arg(&dir)
+    .args(["status", "--porcelain", "--"])
+    .args(&paths)
+    .output()
+    .ok()?;
+  if !output.status.success() {
+    return None;
+  }
+  Some(
+    String::from_utf8_lossy(&output.stdout)
+      .lines()
+      .map(|line| line.get(3..).unwrap_or(line).to_owned())
+      .collect(),
+  )
+}
+
+/// Lines added or changed in each file since some base revision, as read from
+/// the local repository.
+#[derive(Debug, Default)]
+pub struct Changes {
+  /// 1-indexed half-open line ranges, keyed by canonical path.
+  files: HashMap<PathBuf, Vec<Range<usize>>>,
+}
+
+impl Changes {
+  pub fn contains_file(&self, path: &Path) -> bool {
+    self.lines(path).is_some()
+  }
+
+  /// Whether any line from `start` to `end` (inclusive) of `path` changed.
+  pub fn overlaps(&self, path: &Path, start: usize, end: usize) -> bool {
+    self.lines(path).is_some_and(|ranges| {
+      ranges
+        .iter()
+        .any(|range| range.start <= end && start < range.end)
+    })
+  }
+
+  fn lines(&self, path: &Path) -> Option<&Vec<Range<usize>>> {
+    self.files.get(&path.canonicalize().ok()?)
+  }
+}
+
+/// Reads the changes to files under `paths` between `base` and the working
+/// tree, treating untracked files as entirely added.
+pub fn changes(paths: &[PathBuf], base: &str) -> Result<Changes, String> {
+  let (paths, dir) =
+    work_dir(paths).ok_or("--diff-base requires existing paths")?;
+  let git = |args: &[&str], paths: &[PathBuf]| {
+    let output = Command::new("git")
+      .arg("-C")
+      .arg(&dir)
+      .args(["-c", "core.quotePath=false"])
+      .args(args)
+      .arg("--")
+      .args(paths)
+      .output()
+      .map_err(|e| format!("git: {}", e))?;
+    if !output.status.success() {
+      return Err(format!(
+        "git {}: {}",
+        args[0],
+        String::from_utf8_lossy(&output.stderr).trim()
+      ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+  };
+
+  let toplevel = git(&["rev-parse", "--show-toplevel"], &[])?;
+  let toplevel = Path::new(toplevel.lines().next().unwrap_or_default())
+    .canonicalize()
+    .map_err(|e| e.to_string())?;
+  let diff = git(
+    &[
+      "diff",
+      "--no-color",
+      "--no-ext-diff",
+      "--no-prefix",
+      "--unified=0",
+      base,
+    ],
+    &paths,
+  )?;
+  let mut files = parse_diff(&diff)
+    .into_iter()
+    .map(|(path, ranges)| (toplevel.join(path), ranges))
+    .collect::<HashMap<_, _>>();
+  let untracked = git(
+    &[
+      "ls-files",
+      "--others",
+      "--exclude-standard",
+      "--full-name",
+      "-z",
+    ],
+    &paths,
+  )?;
+  for path in untracked.split('\0').filter(|path| !path.is_empty()) {
+    files.insert(
+      toplevel.join(path),
+      std::iter::once(1..usize::MAX).collect(),
+    );
+  }
+  Ok(Changes { files })
+}
+
+/// Parses the new-side line ranges of each file's hunks out of a `git diff
+/// --no-prefix --unified=0`. Pure deletions add no lines, so they're skipped.
+fn parse_diff(diff: &str) -> HashMap<String, Vec<Range<usize>>> {
+  let mut files: HashMap<String, Vec<Range<usize>>> = HashMap::new();
+  let mut file = None;
+  let mut lines = diff.lines();
+  while let Some(line) = lines.next() {
+    if let Some(path) = line.strip_prefix("+++ ") {
+      file = (path != "/dev/null").then(|| path.to_owned());
+    } else if let Some(header) = line.strip_prefix("@@ ") {
+      let Some((old_count, new_start, new_count)) = parse_hunk_header(header)
+      else {
+        continue;
+      };
+      // Skip the hunk's body, whose lines could otherwise be mistaken for
+      // headers.
+      let mut remaining = old_count + new_count;
+      while remaining > 0 {
+        match lines.next() {
+          Some(line) if line.starts_with('\\') => {}
+          Some(_) => remaining -= 1,
+          None => break,
+        }
+      }
+      if let (Some(file), true) = (&file, new_count > 0) {
+        files
+          .entry(file.clone())
+          .or_default()
+          .push(new_start..new_start + new_count);
+      }
+    }
+  }
+  files
+}
+
+/// Parses `-a[,b] +c[,d] @@` into `(b, c, d)`.
+fn parse_hunk_header(header: &str) -> Option<(usize, usize, usize)> {
+  let range = |range: &str| -> Option<(usize, usize)> {
+    match range.split_once(',') {
+      Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+      None => Some((range.parse().ok()?, 1)),
+    }
+  };
+  let mut parts = header.split(' ');
+  let (_, old_count) = range(parts.next()?.strip_prefix('-')?)?;
+  let (new_start, new_count) = range(parts.next()?.strip_prefix('+')?)?;
+  Some((old_count, new_start, new_count))
+}
+
+/// Canonicalizes `paths` and picks the directory to run git in.
+fn work_dir(paths: &[PathBuf]) -> Option<(Vec<PathBuf>, PathBuf)> {
+  let paths = paths
+    .iter()
+    .map(|path| path.canonicalize())
+    .collect::<Result<Vec<_>, _>>()
+    .ok()?;
+  let first = paths.first()?;
+  let dir = if first.is_dir() {
+    first.clone()
+  } else {
+    first.parent()?.to_owned()
+  };
+  Some((paths, dir))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_diff() {
+    let diff = "\
+diff --git src/a.rs src/a.rs
+index 1111111..2222222 100644
+--- src/a.rs
++++ src/a.rs
+@@ -3 +3 @@ fn main() {
+-  a();
++  b();
+@@ -10,2 +9,0 @@ fn foo() {
+-  c();
+-  d();
+@@ -20,0 +19,3 @@ fn bar() {
++++ x
++  e();
++  f();
+diff --git src/b.rs src/b.rs
+deleted file mode 100644
+--- src/b.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-fn b() {}
+";
+    let files = parse_diff(diff);
+    assert_eq!(files.len(), 1);
+    assert_eq!(files["src/a.rs"], [3..4, 19..22]);
+  }
+}
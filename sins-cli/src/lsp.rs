@@ -0,0 +1,627 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use lsp_server::{Connection, Message, Notification, Request, RequestId};
+use lsp_types::notification::{
+  DidChangeTextDocument, DidChangeWatchedFiles, DidCloseTextDocument,
+  DidOpenTextDocument, DidSaveTextDocument, Notification as _,
+  PublishDiagnostics, ShowMessage,
+};
+use lsp_types::request::{CodeActionRequest, RegisterCapability, Request as _};
+use lsp_types::{
+  CodeAction, CodeActionKind, CodeActionOrCommand,
+  CodeActionProviderCapability, DiagnosticSeverity,
+  DidChangeWatchedFilesRegistrationOptions, FileSystemWatcher, GlobPattern,
+  InitializeParams, MessageType, NumberOrString, PublishDiagnosticsParams,
+  Range, Registration, RegistrationParams, ServerCapabilities,
+  ShowMessageParams, TextDocumentSyncCapability, TextDocumentSyncKind,
+  TextEdit, Url, WorkspaceEdit,
+};
+use sins_lib::source::SourceFile;
+use sins_lib::{Applicability, Diagnostic, Position};
+
+use crate::config::Configs;
+
+/// Runs a language server over stdin and stdout until the client exits.
+pub fn run() -> Result<ExitCode, String> {
+  let (connection, io_threads) = Connection::stdio();
+  serve(&connection)?;
+  drop(connection);
+  io_threads.join().map_err(|e| e.to_string())?;
+  Ok(ExitCode::SUCCESS)
+}
+
+pub fn serve(connection: &Connection) -> Result<(), String> {
+  let capabilities = ServerCapabilities {
+    text_document_sync: Some(TextDocumentSyncCapability::Kind(
+      TextDocumentSyncKind::FULL,
+    )),
+    code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+    ..ServerCapabilities::default()
+  };
+  let params = connection
+    .initialize(serde_json::to_value(capabilities).unwrap())
+    .map_err(|e| e.to_string())?;
+  let params: InitializeParams =
+    serde_json::from_value(params).map_err(|e| e.to_string())?;
+
+  let mut server = Server {
+    connection,
+    documents: HashMap::new(),
+    configs: Configs::default(),
+  };
+  let watch_dynamically = params
+    .capabilities
+    .workspace
+    .and_then(|workspace| workspace.did_change_watched_files)
+    .and_then(|watched_files| watched_files.dynamic_registration)
+    .unwrap_or(false);
+  if watch_dynamically {
+    server.watch_configs();
+  }
+
+  for message in &connection.receiver {
+    match message {
+      Message::Request(request) => {
+        if connection
+          .handle_shutdown(&request)
+          .map_err(|e| e.to_string())?
+        {
+          return Ok(());
+        }
+        server.handle_request(request);
+      }
+      Message::Notification(notification) => {
+        server.handle_notification(notification)
+      }
+      Message::Response(_) => {}
+    }
+  }
+  Ok(())
+}
+
+struct Document {
+  source: SourceFile,
+  version: i32,
+  diagnostics: Vec<Diagnostic>,
+}
+
+struct Server<'a> {
+  connection: &'a Connection,
+  documents: HashMap<Url, Document>,
+  configs: Configs,
+}
+
+impl<'a> Server<'a> {
+  fn handle_request(&mut self, request: Request) {
+    let response = match request.extract(CodeActionRequest::METHOD) {
+      Ok((id, params)) => {
+        let params: lsp_types::CodeActionParams = params;
+        let uri = params.text_document.uri;
+        let actions = match self.documents.get(&uri) {
+          Some(document) => code_actions(&uri, document, params.range),
+          None => vec![],
+        };
+        lsp_server::Response::new_ok(id, actions)
+      }
+      Err(lsp_server::ExtractError::MethodMismatch(request)) => {
+        lsp_server::Response::new_err(
+          request.id,
+          lsp_server::ErrorCode::MethodNotFound as i32,
+          format!("unsupported request: {}", request.method),
+        )
+      }
+      Err(lsp_server::ExtractError::JsonError { method, error }) => {
+        self.show_message(
+          MessageType::ERROR,
+          format!("invalid {} request: {}", method, error),
+        );
+        return;
+      }
+    };
+    self.send(Message::Response(response));
+  }
+
+  fn handle_notification(&mut self, notification: Notification) {
+    match notification.method.as_str() {
+      DidOpenTextDocument::METHOD => {
+        if let Some(params) = extract::<DidOpenTextDocument>(notification) {
+          let document = params.text_document;
+          self.open(document.uri, document.text, document.version);
+        }
+      }
+      DidChangeTextDocument::METHOD => {
+        if let Some(params) = extract::<DidChangeTextDocument>(notification) {
+          // Full sync, so the last change holds the whole document.
+          if let Some(change) = params.content_changes.into_iter().last() {
+            let document = params.text_document;
+            self.open(document.uri, change.text, document.version);
+          }
+        }
+      }
+      DidCloseTextDocument::METHOD => {
+        if let Some(params) = extract::<DidCloseTextDocument>(notification) {
+          let uri = params.text_document.uri;
+          self.documents.remove(&uri);
+          self.publish(uri, vec![], None);
+        }
+      }
+      DidSaveTextDocument::METHOD => {
+        if let Some(params) = extract::<DidSaveTextDocument>(notification) {
+          if is_config(&params.text_document.uri) {
+            self.reload_configs();
+          }
+        }
+      }
+      DidChangeWatchedFiles::METHOD => {
+        if let Some(params) = extract::<DidChangeWatchedFiles>(notification) {
+          if params.changes.iter().any(|change| is_config(&change.uri)) {
+            self.reload_configs();
+          }
+        }
+      }
+      _ => {}
+    }
+  }
+
+  fn open(&mut self, uri: Url, text: String, version: i32) {
+    self.documents.insert(
+      uri.clone(),
+      Document {
+        source: SourceFile::new(text),
+        version,
+        diagnostics: vec![],
+      },
+    );
+    self.lint(uri);
+  }
+
+  fn lint(&mut self, uri: Url) {
+    let path = uri
+      .to_file_path()
+      .unwrap_or_else(|_| PathBuf::from("untitled.rs"));
+    let config = match self.configs.resolve(&path) {
+      Ok(config) => config,
+      Err(err) => {
+        self.show_message(MessageType::ERROR, err);
+        return;
+      }
+    };
+    let document = match self.documents.get_mut(&uri) {
+      Some(document) => document,
+      None => return,
+    };
+
+    let result = sins_lib::lint(document.source.text(), &*config);
+    // Spans are only needed while linting, and a long running server would
+    // otherwise keep every version of every document it has parsed around.
+    proc_macro2::extra::invalidate_current_thread_spans();
+
+    let diagnostics = match result {
+      Ok(diagnostics) => {
+        document.diagnostics = diagnostics;
+        document
+          .diagnostics
+          .iter()
+          .map(|d| to_lsp_diagnostic(&document.source, d))
+          .collect()
+      }
+      Err(err) => {
+        document.diagnostics = vec![];
+        let start = to_lsp_position(&document.source, err.start);
+        vec![lsp_types::Diagnostic {
+          range: Range { start, end: start },
+          severity: Some(DiagnosticSeverity::ERROR),
+          source: Some("sins".into()),
+          message: err.message,
+          ..lsp_types::Diagnostic::default()
+        }]
+      }
+    };
+    let version = document.version;
+    self.publish(uri, diagnostics, Some(version));
+  }
+
+  fn reload_configs(&mut self) {
+    self.configs = Configs::default();
+    let uris: Vec<_> = self.documents.keys().cloned().collect();
+    for uri in uris {
+      self.lint(uri);
+    }
+  }
+
+  /// Asks the client to notify us of changes to any `sins.toml`.
+  fn watch_configs(&self) {
+    let options = DidChangeWatchedFilesRegistrationOptions {
+      watchers: vec![FileSystemWatcher {
+        glob_pattern: GlobPattern::String(format!(
+          "**/{}",
+          sins_lib::config::FILE_NAME
+        )),
+        kind: None,
+      }],
+    };
+    let params = RegistrationParams {
+      registrations: vec![Registration {
+        id: "sins/watch-configs".into(),
+        method: DidChangeWatchedFiles::METHOD.into(),
+        register_options: Some(serde_json::to_value(options).unwrap()),
+      }],
+    };
+    self.send(Message::Request(Request::new(
+      RequestId::from("sins/watch-configs".to_owned()),
+      RegisterCapability::METHOD.into(),
+      params,
+    )));
+  }
+
+  fn publish(
+    &self,
+    uri: Url,
+    diagnostics: Vec<lsp_types::Diagnostic>,
+    version: Option<i32>,
+  ) {
+    let params = PublishDiagnosticsParams {
+      uri,
+      diagnostics,
+      version,
+    };
+    self.send(Message::Notification(Notification::new(
+      PublishDiagnostics::METHOD.into(),
+      params,
+    )));
+  }
+
+  fn show_message(&self, typ: MessageType, message: String) {
+    self.send(Message::Notification(Notification::new(
+      ShowMessage::METHOD.into(),
+      ShowMessageParams { typ, message },
+    )));
+  }
+
+  fn send(&self, message: Message) {
+    // Only fails once the client has gone away, at which point the receiver
+    // loop ends too.
+    let _ = self.connection.sender.send(message);
+  }
+}
+
+fn extract<N: lsp_types::notification::Notification>(
+  notification: Notification,
+) -> Option<N::Params> {
+  notification.extract(N::METHOD).ok()
+}
+
+fn is_config(uri: &Url) -> bool {
+  uri.path().rsplit('/').next() == Some(sins_lib::config::FILE_NAME)
+}
+
+/// Converts a `proc_macro2` position (1-indexed lines, columns in chars) into
+/// an LSP one (0-indexed lines, columns in UTF-16 code units).
+fn to_lsp_position(
+  source: &SourceFile,
+  position: Position,
+) -> lsp_types::Position {
+  let line_start = source.offset(Position {
+    line: position.line,
+    column: 0,
+  });
+  let character = match (line_start, source.offset(position)) {
+    (Some(start), Some(end)) => {
+      source.text()[start..end].encode_utf16().count()
+    }
+    _ => position.column,
+  };
+  lsp_types::Position {
+    line: position.line.saturating_sub(1) as u32,
+    character: character as u32,
+  }
+}
+
+fn to_lsp_range(source: &SourceFile, start: Position, end: Position) -> Range {
+  Range {
+    start: to_lsp_position(source, start),
+    end: to_lsp_position(source, end),
+  }
+}
+
+fn to_lsp_diagnostic(
+  source: &SourceFile,
+  diagnostic: &Diagnostic,
+) -> lsp_types::Diagnostic {
+  lsp_types::Diagnostic {
+    range: to_lsp_range(source, diagnostic.start, diagnostic.end),
+    severity: Some(DiagnosticSeverity::WARNING),
+    code: Some(NumberOrString::String(diagnostic.rule.clone())),
+    source: Some("sins".into()),
+    message: diagnostic.message.clone(),
+    ..lsp_types::Diagnostic::default()
+  }
+}
+
+/// Quick fixes for the suggestions of diagnostics overlapping `range`.
+fn code_actions(
+  uri: &Url,
+  document: &Document,
+  range: Range,
+) -> Vec<CodeActionOrCommand> {
+  let source = &document.source;
+  document
+    .diagnostics
+    .iter()
+    .filter_map(|diagnostic| {
+      let suggestion = diagnostic.suggestion.as_ref()?;
+      let lsp_diagnostic = to_lsp_diagnostic(source, diagnostic);
+      let overlaps = lsp_diagnostic.range.start <= range.end
+        && range.start <= lsp_diagnostic.range.end;
+      if !overlaps {
+        return None;
+      }
+      let edits = suggestion
+        .edits
+        .iter()
+        .map(|edit| TextEdit {
+          range: to_lsp_range(source, edit.start, edit.end),
+          new_text: edit.replacement.clone(),
+        })
+        .collect();
+      Some(CodeActionOrCommand::CodeAction(CodeAction {
+        title: suggestion.message.clone(),
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics: Some(vec![lsp_diagnostic]),
+        edit: Some(WorkspaceEdit {
+          changes: Some(HashMap::from([(uri.clone(), edits)])),
+          ..WorkspaceEdit::default()
+        }),
+        is_preferred: Some(
+          suggestion.applicability == Applicability::MachineApplicable,
+        ),
+        ..CodeAction::default()
+      }))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use sins_lib::{Edit, Suggestion};
+
+  fn position(line: usize, column: usize) -> Position {
+    Position { line, column }
+  }
+
+  /// Drives `serve` from a scripted client over an in-memory connection.
+  struct Client {
+    connection: Connection,
+    server: Option<std::thread::JoinHandle<Result<(), String>>>,
+    next_id: i32,
+  }
+
+  impl Client {
+    fn start(capabilities: serde_json::Value) -> Self {
+      let (connection, server_connection) = Connection::memory();
+      let server = std::thread::spawn(move || serve(&server_connection));
+      let mut client = Self {
+        connection,
+        server: Some(server),
+        next_id: 0,
+      };
+      client.request(
+        "initialize",
+        serde_json::json!({ "capabilities": capabilities }),
+      );
+      client.notify("initialized", serde_json::json!({}));
+      client
+    }
+
+    fn request(
+      &mut self,
+      method: &str,
+      params: serde_json::Value,
+    ) -> serde_json::Value {
+      self.next_id += 1;
+      let id = RequestId::from(self.next_id);
+      self
+        .connection
+        .sender
+        .send(Message::Request(Request::new(
+          id.clone(),
+          method.into(),
+          params,
+        )))
+        .unwrap();
+      loop {
+        match self.receive() {
+          Message::Response(response) if response.id == id => {
+            return response.result.unwrap_or_default()
+          }
+          _ => {}
+        }
+      }
+    }
+
+    fn notify(&self, method: &str, params: serde_json::Value) {
+      self
+        .connection
+        .sender
+        .send(Message::Notification(Notification::new(
+          method.into(),
+          params,
+        )))
+        .unwrap();
+    }
+
+    fn receive(&self) -> Message {
+      self
+        .connection
+        .receiver
+        .recv_timeout(std::time::Duration::from_secs(10))
+        .unwrap()
+    }
+
+    fn expect_notification(&self, method: &str) -> serde_json::Value {
+      loop {
+        match self.receive() {
+          Message::Notification(n) if n.method == method => return n.params,
+          _ => {}
+        }
+      }
+    }
+
+    fn shutdown(mut self) {
+      self.request("shutdown", serde_json::Value::Null);
+      self.notify("exit", serde_json::Value::Null);
+      self.server.take().unwrap().join().unwrap().unwrap();
+    }
+  }
+
+  #[test]
+  fn test_scripted_session() {
+    let dir = std::env::temp_dir().join("sins-lsp-test-scripted-session");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let uri = Url::from_file_path(dir.join("main.rs")).unwrap();
+    let config_uri = Url::from_file_path(dir.join("sins.toml")).unwrap();
+
+    let client = Client::start(serde_json::json!({
+      "workspace": { "didChangeWatchedFiles": { "dynamicRegistration": true } }
+    }));
+    match client.receive() {
+      Message::Request(request) => {
+        assert_eq!(request.method, RegisterCapability::METHOD)
+      }
+      message => panic!("expected a registration, got {:?}", message),
+    }
+
+    client.notify(
+      "textDocument/didOpen",
+      serde_json::json!({ "textDocument": {
+        "uri": uri, "languageId": "rust", "version": 1, "text": "fn main() {\n  let = ;\n}\n",
+      }}),
+    );
+    let published = client.expect_notification(PublishDiagnostics::METHOD);
+    assert_eq!(published["version"], 1);
+    assert_eq!(published["diagnostics"][0]["severity"], 1);
+    assert_eq!(published["diagnostics"][0]["range"]["start"]["line"], 1);
+
+    client.notify(
+      "textDocument/didChange",
+      serde_json::json!({
+        "textDocument": { "uri": uri, "version": 2 },
+        "contentChanges": [{ "text": "fn main() {}\n" }],
+      }),
+    );
+    let published = client.expect_notification(PublishDiagnostics::METHOD);
+    assert_eq!(published["version"], 2);
+    assert_eq!(published["diagnostics"], serde_json::json!([]));
+
+    std::fs::write(dir.join("sins.toml"), "[rules]\nunknown = true\n").unwrap();
+    client.notify(
+      "workspace/didChangeWatchedFiles",
+      serde_json::json!({ "changes": [{ "uri": config_uri, "type": 1 }] }),
+    );
+    let message = client.expect_notification(ShowMessage::METHOD);
+    assert!(message["message"].as_str().unwrap().contains("sins.toml"));
+
+    std::fs::write(dir.join("sins.toml"), "").unwrap();
+    client.notify(
+      "textDocument/didSave",
+      serde_json::json!({ "textDocument": { "uri": config_uri } }),
+    );
+    let published = client.expect_notification(PublishDiagnostics::METHOD);
+    assert_eq!(published["version"], 2);
+
+    client.notify(
+      "textDocument/didClose",
+      serde_json::json!({ "textDocument": { "uri": uri } }),
+    );
+    let published = client.expect_notification(PublishDiagnostics::METHOD);
+    assert_eq!(published["version"], serde_json::Value::Null);
+
+    client.shutdown();
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_to_lsp_position_counts_utf16() {
+    let source = SourceFile::new("let a = \"😀\"; b\n");
+    assert_eq!(
+      to_lsp_position(&source, position(1, 13)),
+      lsp_types::Position {
+        line: 0,
+        character: 14
+      }
+    );
+  }
+
+  #[test]
+  fn test_code_actions() {
+    let uri = Url::parse("file:///main.rs").unwrap();
+    let diagnostic = |line, suggestion| Diagnostic {
+      rule: "rule".into(),
+      message: "message".into(),
+      start: position(line, 2),
+      end: position(line, 5),
+      suggestion,
+    };
+    let document = Document {
+      source: SourceFile::new("a\nb\n  foo;\n  bar;\n"),
+      version: 1,
+      diagnostics: vec![
+        diagnostic(3, None),
+        diagnostic(
+          3,
+          Some(Suggestion {
+            message: "use `baz`".into(),
+            applicability: Applicability::MachineApplicable,
+            edits: vec![Edit {
+              start: position(3, 2),
+              end: position(3, 5),
+              replacement: "baz".into(),
+            }],
+          }),
+        ),
+        diagnostic(
+          4,
+          Some(Suggestion {
+            message: "use `qux`".into(),
+            applicability: Applicability::MaybeIncorrect,
+            edits: vec![],
+          }),
+        ),
+      ],
+    };
+    let at = |line, character| lsp_types::Position { line, character };
+
+    let actions = code_actions(
+      &uri,
+      &document,
+      Range {
+        start: at(2, 3),
+        end: at(2, 3),
+      },
+    );
+    assert_eq!(actions.len(), 1);
+    let action = match &actions[0] {
+      CodeActionOrCommand::CodeAction(action) => action,
+      command => panic!("expected a code action, got {:?}", command),
+    };
+    assert_eq!(action.title, "use `baz`");
+    assert_eq!(action.is_preferred, Some(true));
+    let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+    assert_eq!(edits[0].new_text, "baz");
+    assert_eq!(edits[0].range.start.character, 2);
+
+    let actions = code_actions(
+      &uri,
+      &document,
+      Range {
+        start: at(0, 0),
+        end: at(4, 0),
+      },
+    );
+    assert_eq!(actions.len(), 2);
+  }
+}
@@ -1,5 +1,66 @@
-fn main() {
-  println!("Hello, world!");
+mod baseline;
+mod cache;
+mod check;
+mod config;
+mod files;
+mod fix;
+mod git;
+mod lsp;
+mod plugin;
+mod rewrite;
+mod search;
+mod wasm;
+mod watch;
+
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(name = "sins", version, about = "A rust linter based off of syn")]
+struct Cli {
+  #[command(subcommand)]
+  command: Command,
+  /// Number of files to lint in parallel (defaults to the number of CPUs).
+  #[arg(short, long, global = true)]
+  jobs: Option<usize>,
 }
 
+#[derive(Subcommand)]
+enum Command {
+  /// Lint files and print any diagnostics.
+  Check(check::CheckArgs),
+  /// Apply machine-applicable suggestions in place.
+  Fix(fix::FixArgs),
+  /// Run a language server over stdio.
+  Lsp,
+  /// Print the code matching a structural pattern.
+  Search(search::SearchArgs),
+  /// Replace the code matching a structural pattern.
+  Rewrite(rewrite::RewriteArgs),
+  /// Lint files, then re-lint them as they change.
+  Watch(watch::WatchArgs),
+}
 
+fn main() -> std::process::ExitCode {
+  let cli = Cli::parse();
+  if let Some(jobs) = cli.jobs {
+    rayon::ThreadPoolBuilder::new()
+      .num_threads(jobs)
+      .build_global()
+      .expect("the global thread pool is only configured once");
+  }
+  let result = match cli.command {
+    Command::Check(args) => check::run(&args),
+    Command::Fix(args) => fix::run(&args),
+    Command::Lsp => lsp::run(),
+    Command::Search(args) => search::run(&args),
+    Command::Rewrite(args) => rewrite::run(&args),
+    Command::Watch(args) => watch::run(&args),
+  };
+  match result {
+    Ok(code) => code,
+    Err(err) => {
+      eprintln!("error: {}", err);
+      std::process::ExitCode::from(2)
+    }
+  }
+}
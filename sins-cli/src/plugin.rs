@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use sins_lib::{Diagnostic, NodeKind, Position, Rule, RuleContext};
+use sins_plugin::{Buffer, Response, ABI_VERSION};
+
+use crate::wasm::WasmPlugin;
+
+type AbiVersionFn = extern "C" fn() -> u32;
+type LintFn = unsafe extern "C" fn(*const u8, usize) -> Buffer;
+type FreeFn = unsafe extern "C" fn(Buffer);
+/// Loaded plugins by canonical path and hash of their contents.
+type Loaded = HashMap<(PathBuf, blake3::Hash), Arc<Plugin>>;
+
+/// A loaded plugin, see `sins-plugin`.
+pub struct Plugin {
+  pub path: PathBuf,
+  /// Hash of the library, so that rebuilding a plugin invalidates the cache.
+  pub hash: blake3::Hash,
+  backend: Backend,
+}
+
+enum Backend {
+  Native(Native),
+  Wasm(WasmPlugin),
+}
+
+/// A plugin `cdylib`, which runs with the same privileges as sins.
+struct Native {
+  lint: LintFn,
+  free: FreeFn,
+  // Keeps `lint` and `free` valid.
+  _library: libloading::Library,
+}
+
+impl std::fmt::Debug for Plugin {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Plugin").field("path", &self.path).finish()
+  }
+}
+
+impl Plugin {
+  /// Loads the plugin at `path`, or returns the already loaded one if the
+  /// file hasn't changed since. Plugins are never unloaded, since rules from
+  /// them may still be running, so a rebuilt plugin is loaded alongside the
+  /// old one.
+  pub fn load(path: &Path) -> Result<Arc<Self>, String> {
+    static LOADED: Mutex<Option<Loaded>> = Mutex::new(None);
+    let error =
+      |e: &dyn std::fmt::Display| format!("{}: {}", path.display(), e);
+    let path = path.canonicalize().map_err(|e| error(&e))?;
+    let bytes = std::fs::read(&path).map_err(|e| error(&e))?;
+    let key = (path, blake3::hash(&bytes));
+    let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+    let loaded = loaded.get_or_insert_with(HashMap::new);
+    if let Some(plugin) = loaded.get(&key) {
+      return Ok(plugin.clone());
+    }
+    let plugin =
+      Arc::new(Self::open(&key.0, &bytes, key.1).map_err(|e| error(&e))?);
+    loaded.insert(key, plugin.clone());
+    Ok(plugin)
+  }
+
+  fn open(
+    path: &Path,
+    bytes: &[u8],
+    hash: blake3::Hash,
+  ) -> Result<Self, String> {
+    let backend = if path.extension().is_some_and(|ext| ext == "wasm") {
+      Backend::Wasm(WasmPlugin::new(bytes)?)
+    } else {
+      Backend::Native(Native::open(path)?)
+    };
+    Ok(Self {
+      path: path.to_owned(),
+      hash,
+      backend,
+    })
+  }
+
+  pub fn lint(&self, source: &str) -> Response {
+    match &self.backend {
+      Backend::Native(native) => native.lint(source),
+      Backend::Wasm(wasm) => wasm.lint(source),
+    }
+  }
+}
+
+impl Native {
+  fn open(path: &Path) -> Result<Self, String> {
+    // SAFETY: loading runs the library's initializers, which we have to trust
+    // as much as the rest of the plugin the user asked for.
+    let library = unsafe { libloading::Library::new(path) }
+      .map_err(|e| format!("failed to load plugin: {}", e))?;
+    // SAFETY: the symbols' types are those `sins_plugin::register!` exports,
+    // which is checked by the ABI version before using the others.
+    unsafe {
+      let abi_version = *library
+        .get::<AbiVersionFn>(b"sins_plugin_abi_version\0")
+        .map_err(|_| {
+          "not a sins plugin (no `sins_plugin_abi_version`, see \
+           `sins_plugin::register!`)"
+            .to_owned()
+        })?;
+      check_abi_version(abi_version())?;
+      let lint = *library
+        .get::<LintFn>(b"sins_plugin_lint\0")
+        .map_err(|e| e.to_string())?;
+      let free = *library
+        .get::<FreeFn>(b"sins_plugin_free\0")
+        .map_err(|e| e.to_string())?;
+      Ok(Self {
+        lint,
+        free,
+        _library: library,
+      })
+    }
+  }
+
+  fn lint(&self, source: &str) -> Response {
+    // SAFETY: `source` is valid UTF-8 of the given length, and the buffer
+    // comes from this plugin and is freed exactly once.
+    let bytes = unsafe {
+      let buffer = (self.lint)(source.as_ptr(), source.len());
+      let bytes = buffer.as_slice().to_vec();
+      (self.free)(buffer);
+      bytes
+    };
+    serde_json::from_slice(&bytes)
+      .map_err(|e| format!("invalid response from plugin: {}", e))?
+  }
+}
+
+pub fn check_abi_version(abi_version: u32) -> Result<(), String> {
+  if abi_version == ABI_VERSION {
+    return Ok(());
+  }
+  Err(format!(
+    "plugin was built against plugin ABI version {}, but this sins supports \
+     version {}; rebuild it against a matching sins-plugin",
+    abi_version, ABI_VERSION
+  ))
+}
+
+/// Runs a plugin's rules over each file, reporting their diagnostics except
+/// those for rules disabled in `sins.toml`.
+pub struct PluginRule {
+  pub plugin: Arc<Plugin>,
+  pub disable: Arc<[String]>,
+}
+
+impl<'ast> Rule<'ast> for PluginRule {
+  fn name(&self) -> &'static str {
+    "plugin"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::File]
+  }
+
+  fn apply_file(
+    &mut self,
+    context: &RuleContext,
+    _node: &'ast sins_plugin::syn::File,
+  ) {
+    let Some(source) = context.source() else {
+      return;
+    };
+    match self.plugin.lint(source.text()) {
+      Ok(Ok(diagnostics)) => {
+        for diagnostic in diagnostics {
+          if !self.disable.contains(&diagnostic.rule) {
+            context.emit_diagnostic(diagnostic);
+          }
+        }
+      }
+      // The file parsed for us, so a plugin built with another syn may
+      // disagree; there's nothing it could report anyway.
+      Ok(Err(_)) => {}
+      Err(message) => {
+        let start = Position { line: 1, column: 0 };
+        context.emit_diagnostic(Diagnostic {
+          rule: "plugin".to_owned(),
+          message: format!(
+            "plugin {} failed: {}",
+            self.plugin.path.display(),
+            message
+          ),
+          start,
+          end: start,
+          suggestion: None,
+        });
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_load_invalid() {
+    let dir = std::env::temp_dir().join("sins-plugin-test-load-invalid");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("libnot_a_plugin.so");
+    std::fs::write(&path, "fn main() {}\n").unwrap();
+
+    let err = Plugin::load(&path).unwrap_err();
+    assert!(err.contains("failed to load plugin"), "{}", err);
+    assert!(Plugin::load(&dir.join("missing.so")).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_reload_changed() {
+    use crate::wasm::tests::plugin;
+    let dir = std::env::temp_dir().join("sins-plugin-test-reload-changed");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("rules.wasm");
+    std::fs::write(&path, plugin(ABI_VERSION, "")).unwrap();
+
+    let first = Plugin::load(&path).unwrap();
+    assert!(Arc::ptr_eq(&first, &Plugin::load(&path).unwrap()));
+    std::fs::write(&path, plugin(ABI_VERSION, "nop")).unwrap();
+    let rebuilt = Plugin::load(&path).unwrap();
+    assert!(!Arc::ptr_eq(&first, &rebuilt));
+    assert_ne!(first.hash, rebuilt.hash);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+}
@@ -0,0 +1,142 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use rayon::prelude::*;
+use sins_lib::pattern::{Pattern, Rewrite};
+use sins_lib::ParseError;
+
+#[derive(clap::Args)]
+pub struct RewriteArgs {
+  /// Pattern to replace, as for `sins search`.
+  pub pattern: String,
+  /// Replacement, in which `$name` is replaced by what it matched.
+  pub template: String,
+  /// Files or directories to rewrite.
+  #[arg(default_value = ".")]
+  pub paths: Vec<PathBuf>,
+  /// Print a unified diff of the rewrites instead of writing them.
+  #[arg(long)]
+  pub dry_run: bool,
+  /// Rewrite files even if they have uncommitted changes.
+  #[arg(long)]
+  pub allow_dirty: bool,
+}
+
+pub fn run(args: &RewriteArgs) -> Result<ExitCode, String> {
+  if !args.allow_dirty && !args.dry_run {
+    crate::fix::check_clean(&args.paths)?;
+  }
+  Pattern::parse(&args.pattern)?;
+
+  let files = crate::files::collect(&args.paths).map_err(|e| e.to_string())?;
+  let results: Vec<_> = files
+    .par_iter()
+    .map(|path| rewrite_file(path, args))
+    .collect();
+
+  let mut failed = false;
+  for (path, result) in files.iter().zip(results) {
+    match result? {
+      Ok(Rewritten::Unchanged) => {}
+      Ok(Rewritten::Diff(patch)) => print!("{}", patch),
+      Ok(Rewritten::Written(replacements)) => {
+        eprintln!("rewrote {} ({} replaced)", path.display(), replacements)
+      }
+      Ok(Rewritten::Broken(err)) => {
+        failed = true;
+        eprintln!(
+          "[{}:{}:{}] rewrite produces invalid code, leaving the file \
+           unchanged: {}",
+          path.display(),
+          err.start.line,
+          err.start.column + 1,
+          err.message
+        );
+      }
+      Err(err) => {
+        failed = true;
+        crate::check::print_parse_error(path, &err);
+      }
+    }
+  }
+  Ok(if failed {
+    ExitCode::FAILURE
+  } else {
+    ExitCode::SUCCESS
+  })
+}
+
+enum Rewritten {
+  Unchanged,
+  Diff(String),
+  Written(usize),
+  /// The rewritten source doesn't parse, with the error's position in it.
+  Broken(ParseError),
+}
+
+fn rewrite_file(
+  path: &Path,
+  args: &RewriteArgs,
+) -> Result<Result<Rewritten, ParseError>, String> {
+  let source = crate::check::read(path)?;
+  let pattern = Pattern::parse(&args.pattern)?;
+  let Rewrite {
+    source: rewritten,
+    replacements,
+    error,
+  } = match sins_lib::pattern::rewrite(&source, &pattern, &args.template) {
+    Ok(rewrite) => rewrite,
+    Err(err) => return Ok(Err(err)),
+  };
+  if replacements == 0 {
+    return Ok(Ok(Rewritten::Unchanged));
+  }
+  if let Some(err) = error {
+    return Ok(Ok(Rewritten::Broken(err)));
+  }
+  if args.dry_run {
+    let patch =
+      crate::fix::diff(&path.display().to_string(), &source, &rewritten);
+    return Ok(Ok(Rewritten::Diff(patch)));
+  }
+  write_atomic(path, &rewritten)
+    .map_err(|e| format!("{}: {}", path.display(), e))?;
+  Ok(Ok(Rewritten::Written(replacements)))
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it into
+/// place, so that `path` is never left partially written.
+fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+  let mut tmp = path.as_os_str().to_owned();
+  tmp.push(".sins-tmp");
+  let tmp = PathBuf::from(tmp);
+  std::fs::write(&tmp, contents)?;
+  let result = std::fs::metadata(path)
+    .and_then(|metadata| std::fs::set_permissions(&tmp, metadata.permissions()))
+    .and_then(|()| std::fs::rename(&tmp, path));
+  if result.is_err() {
+    let _ = std::fs::remove_file(&tmp);
+  }
+  result
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_write_atomic() {
+    let dir = std::env::temp_dir().join("sins-rewrite-test-write-atomic");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("main.rs");
+    std::fs::write(&path, "fn main() {}\n").unwrap();
+
+    write_atomic(&path, "fn main() { a(); }\n").unwrap();
+    assert_eq!(
+      std::fs::read_to_string(&path).unwrap(),
+      "fn main() { a(); }\n"
+    );
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+  }
+}
@@ -0,0 +1,92 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use rayon::prelude::*;
+use sins_lib::pattern::{Match, Pattern};
+
+#[derive(clap::Args)]
+pub struct SearchArgs {
+  /// Rust expression, statement or item to search for, where `$name` matches
+  /// any expression and `$...name` any number of arguments or statements.
+  pub pattern: String,
+  /// Files or directories to search.
+  #[arg(default_value = ".")]
+  pub paths: Vec<PathBuf>,
+  /// Print matches as a JSON array.
+  #[arg(long)]
+  pub json: bool,
+}
+
+#[derive(serde::Serialize)]
+struct FileMatch<'a> {
+  path: &'a Path,
+  text: String,
+  #[serde(flatten)]
+  inner: Match,
+}
+
+pub fn run(args: &SearchArgs) -> Result<ExitCode, String> {
+  // Checked up front for a single error message, but compiled again on each
+  // thread since patterns can't be shared between them.
+  Pattern::parse(&args.pattern)?;
+
+  let files = crate::files::collect(&args.paths).map_err(|e| e.to_string())?;
+  let results: Vec<_> = files
+    .par_iter()
+    .map(|path| {
+      let source = crate::check::read(path)?;
+      let pattern = Pattern::parse(&args.pattern)?;
+      let matches = sins_lib::pattern::search(&source, &pattern);
+      Ok((source, matches))
+    })
+    .collect::<Vec<Result<_, String>>>();
+
+  let mut found = false;
+  let mut json = vec![];
+  for (path, result) in files.iter().zip(results) {
+    let (source, matches) = result?;
+    let matches: Vec<Match> = match matches {
+      Ok(matches) => matches,
+      Err(err) => {
+        crate::check::print_parse_error(path, &err);
+        continue;
+      }
+    };
+    found |= !matches.is_empty();
+    let source = sins_lib::source::SourceFile::new(source);
+    for m in matches {
+      let text = source
+        .byte_range(m.start, m.end)
+        .map_or_else(String::new, |range| source.text()[range].to_owned());
+      if args.json {
+        json.push(FileMatch {
+          path,
+          text,
+          inner: m,
+        });
+        continue;
+      }
+      let first_line = text.lines().next().unwrap_or_default();
+      println!(
+        "[{}:{}:{}] {}",
+        path.display(),
+        m.start.line,
+        m.start.column + 1,
+        first_line
+      );
+      for (name, text) in &m.bindings {
+        println!("    ${} = {}", name, text);
+      }
+    }
+  }
+  if args.json {
+    let json =
+      serde_json::to_string_pretty(&json).map_err(|e| e.to_string())?;
+    println!("{}", json);
+  }
+  Ok(if found {
+    ExitCode::SUCCESS
+  } else {
+    ExitCode::FAILURE
+  })
+}
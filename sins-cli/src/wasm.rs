@@ -0,0 +1,147 @@
+use sins_plugin::Response;
+use wasmi::{Engine, Instance, Linker, Module, Store, StoreLimits};
+
+/// Instructions a plugin may execute linting one file, well beyond what
+/// parsing and linting needs, so that a runaway plugin fails instead of
+/// hanging.
+const FUEL: u64 = 1_000_000_000;
+
+/// Memory a plugin may grow to linting one file.
+const MEMORY: usize = 256 << 20;
+
+/// A plugin compiled to WebAssembly, see `sins-plugin`. Each file is linted by
+/// a fresh instance with no imports, so plugins can't touch anything but the
+/// source they're given, nor keep state between files.
+pub struct WasmPlugin {
+  engine: Engine,
+  module: Module,
+  fuel: u64,
+}
+
+impl WasmPlugin {
+  /// Compiles `wasm` and checks its ABI version.
+  pub fn new(wasm: &[u8]) -> Result<Self, String> {
+    let mut config = wasmi::Config::default();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, wasm)
+      .map_err(|e| format!("invalid WebAssembly module: {}", e))?;
+    let plugin = Self {
+      engine,
+      module,
+      fuel: FUEL,
+    };
+
+    let (mut store, instance) = plugin.instantiate()?;
+    let abi_version = instance
+      .get_typed_func::<(), u32>(&store, "sins_plugin_abi_version")
+      .map_err(|_| {
+        "not a sins plugin (no `sins_plugin_abi_version`, see \
+         `sins_plugin::register!`)"
+          .to_owned()
+      })?
+      .call(&mut store, ())
+      .map_err(|e| e.to_string())?;
+    crate::plugin::check_abi_version(abi_version)?;
+    Ok(plugin)
+  }
+
+  fn instantiate(&self) -> Result<(Store<StoreLimits>, Instance), String> {
+    let limits = wasmi::StoreLimitsBuilder::new().memory_size(MEMORY).build();
+    let mut store = Store::new(&self.engine, limits);
+    store.limiter(|limits| limits);
+    store.set_fuel(self.fuel).map_err(|e| e.to_string())?;
+    // Nothing is linked, so a module importing anything fails here.
+    let instance = Linker::new(&self.engine)
+      .instantiate(&mut store, &self.module)
+      .and_then(|instance| instance.start(&mut store))
+      .map_err(|e| e.to_string())?;
+    Ok((store, instance))
+  }
+
+  pub fn lint(&self, source: &str) -> Response {
+    let (mut store, instance) = self.instantiate()?;
+    let memory = instance
+      .get_memory(&store, "memory")
+      .ok_or("plugin doesn't export `memory`")?;
+    let alloc = instance
+      .get_typed_func::<u32, u32>(&store, "sins_plugin_alloc")
+      .map_err(|e| e.to_string())?;
+    let lint = instance
+      .get_typed_func::<(u32, u32), u64>(&store, "sins_plugin_lint")
+      .map_err(|e| e.to_string())?;
+
+    let len = u32::try_from(source.len()).map_err(|e| e.to_string())?;
+    let ptr = alloc.call(&mut store, len).map_err(|e| e.to_string())?;
+    memory
+      .write(&mut store, ptr as usize, source.as_bytes())
+      .map_err(|e| e.to_string())?;
+    let response = lint
+      .call(&mut store, (ptr, len))
+      .map_err(|e| e.to_string())?;
+    let start = (response >> 32) as usize;
+    let end = start + (response & 0xffff_ffff) as usize;
+    let bytes = memory
+      .data(&store)
+      .get(start..end)
+      .ok_or("plugin returned a response out of bounds")?;
+    serde_json::from_slice(bytes)
+      .map_err(|e| format!("invalid response from plugin: {}", e))?
+  }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+
+  /// A module implementing the plugin ABI by hand, which reports one fixed
+  /// diagnostic, with `body` spliced into `sins_plugin_lint`.
+  pub(crate) fn plugin(abi_version: u32, body: &str) -> Vec<u8> {
+    let response = r#"{"Ok":{"Ok":[{"rule":"wat","message":"hi","start":{"line":1,"column":0},"end":{"line":1,"column":2},"suggestion":null}]}}"#;
+    wat::parse_str(format!(
+      r#"(module
+        (memory (export "memory") 1)
+        (data (i32.const 0) "{response}")
+        (func (export "sins_plugin_abi_version") (result i32)
+          i32.const {abi_version})
+        (func (export "sins_plugin_alloc") (param i32) (result i32)
+          i32.const 1024)
+        (func (export "sins_plugin_lint") (param i32 i32) (result i64)
+          {body}
+          i64.const {len}))"#,
+      response = response.replace('"', "\\\""),
+      len = response.len(),
+    ))
+    .unwrap()
+  }
+
+  #[test]
+  fn test_lint() {
+    let plugin =
+      WasmPlugin::new(&plugin(sins_plugin::ABI_VERSION, "")).unwrap();
+    let diagnostics = plugin.lint("fn main() {}").unwrap().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].rule, "wat");
+  }
+
+  #[test]
+  fn test_sandbox() {
+    let err = WasmPlugin::new(&plugin(0, "")).err().unwrap();
+    assert!(err.contains("ABI version 0"), "{}", err);
+
+    let mut plugin =
+      WasmPlugin::new(&plugin(sins_plugin::ABI_VERSION, "(loop (br 0))"))
+        .unwrap();
+    plugin.fuel = 1_000_000;
+    let err = plugin.lint("fn main() {}").unwrap_err();
+    assert!(err.contains("fuel"), "{}", err);
+
+    let wasi = wat::parse_str(
+      r#"(module
+        (import "wasi_snapshot_preview1" "fd_write"
+          (func (param i32 i32 i32 i32) (result i32))))"#,
+    )
+    .unwrap();
+    assert!(WasmPlugin::new(&wasi).is_err());
+  }
+}
@@ -0,0 +1,305 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use notify::{EventKind, RecursiveMode, Watcher};
+use rayon::prelude::*;
+use sins_lib::config::FILE_NAME;
+
+use crate::cache::LintResult;
+use crate::config::Configs;
+
+#[derive(clap::Args)]
+pub struct WatchArgs {
+  /// Files or directories to watch.
+  #[arg(default_value = ".")]
+  pub paths: Vec<PathBuf>,
+}
+
+/// How long to keep collecting events after a change before re-linting, so
+/// that e.g. saving several files at once only redraws once.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+pub fn run(args: &WatchArgs) -> Result<ExitCode, String> {
+  let roots = args
+    .paths
+    .iter()
+    .map(|path| {
+      path
+        .canonicalize()
+        .map_err(|e| format!("{}: {}", path.display(), e))
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+  let (sender, receiver) = std::sync::mpsc::channel();
+  let mut watcher =
+    notify::recommended_watcher(sender).map_err(|e| e.to_string())?;
+  for root in &roots {
+    watcher
+      .watch(root, RecursiveMode::Recursive)
+      .map_err(|e| format!("{}: {}", root.display(), e))?;
+  }
+
+  let mut watch = Watch {
+    cwd: std::env::current_dir().map_err(|e| e.to_string())?,
+    files: BTreeMap::new(),
+    configs: Configs::default(),
+    errors: vec![],
+    roots,
+  };
+  let files = crate::files::collect(&watch.roots).map_err(|e| e.to_string())?;
+  watch.lint(files.into_iter().collect());
+  watch.render();
+
+  while let Ok(event) = receiver.recv() {
+    let mut changed = BTreeSet::new();
+    let mut event = Ok(event);
+    loop {
+      match event {
+        Ok(Ok(event)) if !matches!(event.kind, EventKind::Access(_)) => {
+          changed.extend(event.paths)
+        }
+        Ok(Err(err)) => watch.errors.push(err.to_string()),
+        Ok(Ok(_)) => {}
+        Err(_) => break,
+      }
+      event = receiver.recv_timeout(DEBOUNCE);
+    }
+    if watch.update(changed) || !watch.errors.is_empty() {
+      watch.render();
+    }
+  }
+  Ok(ExitCode::SUCCESS)
+}
+
+struct Linted {
+  fingerprint: blake3::Hash,
+  result: LintResult,
+}
+
+struct Watch {
+  cwd: PathBuf,
+  roots: Vec<PathBuf>,
+  configs: Configs,
+  files: BTreeMap<PathBuf, Linted>,
+  /// Errors since the last redraw.
+  errors: Vec<String>,
+}
+
+impl Watch {
+  /// Re-lints the files among `changed` paths, and every file whose
+  /// configuration differs if a `sins.toml` changed. Paths that no longer
+  /// exist stop being watched, along with every file beneath them if they
+  /// were directories, and the files in directories created or moved in
+  /// start being watched. Returns whether any watched file was affected.
+  fn update(&mut self, changed: BTreeSet<PathBuf>) -> bool {
+    let mut stale = BTreeSet::new();
+    let mut removed = false;
+    for path in &changed {
+      if !path.exists() {
+        let watched = self.files.len();
+        self.files.retain(|file, _| !file.starts_with(path));
+        removed |= self.files.len() != watched;
+      } else if path.is_file() && crate::files::includes(&self.roots, path) {
+        stale.insert(path.clone());
+      } else if path.is_dir()
+        && !self.files.keys().any(|file| file.starts_with(path))
+      {
+        // Events for a new directory don't list the files it came with.
+        match crate::files::collect(std::slice::from_ref(path)) {
+          Ok(files) => stale.extend(
+            files
+              .into_iter()
+              .filter(|file| crate::files::includes(&self.roots, file)),
+          ),
+          Err(err) => self.errors.push(format!("{}: {}", path.display(), err)),
+        }
+      }
+    }
+
+    let config_changed = changed
+      .iter()
+      .any(|path| path.file_name() == Some(FILE_NAME.as_ref()));
+    if config_changed {
+      self.configs = Configs::default();
+      for (path, linted) in &self.files {
+        match self.configs.resolve(path) {
+          Ok(config) if config.fingerprint == linted.fingerprint => {}
+          _ => {
+            stale.insert(path.clone());
+          }
+        }
+      }
+    }
+    let affected = removed || config_changed || !stale.is_empty();
+    self.lint(stale);
+    affected
+  }
+
+  fn lint(&mut self, paths: BTreeSet<PathBuf>) {
+    let mut jobs = vec![];
+    for path in paths {
+      match self.configs.resolve(&path) {
+        Ok(config) => jobs.push((path, config)),
+        Err(err) => {
+          self.files.remove(&path);
+          self.errors.push(err);
+        }
+      }
+    }
+
+    let results: Vec<_> = jobs
+      .par_iter()
+      .map(|(path, config)| {
+        let source = crate::check::read(path)?;
+        Ok(sins_lib::lint(&source, &**config))
+      })
+      .collect::<Vec<Result<_, String>>>();
+    // Nothing parsed on the pool outlives linting, so drop the spans every
+    // parse leaves behind instead of accumulating them while watching.
+    rayon::broadcast(|_| proc_macro2::extra::invalidate_current_thread_spans());
+
+    for ((path, config), result) in jobs.into_iter().zip(results) {
+      match result {
+        Ok(result) => {
+          let fingerprint = config.fingerprint;
+          self.files.insert(
+            path,
+            Linted {
+              fingerprint,
+              result,
+            },
+          );
+        }
+        Err(err) => {
+          self.files.remove(&path);
+          self.errors.push(err);
+        }
+      }
+    }
+  }
+
+  /// Clears the terminal and prints every file's diagnostics followed by a
+  /// summary.
+  fn render(&mut self) {
+    print!("\x1b[2J\x1b[H");
+    for err in self.errors.drain(..) {
+      println!("error: {}", err);
+    }
+    let mut unparsed = 0;
+    for (path, linted) in &self.files {
+      let path = path.strip_prefix(&self.cwd).unwrap_or(path);
+      match &linted.result {
+        Ok(diagnostics) => crate::check::print_diagnostics(path, diagnostics),
+        Err(err) => {
+          unparsed += 1;
+          crate::check::print_parse_error(path, err);
+        }
+      }
+    }
+
+    let totals = totals(self.files.values().map(|linted| &linted.result));
+    let problems: usize = totals.iter().map(|(_, count)| count).sum();
+    println!();
+    println!(
+      "{} problems, {} files unparseable, {} files watched",
+      problems,
+      unparsed,
+      self.files.len()
+    );
+    for (rule, count) in totals {
+      println!("  {:>5} {}", count, rule);
+    }
+  }
+}
+
+/// Number of diagnostics reported by each rule, most frequent first.
+fn totals<'a>(
+  results: impl Iterator<Item = &'a LintResult>,
+) -> Vec<(&'a str, usize)> {
+  let mut totals = HashMap::new();
+  for diagnostics in results.filter_map(|result| result.as_ref().ok()) {
+    for diagnostic in diagnostics {
+      *totals.entry(diagnostic.rule.as_str()).or_insert(0) += 1;
+    }
+  }
+  let mut totals: Vec<_> = totals.into_iter().collect();
+  totals.sort_by(|(a, a_count), (b, b_count)| {
+    b_count.cmp(a_count).then_with(|| a.cmp(b))
+  });
+  totals
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use sins_lib::{Diagnostic, ParseError, Position};
+
+  #[test]
+  fn test_remove_dir() {
+    let root = std::env::temp_dir().join("sins-watch-test-remove-dir");
+    let linted = || Linted {
+      fingerprint: blake3::hash(b""),
+      result: Ok(vec![]),
+    };
+    let mut watch = Watch {
+      cwd: root.clone(),
+      roots: vec![root.clone()],
+      configs: Configs::default(),
+      files: ["src/a.rs", "src/b/c.rs", "src.rs", "tests/d.rs"]
+        .into_iter()
+        .map(|file| (root.join(file), linted()))
+        .collect(),
+      errors: vec![],
+    };
+
+    assert!(watch.update(BTreeSet::from([root.join("src")])));
+    let files: Vec<_> = watch.files.keys().cloned().collect();
+    assert_eq!(files, [root.join("src.rs"), root.join("tests/d.rs")]);
+    assert!(!watch.update(BTreeSet::from([root.join("src")])));
+  }
+
+  #[test]
+  fn test_add_dir() {
+    let root = std::env::temp_dir().join("sins-watch-test-add-dir");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("src/b")).unwrap();
+    std::fs::create_dir_all(root.join("src/target")).unwrap();
+    for file in ["src/a.rs", "src/b/c.rs", "src/target/d.rs", "src/e.txt"] {
+      std::fs::write(root.join(file), "fn main() {}\n").unwrap();
+    }
+    let mut watch = Watch {
+      cwd: root.clone(),
+      roots: vec![root.clone()],
+      configs: Configs::default(),
+      files: BTreeMap::new(),
+      errors: vec![],
+    };
+
+    assert!(watch.update(BTreeSet::from([root.join("src")])));
+    let files: Vec<_> = watch.files.keys().cloned().collect();
+    assert_eq!(files, [root.join("src/a.rs"), root.join("src/b/c.rs")]);
+    assert!(watch.errors.is_empty(), "{:?}", watch.errors);
+    std::fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn test_totals() {
+    let diagnostic = |rule: &str| Diagnostic {
+      rule: rule.into(),
+      message: String::new(),
+      start: Position { line: 1, column: 0 },
+      end: Position { line: 1, column: 1 },
+      suggestion: None,
+    };
+    let results: Vec<LintResult> = vec![
+      Ok(vec![diagnostic("b"), diagnostic("c")]),
+      Err(ParseError {
+        start: Position { line: 1, column: 0 },
+        message: String::new(),
+      }),
+      Ok(vec![diagnostic("c"), diagnostic("a")]),
+    ];
+    assert_eq!(totals(results.iter()), [("c", 2), ("a", 1), ("b", 1)]);
+  }
+}
@@ -0,0 +1,57 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+/// Runs `sins check --stdin` in `dir` with `source` piped in.
+fn check_stdin(dir: &Path, args: &[&str], source: &str) -> Output {
+  let mut child = Command::new(env!("CARGO_BIN_EXE_sins"))
+    .args(["check", "--no-cache", "--stdin"])
+    .args(args)
+    .current_dir(dir)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .unwrap();
+  child
+    .stdin
+    .take()
+    .unwrap()
+    .write_all(source.as_bytes())
+    .unwrap();
+  child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_stdin_filename() {
+  let dir = std::env::temp_dir().join("sins-test-stdin-filename");
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(dir.join("project")).unwrap();
+  std::fs::write(
+    dir.join("project/sins.toml"),
+    "[rules]\nrestriction = [\"todo\"]\n",
+  )
+  .unwrap();
+  let source = "fn main() {\n  todo!()\n}\n";
+
+  // The buffer is linted with the config of the file it stands for, which
+  // needn't exist yet, and reported under its name.
+  let output =
+    check_stdin(&dir, &["--stdin-filename", "project/src/main.rs"], source);
+  assert!(!output.status.success());
+  assert_eq!(
+    String::from_utf8(output.stdout).unwrap(),
+    "[project/src/main.rs:2:3] todo: `todo!` left in code\n"
+  );
+
+  let output = check_stdin(&dir, &["--stdin-filename", "main.rs"], source);
+  assert!(output.status.success());
+  assert_eq!(String::from_utf8(output.stdout).unwrap(), "");
+
+  let output = check_stdin(&dir.join("project"), &[], source);
+  assert_eq!(
+    String::from_utf8(output.stdout).unwrap(),
+    "[<stdin>:2:3] todo: `todo!` left in code\n"
+  );
+  std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,109 @@
+//! Lint throughput over a corpus of rust sources.
+//!
+//! By default the corpus is generated, set `SINS_BENCH_CORPUS` to a directory
+//! to benchmark against the `.rs` files beneath it instead.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use sins_lib::{NodeKind, Rule, RuleContext, RuleEngine};
+
+fn generated_corpus() -> Vec<String> {
+  (0..50)
+    .map(|file| {
+      (0..100)
+        .map(|f| {
+          format!(
+            "fn f{file}_{f}(a: u32, b: &[u32]) -> u32 {{
+              let c = b.iter().map(|x| x * 2 + a).filter(|x| *x > {f}).count();
+              if a <= 0 || c == 0 {{ return a.max(1).min(c as u32); }}
+              b.len() as u32 - a * (c as u32 + 1) / 3
+            }}\n"
+          )
+        })
+        .collect()
+    })
+    .collect()
+}
+
+fn read_corpus(dir: &std::path::Path, corpus: &mut Vec<String>) {
+  for entry in std::fs::read_dir(dir).unwrap() {
+    let path = entry.unwrap().path();
+    if path.is_dir() {
+      read_corpus(&path, corpus);
+    } else if path.extension() == Some("rs".as_ref()) {
+      let source = std::fs::read_to_string(&path).unwrap();
+      if syn::parse_file(&source).is_ok() {
+        corpus.push(source);
+      }
+    }
+  }
+}
+
+fn corpus() -> Vec<String> {
+  match std::env::var_os("SINS_BENCH_CORPUS") {
+    Some(dir) => {
+      let mut corpus = vec![];
+      read_corpus(dir.as_ref(), &mut corpus);
+      corpus
+    }
+    None => generated_corpus(),
+  }
+}
+
+/// A rule that does nothing, for measuring dispatch overhead.
+struct NoopRule(&'static [NodeKind]);
+impl<'ast> Rule<'ast> for NoopRule {
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    self.0
+  }
+
+  fn apply_expr_binary(&mut self, _: &RuleContext, _: &'ast syn::ExprBinary) {
+    criterion::black_box(());
+  }
+}
+
+fn bench(c: &mut Criterion) {
+  let corpus = corpus();
+  let bytes = corpus.iter().map(|source| source.len() as u64).sum();
+  let syntax_trees: Vec<_> = corpus
+    .iter()
+    .map(|source| syn::parse_file(source).unwrap())
+    .collect();
+
+  let mut group = c.benchmark_group("engine");
+  group.throughput(Throughput::Bytes(bytes));
+  group.bench_function("parse", |b| {
+    b.iter(|| {
+      for source in &corpus {
+        criterion::black_box(syn::parse_file(source).unwrap());
+      }
+    })
+  });
+  group.bench_function("lint/builtin", |b| {
+    let config = sins_lib::config::Config::default();
+    b.iter(|| {
+      for source in &corpus {
+        criterion::black_box(sins_lib::lint(source, &config).unwrap());
+      }
+    })
+  });
+  for (name, node_kinds) in [
+    ("apply_rules/100_uninterested", &[NodeKind::ItemFn][..]),
+    ("apply_rules/100_interested", &[NodeKind::ExprBinary][..]),
+  ] {
+    group.bench_function(name, |b| {
+      b.iter(|| {
+        for syntax_tree in &syntax_trees {
+          let mut rule_engine = RuleEngine::new();
+          for _ in 0..100 {
+            rule_engine.add_rule(Box::new(NoopRule(node_kinds)));
+          }
+          criterion::black_box(rule_engine.apply_rules(syntax_tree));
+        }
+      })
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -0,0 +1,181 @@
+use syn::spanned::Spanned;
+
+use crate::source::SourceFile;
+use crate::{Diagnostic, Position};
+
+/// Identifies a diagnostic by what it was reported on rather than where, so
+/// that it still matches after unrelated edits shift the file's lines.
+#[derive(
+  Clone,
+  Debug,
+  PartialEq,
+  Eq,
+  PartialOrd,
+  Ord,
+  Hash,
+  serde::Serialize,
+  serde::Deserialize,
+)]
+pub struct Fingerprint {
+  pub rule: String,
+  pub file: String,
+  /// Path of the innermost item enclosing the diagnostic, e.g.
+  /// `tests::impl Foo::bar`, or empty at the top level.
+  pub item: String,
+  /// The reported source text, with runs of whitespace collapsed.
+  pub snippet: String,
+}
+
+/// Fingerprints each of `diagnostics`, which were reported for `source`. The
+/// item paths are left empty if `source` doesn't parse.
+pub fn fingerprints(
+  source: &str,
+  file: &str,
+  diagnostics: &[Diagnostic],
+) -> Vec<Fingerprint> {
+  let syntax_tree = syn::parse_file(source).ok();
+  let source = SourceFile::new(source);
+  diagnostics
+    .iter()
+    .map(|diagnostic| {
+      let item = syntax_tree.as_ref().map_or_else(String::new, |tree| {
+        item_path(&source, &tree.items, diagnostic.start).join("::")
+      });
+      let snippet = source
+        .byte_range(diagnostic.start, diagnostic.end)
+        .map_or_else(String::new, |range| normalize(&source.text()[range]));
+      Fingerprint {
+        rule: diagnostic.rule.clone(),
+        file: file.to_owned(),
+        item,
+        snippet,
+      }
+    })
+    .collect()
+}
+
+fn normalize(snippet: &str) -> String {
+  snippet.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn contains(node: &dyn Spanned, position: Position) -> bool {
+  let span = node.span();
+  Position::from(span.start()) <= position
+    && position < Position::from(span.end())
+}
+
+/// Names of the items, innermost last, that enclose `position`.
+fn item_path(
+  source: &SourceFile,
+  items: &[syn::Item],
+  position: Position,
+) -> Vec<String> {
+  let item = match items.iter().find(|item| contains(item, position)) {
+    Some(item) => item,
+    None => return vec![],
+  };
+  let snippet = |node: &dyn Spanned| {
+    source.snippet(node).map(normalize).unwrap_or_default()
+  };
+  let (name, inner) = match item {
+    syn::Item::Mod(item) => {
+      let inner = item
+        .content
+        .as_ref()
+        .map_or_else(Vec::new, |(_, items)| item_path(source, items, position));
+      (item.ident.to_string(), inner)
+    }
+    syn::Item::Impl(item) => {
+      let name = match &item.trait_ {
+        Some((_, path, _)) => {
+          format!("impl {} for {}", snippet(path), snippet(&item.self_ty))
+        }
+        None => format!("impl {}", snippet(&item.self_ty)),
+      };
+      let inner = item
+        .items
+        .iter()
+        .filter(|item| contains(item, position))
+        .filter_map(|item| match item {
+          syn::ImplItem::Const(item) => Some(item.ident.to_string()),
+          syn::ImplItem::Method(item) => Some(item.sig.ident.to_string()),
+          syn::ImplItem::Type(item) => Some(item.ident.to_string()),
+          _ => None,
+        })
+        .collect();
+      (name, inner)
+    }
+    syn::Item::Trait(item) => {
+      let inner = item
+        .items
+        .iter()
+        .filter(|item| contains(item, position))
+        .filter_map(|item| match item {
+          syn::TraitItem::Const(item) => Some(item.ident.to_string()),
+          syn::TraitItem::Method(item) => Some(item.sig.ident.to_string()),
+          syn::TraitItem::Type(item) => Some(item.ident.to_string()),
+          _ => None,
+        })
+        .collect();
+      (item.ident.to_string(), inner)
+    }
+    syn::Item::Fn(item) => (item.sig.ident.to_string(), vec![]),
+    syn::Item::Const(item) => (item.ident.to_string(), vec![]),
+    syn::Item::Static(item) => (item.ident.to_string(), vec![]),
+    syn::Item::Struct(item) => (item.ident.to_string(), vec![]),
+    syn::Item::Enum(item) => (item.ident.to_string(), vec![]),
+    syn::Item::Union(item) => (item.ident.to_string(), vec![]),
+    syn::Item::Type(item) => (item.ident.to_string(), vec![]),
+    _ => return vec![],
+  };
+  std::iter::once(name).chain(inner).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_fingerprints() {
+    let source = "\
+mod tests {
+  impl Foo for Vec<u8> {
+    fn bar() {
+      x   .
+        unwrap();
+    }
+  }
+}
+
+fn main() { y.unwrap(); }
+";
+    let diagnostic = |start: (usize, usize), end: (usize, usize)| Diagnostic {
+      rule: "unwrap_used".into(),
+      message: String::new(),
+      start: Position {
+        line: start.0,
+        column: start.1,
+      },
+      end: Position {
+        line: end.0,
+        column: end.1,
+      },
+      suggestion: None,
+    };
+    let diagnostics =
+      [diagnostic((4, 6), (5, 16)), diagnostic((10, 12), (10, 22))];
+    let fingerprint = |item: &str, snippet: &str| Fingerprint {
+      rule: "unwrap_used".into(),
+      file: "src/lib.rs".into(),
+      item: item.into(),
+      snippet: snippet.into(),
+    };
+    assert_eq!(
+      fingerprints(source, "src/lib.rs", &diagnostics),
+      [
+        fingerprint("tests::impl Foo for Vec<u8>::bar", "x . unwrap()"),
+        fingerprint("main", "y.unwrap()"),
+      ]
+    );
+  }
+}
@@ -0,0 +1,294 @@
+//! Rules reporting boolean expressions that can be written more simply.
+
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use super::operators::is_value;
+use super::sugg::{
+  self, report_replacement, report_rewrite, span_key, Positions,
+};
+use crate::types::LocalTypes;
+use crate::{Applicability, NodeKind, Position, Rule, RuleContext};
+
+fn bool_literal(expr: &syn::Expr) -> Option<bool> {
+  match expr {
+    syn::Expr::Lit(syn::ExprLit {
+      lit: syn::Lit::Bool(lit),
+      ..
+    }) => Some(lit.value),
+    syn::Expr::Paren(paren) => bool_literal(&paren.expr),
+    _ => None,
+  }
+}
+
+/// The operand of `!expr`, looking through parentheses.
+fn negated(expr: &syn::Expr) -> Option<&syn::Expr> {
+  match expr {
+    syn::Expr::Unary(syn::ExprUnary {
+      op: syn::UnOp::Not(_),
+      expr,
+      ..
+    }) => Some(expr),
+    syn::Expr::Paren(paren) => negated(&paren.expr),
+    _ => None,
+  }
+}
+
+/// Reports comparisons with boolean literals, `x == true` for `x`.
+#[derive(Default)]
+pub struct BoolComparison {
+  positions: Positions,
+}
+
+impl<'ast> Rule<'ast> for BoolComparison {
+  fn name(&self) -> &'static str {
+    "bool_comparison"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr, NodeKind::ExprBinary]
+  }
+
+  fn apply_expr(&mut self, _context: &RuleContext, node: &'ast syn::Expr) {
+    self.positions.enter(node);
+  }
+
+  fn apply_expr_binary(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprBinary,
+  ) {
+    let (value, other) =
+      match (bool_literal(&node.left), bool_literal(&node.right)) {
+        (Some(value), None) => (value, &*node.right),
+        (None, Some(value)) => (value, &*node.left),
+        _ => return,
+      };
+    let (equality, negate) = match node.op {
+      syn::BinOp::Eq(_) => ("equality", !value),
+      syn::BinOp::Ne(_) => ("inequality", value),
+      _ => return,
+    };
+    let message = if negate {
+      format!(
+        "{} checks against {} can be replaced by a negation",
+        equality, value
+      )
+    } else {
+      format!("{} checks against {} are unnecessary", equality, value)
+    };
+    let (text, precedence) = if negate {
+      let Some(operand) = sugg::snippet(context, other, sugg::UNARY) else {
+        return;
+      };
+      (format!("!{}", operand), sugg::UNARY)
+    } else {
+      let Some(text) = context.snippet(other) else {
+        return;
+      };
+      (text.to_owned(), sugg::precedence(other))
+    };
+    let replacement = self.positions.fit(node, &text, precedence);
+    report_rewrite(context, message, node, replacement);
+  }
+}
+
+/// Reports negations that can be removed or merged: `!!x` for `x`,
+/// `!(a == b)` for `a != b`, and, by De Morgan's laws, `!a && !b` for
+/// `!(a || b)` and `!(!a || !b)` for `a && b`. Removing a `!` from a reference
+/// changes its type, so fixes are only machine-applicable where the operands
+/// are known to be values.
+#[derive(Default)]
+pub struct NonminimalBool {
+  positions: Positions,
+  types: Rc<LocalTypes>,
+  /// Spans of expressions rewritten along with their parent.
+  rewritten: HashSet<(Position, Position)>,
+}
+
+impl NonminimalBool {
+  /// Machine-applicable if all of `operands` are known to be values.
+  fn applicability(&self, operands: &[&syn::Expr]) -> Applicability {
+    if operands
+      .iter()
+      .all(|operand| is_value(&self.types, operand))
+    {
+      Applicability::MachineApplicable
+    } else {
+      Applicability::MaybeIncorrect
+    }
+  }
+
+  /// The simpler form of `expr`, its precedence, and whether it's sure to
+  /// have the same type.
+  fn simplify(
+    &mut self,
+    context: &RuleContext,
+    expr: &syn::Expr,
+  ) -> Option<(String, u8, Applicability)> {
+    if let Some(operand) = negated(expr) {
+      // `!!x`
+      if let Some(inner) = negated(operand) {
+        let text = context.snippet(inner)?.to_owned();
+        let applicability = self.applicability(&[inner]);
+        return Some((text, sugg::precedence(inner), applicability));
+      }
+      let syn::Expr::Paren(paren) = operand else {
+        return None;
+      };
+      let syn::Expr::Binary(binary) = &*paren.expr else {
+        return None;
+      };
+      let flipped = match binary.op {
+        syn::BinOp::Eq(_) => "!=",
+        syn::BinOp::Ne(_) => "==",
+        syn::BinOp::And(_) | syn::BinOp::Or(_) => {
+          // `!(!a || !b)`
+          let a = negated(&binary.left)?;
+          let b = negated(&binary.right)?;
+          let op = if matches!(binary.op, syn::BinOp::And(_)) {
+            "||"
+          } else {
+            "&&"
+          };
+          let precedence = if op == "||" { 3 } else { 4 };
+          self.rewritten.insert(span_key(binary));
+          let text = format!(
+            "{} {} {}",
+            sugg::snippet(context, a, precedence)?,
+            op,
+            sugg::snippet(context, b, precedence + 1)?
+          );
+          return Some((text, precedence, self.applicability(&[a, b])));
+        }
+        _ => return None,
+      };
+      let left = context.snippet(&binary.left)?;
+      let right = context.snippet(&binary.right)?;
+      let text = format!("{} {} {}", left, flipped, right);
+      return Some((text, 5, Applicability::MachineApplicable));
+    }
+    // `!a && !b`
+    let syn::Expr::Binary(binary) = expr else {
+      return None;
+    };
+    let op = match binary.op {
+      syn::BinOp::And(_) => "||",
+      syn::BinOp::Or(_) => "&&",
+      _ => return None,
+    };
+    if self.rewritten.contains(&span_key(binary)) {
+      return None;
+    }
+    let a = negated(&binary.left)?;
+    let b = negated(&binary.right)?;
+    let text =
+      format!("!({} {} {})", context.snippet(a)?, op, context.snippet(b)?);
+    Some((text, sugg::UNARY, self.applicability(&[a, b])))
+  }
+}
+
+impl<'ast> Rule<'ast> for NonminimalBool {
+  fn name(&self) -> &'static str {
+    "nonminimal_bool"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::File, NodeKind::Expr]
+  }
+
+  fn apply_file(&mut self, context: &RuleContext, node: &'ast syn::File) {
+    self.types = context.types(node);
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    self.positions.enter(node);
+    if let Some((text, precedence, applicability)) =
+      self.simplify(context, node)
+    {
+      let replacement = self.positions.fit(node, &text, precedence);
+      report_replacement(
+        context,
+        "this boolean expression can be simplified",
+        node,
+        replacement,
+        applicability,
+      );
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::clippy::{fixes, machine_applicable};
+
+  #[test]
+  fn test_bool_comparison() {
+    let source = "
+fn f(x: bool, y: bool) {
+  x == true;
+  false == x;
+  x != true;
+  y && x != false;
+  !(x == false) == y;
+}
+";
+    assert_eq!(
+      fixes("bool_comparison", source),
+      [
+        (3, "x".into()),
+        (4, "!x".into()),
+        (5, "!x".into()),
+        (6, "x".into()),
+        (7, "!x".into()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_nonminimal_bool() {
+    let source = "
+fn f(a: bool, b: bool) {
+  !!a;
+  !(a == b);
+  c && !(a != b) == d;
+  !a && !b.c();
+  !(!a || !b);
+  d && !(!a && !b);
+  !(a && b);
+}
+";
+    assert_eq!(
+      fixes("nonminimal_bool", source),
+      [
+        (3, "a".into()),
+        (4, "a != b".into()),
+        (5, "(a == b)".into()),
+        (6, "!(a || b.c())".into()),
+        (7, "a && b".into()),
+        (8, "(a || b)".into()),
+      ]
+    );
+    assert_eq!(machine_applicable("nonminimal_bool", source), [4, 5]);
+  }
+
+  #[test]
+  fn test_nonminimal_bool_on_reference() {
+    let source = "
+fn f(r: &bool, x: u8) {
+  let b: bool = !!r;
+  !!(x > 1);
+  !x.is_empty() && !(x < 2);
+}
+";
+    assert_eq!(
+      fixes("nonminimal_bool", source),
+      [
+        (3, "r".into()),
+        (4, "(x > 1)".into()),
+        (5, "!(x.is_empty() || (x < 2))".into()),
+      ]
+    );
+    assert_eq!(machine_applicable("nonminimal_bool", source), [4, 5]);
+  }
+}
@@ -0,0 +1,331 @@
+//! Rules reporting `as` casts between numeric types that may lose
+//! information, where the types can be told from the file.
+
+use std::rc::Rc;
+
+use crate::types::{LocalTypes, NumType};
+use crate::{NodeKind, Report, Rule, RuleContext};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CastLint {
+  PossibleTruncation,
+  SignLoss,
+  PossibleWrap,
+  PrecisionLoss,
+  /// Casts to a numeric type from an expression whose type is unknown, which
+  /// the other lints can't check. Pedantic.
+  UnknownSource,
+}
+
+pub struct Cast {
+  lint: CastLint,
+  types: Rc<LocalTypes>,
+}
+
+impl Cast {
+  pub fn new(lint: CastLint) -> Self {
+    Self {
+      lint,
+      types: Rc::default(),
+    }
+  }
+
+  /// What's wrong with casting `expr`, of type `from`, to `to`, if this lint
+  /// applies.
+  fn check(
+    &self,
+    expr: &syn::Expr,
+    from: NumType,
+    to: NumType,
+  ) -> Option<String> {
+    let (from_min, from_max) = from.bits();
+    let (to_min, to_max) = to.bits();
+    let from_int = !from.is_float();
+    let to_int = !to.is_float();
+    let cast = format!("casting `{}` to `{}`", from.name(), to.name());
+    match self.lint {
+      CastLint::PossibleTruncation if !from_int => {
+        let lossy = to_int || (from, to) == (NumType::F64, NumType::F32);
+        lossy.then(|| format!("{} may truncate the value", cast))
+      }
+      CastLint::PossibleTruncation => {
+        if !to_int || to_min >= from_max {
+          return None;
+        }
+        let targets = if to.is_pointer_sized() {
+          " on targets with 32-bit wide pointers"
+        } else if from.is_pointer_sized() {
+          " on targets with 64-bit wide pointers"
+        } else {
+          ""
+        };
+        Some(format!("{} may truncate the value{}", cast, targets))
+      }
+      CastLint::SignLoss => {
+        let lossy = (from.is_signed() || !from_int)
+          && to_int
+          && !to.is_signed()
+          && !is_non_negative_literal(expr);
+        lossy.then(|| format!("{} may lose the sign of the value", cast))
+      }
+      CastLint::PossibleWrap => {
+        // Targets that are always narrower truncate rather than wrap.
+        let lossy = from_int
+          && !from.is_signed()
+          && to.is_signed()
+          && to_min <= from_max
+          && from_min <= to_max;
+        lossy.then(|| format!("{} may wrap around the value", cast))
+      }
+      CastLint::PrecisionLoss => {
+        let mantissa = match to {
+          NumType::F32 => 23,
+          NumType::F64 => 52,
+          _ => return None,
+        };
+        // A sign bit leaves one bit less of magnitude.
+        let magnitude = from_max - u32::from(from.is_signed());
+        let lossy = from_int && magnitude > mantissa + 1;
+        lossy.then(|| {
+          format!(
+            "{} may lose precision, as `{}`'s mantissa is only {} bits wide",
+            cast,
+            to.name(),
+            mantissa
+          )
+        })
+      }
+      CastLint::UnknownSource => None,
+    }
+  }
+
+  /// What's wrong with casting the unsuffixed integer literal `value`
+  /// (negated if `negative`) to `to`, which needs no type to check.
+  fn check_literal(
+    &self,
+    value: u128,
+    negative: bool,
+    to: NumType,
+  ) -> Option<String> {
+    let to_name = to.name();
+    match self.lint {
+      CastLint::PossibleTruncation if !to.is_float() => {
+        let (_, to_max) = to.bits();
+        let bits = to_max.min(128) - u32::from(to.is_signed());
+        let max = if bits >= 128 {
+          u128::MAX
+        } else {
+          (1 << bits) - 1
+        };
+        // Two's complement reaches one further below zero, to `-128i8`.
+        let max = max + u128::from(negative && to.is_signed());
+        let sign = if negative { "-" } else { "" };
+        (value > max).then(|| {
+          format!(
+            "casting `{}{}` to `{}` truncates the value",
+            sign, value, to_name
+          )
+        })
+      }
+      CastLint::SignLoss if negative && !to.is_signed() && !to.is_float() => {
+        Some(format!(
+          "casting `-{}` to `{}` loses the sign of the value",
+          value, to_name
+        ))
+      }
+      _ => None,
+    }
+  }
+}
+
+/// The value of an unsuffixed integer literal, possibly negated, and whether
+/// it's negated.
+fn unsuffixed_literal(expr: &syn::Expr) -> Option<(u128, bool)> {
+  match expr {
+    syn::Expr::Lit(syn::ExprLit {
+      lit: syn::Lit::Int(int),
+      ..
+    }) if int.suffix().is_empty() => Some((int.base10_parse().ok()?, false)),
+    syn::Expr::Unary(syn::ExprUnary {
+      op: syn::UnOp::Neg(_),
+      expr,
+      ..
+    }) => {
+      let (value, negative) = unsuffixed_literal(expr)?;
+      Some((value, !negative))
+    }
+    syn::Expr::Paren(paren) => unsuffixed_literal(&paren.expr),
+    _ => None,
+  }
+}
+
+fn is_non_negative_literal(expr: &syn::Expr) -> bool {
+  match expr {
+    syn::Expr::Lit(lit) => match &lit.lit {
+      syn::Lit::Int(_) => true,
+      syn::Lit::Float(float) => !float.base10_digits().starts_with('-'),
+      _ => false,
+    },
+    syn::Expr::Paren(paren) => is_non_negative_literal(&paren.expr),
+    _ => false,
+  }
+}
+
+impl<'ast> Rule<'ast> for Cast {
+  fn name(&self) -> &'static str {
+    match self.lint {
+      CastLint::PossibleTruncation => "cast_possible_truncation",
+      CastLint::SignLoss => "cast_sign_loss",
+      CastLint::PossibleWrap => "cast_possible_wrap",
+      CastLint::PrecisionLoss => "cast_precision_loss",
+      CastLint::UnknownSource => "cast_unknown_source",
+    }
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::File, NodeKind::Expr]
+  }
+
+  fn apply_file(&mut self, context: &RuleContext, node: &'ast syn::File) {
+    self.types = context.types(node);
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let syn::Expr::Cast(cast) = node else {
+      return;
+    };
+    let Some(to) = NumType::from_type(&cast.ty) else {
+      return;
+    };
+    let message =
+      if let Some((value, negative)) = unsuffixed_literal(&cast.expr) {
+        self.check_literal(value, negative, to)
+      } else {
+        match self.types.type_of(&cast.expr) {
+          Some(from) => self.check(&cast.expr, from, to),
+          None => (self.lint == CastLint::UnknownSource).then(|| {
+            format!(
+              "casting a value of unknown type to `{}`, which may lose \
+             information",
+              to.name()
+            )
+          }),
+        }
+      };
+    if let Some(message) = message {
+      context.emit_report(Report {
+        message,
+        node,
+        suggestion: None,
+      });
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::clippy::lint_with;
+
+  /// The line and message of each diagnostic of `rule` in `source`.
+  fn messages(rule: &str, source: &str) -> Vec<(usize, String)> {
+    lint_with(rule, source)
+      .into_iter()
+      .map(|d| (d.start.line, d.message))
+      .collect()
+  }
+
+  fn lines(rule: &str, source: &str) -> Vec<usize> {
+    lint_with(rule, source)
+      .into_iter()
+      .map(|d| d.start.line)
+      .collect()
+  }
+
+  #[test]
+  fn test_cast_possible_truncation() {
+    let source = "
+fn f(a: u64, c: usize, d: f64) {
+  a as u32;
+  a as u128;
+  c as u32;
+  d as f32;
+  d as i64;
+  300 as u8;
+  1 as u8;
+  -128 as i8;
+  -129 as i8;
+  128 as i8;
+  x as u8;
+}
+";
+    assert_eq!(
+      messages("cast_possible_truncation", source),
+      [
+        (3, "casting `u64` to `u32` may truncate the value".into()),
+        (
+          5,
+          "casting `usize` to `u32` may truncate the value on targets with \
+           64-bit wide pointers"
+            .into()
+        ),
+        (6, "casting `f64` to `f32` may truncate the value".into()),
+        (7, "casting `f64` to `i64` may truncate the value".into()),
+        (8, "casting `300` to `u8` truncates the value".into()),
+        (11, "casting `-129` to `i8` truncates the value".into()),
+        (12, "casting `128` to `i8` truncates the value".into()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_cast_sign_loss() {
+    let source = "
+fn f(b: i32, e: u8) {
+  b as u32;
+  b as i64;
+  e as u16;
+  -1 as u16;
+  1 as u16;
+}
+";
+    assert_eq!(lines("cast_sign_loss", source), [3, 6]);
+  }
+
+  #[test]
+  fn test_cast_possible_wrap() {
+    let source = "
+fn f(c: usize, e: u8) {
+  c as i64;
+  3u32 as i32;
+  e as i16;
+  e as i8;
+}
+";
+    assert_eq!(lines("cast_possible_wrap", source), [3, 4, 6]);
+  }
+
+  #[test]
+  fn test_cast_precision_loss() {
+    let source = "
+fn f(b: i32, d: u64) {
+  b as f32;
+  b as f64;
+  d as f64;
+}
+";
+    assert_eq!(lines("cast_precision_loss", source), [3, 5]);
+  }
+
+  #[test]
+  fn test_cast_unknown_source() {
+    let source = "
+fn f(a: u64) {
+  x as u8;
+  x as String;
+  a as u8;
+  1 as u8;
+}
+";
+    assert_eq!(lines("cast_unknown_source", source), [3]);
+  }
+}
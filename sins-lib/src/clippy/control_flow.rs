@@ -0,0 +1,628 @@
+//! Rules reporting `if`s, `return`s and `continue`s that the block structure
+//! makes unnecessary.
+
+use std::collections::HashSet;
+
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+use super::sugg::{self, has_comments, report_rewrite, span_key, Positions};
+use crate::{
+  Applicability, Edit, NodeKind, Position, Report, Rule, RuleContext,
+  Suggestion,
+};
+
+/// Removes the code from `start` to `end` to fix `node`.
+fn report_removal(
+  context: &RuleContext,
+  message: &str,
+  node: &dyn Spanned,
+  start: Position,
+  end: Position,
+) {
+  context.emit_report(Report {
+    message: message.into(),
+    node,
+    suggestion: Some(Suggestion {
+      message: "remove it".into(),
+      applicability: Applicability::MachineApplicable,
+      edits: vec![Edit {
+        start,
+        end,
+        replacement: String::new(),
+      }],
+    }),
+  });
+}
+
+/// Reports `return` as the last thing a function or closure does, including in
+/// the branches of a trailing `if` or `match`.
+pub struct NeedlessReturn;
+
+impl<'ast> Rule<'ast> for NeedlessReturn {
+  fn name(&self) -> &'static str {
+    "needless_return"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::File]
+  }
+
+  fn apply_file(&mut self, context: &RuleContext, node: &'ast syn::File) {
+    ReturnFinder { context }.visit_file(node);
+  }
+}
+
+/// Walks a file for `NeedlessReturn`, which needs function bodies of all
+/// kinds.
+struct ReturnFinder<'a> {
+  context: &'a RuleContext,
+}
+
+impl ReturnFinder<'_> {
+  fn check_block(&self, block: &syn::Block) {
+    match block.stmts.last() {
+      Some(syn::Stmt::Semi(syn::Expr::Return(ret), semi)) => {
+        self.report(ret, semi.span().end().into());
+      }
+      Some(syn::Stmt::Expr(expr)) => self.check_tail(expr),
+      _ => {}
+    }
+  }
+
+  fn check_tail(&self, expr: &syn::Expr) {
+    match expr {
+      syn::Expr::Return(ret) => self.report(ret, ret.span().end().into()),
+      syn::Expr::Block(block) => self.check_block(&block.block),
+      syn::Expr::If(expr_if) => {
+        self.check_block(&expr_if.then_branch);
+        if let Some((_, else_branch)) = &expr_if.else_branch {
+          self.check_tail(else_branch);
+        }
+      }
+      syn::Expr::Match(expr_match) => {
+        for arm in &expr_match.arms {
+          self.check_tail(&arm.body);
+        }
+      }
+      _ => {}
+    }
+  }
+
+  /// Reports `ret`, which ends at `end` with any semicolon.
+  fn report(&self, ret: &syn::ExprReturn, end: Position) {
+    let replacement = match &ret.expr {
+      Some(value) => match self.context.snippet(&**value) {
+        Some(text) => text.to_owned(),
+        None => return,
+      },
+      None => String::new(),
+    };
+    self.context.emit_report(Report {
+      message: "unneeded `return` statement".into(),
+      node: ret,
+      suggestion: Some(Suggestion {
+        message: "remove `return`".into(),
+        applicability: Applicability::MachineApplicable,
+        edits: vec![Edit {
+          start: ret.span().start().into(),
+          end,
+          replacement,
+        }],
+      }),
+    });
+  }
+}
+
+impl<'ast> Visit<'ast> for ReturnFinder<'_> {
+  fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+    self.check_block(&node.block);
+    syn::visit::visit_item_fn(self, node);
+  }
+
+  fn visit_impl_item_method(&mut self, node: &'ast syn::ImplItemMethod) {
+    self.check_block(&node.block);
+    syn::visit::visit_impl_item_method(self, node);
+  }
+
+  fn visit_trait_item_method(&mut self, node: &'ast syn::TraitItemMethod) {
+    if let Some(block) = &node.default {
+      self.check_block(block);
+    }
+    syn::visit::visit_trait_item_method(self, node);
+  }
+
+  fn visit_expr_closure(&mut self, node: &'ast syn::ExprClosure) {
+    if let syn::Expr::Block(block) = &*node.body {
+      self.check_block(&block.block);
+    }
+    syn::visit::visit_expr_closure(self, node);
+  }
+}
+
+/// Reports `continue` as the last thing a loop body does, or as the whole of
+/// the `else` of its trailing `if`.
+pub struct NeedlessContinue;
+
+impl NeedlessContinue {
+  fn check_block(
+    context: &RuleContext,
+    block: &syn::Block,
+    label: Option<&syn::Lifetime>,
+  ) {
+    let continues = |expr: &syn::Expr| match expr {
+      syn::Expr::Continue(expr) => {
+        expr.label.is_none() || expr.label.as_ref() == label
+      }
+      _ => false,
+    };
+    match block.stmts.last() {
+      Some(syn::Stmt::Semi(expr, semi)) if continues(expr) => {
+        report_removal(
+          context,
+          "this `continue` expression is redundant",
+          expr,
+          expr.span().start().into(),
+          semi.span().end().into(),
+        );
+      }
+      Some(syn::Stmt::Expr(expr)) if continues(expr) => {
+        report_removal(
+          context,
+          "this `continue` expression is redundant",
+          expr,
+          expr.span().start().into(),
+          expr.span().end().into(),
+        );
+      }
+      Some(syn::Stmt::Semi(syn::Expr::If(expr_if), _))
+      | Some(syn::Stmt::Expr(syn::Expr::If(expr_if))) => {
+        Self::check_if(context, expr_if, label, &continues);
+      }
+      _ => {}
+    }
+  }
+
+  fn check_if(
+    context: &RuleContext,
+    expr_if: &syn::ExprIf,
+    label: Option<&syn::Lifetime>,
+    continues: &dyn Fn(&syn::Expr) -> bool,
+  ) {
+    Self::check_block(context, &expr_if.then_branch, label);
+    match expr_if.else_branch.as_ref().map(|(_, branch)| &**branch) {
+      Some(syn::Expr::Block(block)) => {
+        let only_continue = match block.block.stmts.as_slice() {
+          [syn::Stmt::Semi(expr, _)] | [syn::Stmt::Expr(expr)] => {
+            continues(expr)
+          }
+          _ => false,
+        };
+        if only_continue {
+          report_removal(
+            context,
+            "this `else` block is redundant",
+            block,
+            expr_if.then_branch.span().end().into(),
+            block.span().end().into(),
+          );
+        } else {
+          Self::check_block(context, &block.block, label);
+        }
+      }
+      Some(syn::Expr::If(else_if)) => {
+        Self::check_if(context, else_if, label, continues);
+      }
+      _ => {}
+    }
+  }
+}
+
+impl<'ast> Rule<'ast> for NeedlessContinue {
+  fn name(&self) -> &'static str {
+    "needless_continue"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let (body, label) = match node {
+      syn::Expr::ForLoop(expr) => (&expr.body, &expr.label),
+      syn::Expr::While(expr) => (&expr.body, &expr.label),
+      syn::Expr::Loop(expr) => (&expr.body, &expr.label),
+      _ => return,
+    };
+    Self::check_block(context, body, label.as_ref().map(|label| &label.name));
+  }
+}
+
+/// The boolean literal a block consists of.
+fn block_bool(block: &syn::Block) -> Option<bool> {
+  let expr = match block.stmts.as_slice() {
+    [syn::Stmt::Expr(expr)] => expr,
+    _ => return None,
+  };
+  match expr {
+    syn::Expr::Lit(syn::ExprLit {
+      lit: syn::Lit::Bool(lit),
+      ..
+    }) => Some(lit.value),
+    _ => None,
+  }
+}
+
+/// Reports `if c { true } else { false }` for `c`, and its negation for `!c`.
+#[derive(Default)]
+pub struct NeedlessBool {
+  positions: Positions,
+  /// Spans of `if`s that are the `else` branch of another, which must stay
+  /// blocks.
+  else_ifs: HashSet<(Position, Position)>,
+}
+
+impl<'ast> Rule<'ast> for NeedlessBool {
+  fn name(&self) -> &'static str {
+    "needless_bool"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    self.positions.enter(node);
+    let syn::Expr::If(expr_if) = node else {
+      return;
+    };
+    if let Some((_, else_if @ syn::Expr::If(_))) =
+      expr_if.else_branch.as_ref().map(|(e, b)| (e, &**b))
+    {
+      self.else_ifs.insert(span_key(else_if));
+    }
+    let Some((_, syn::Expr::Block(else_block))) =
+      expr_if.else_branch.as_ref().map(|(e, b)| (e, &**b))
+    else {
+      return;
+    };
+    if matches!(*expr_if.cond, syn::Expr::Let(_)) {
+      return;
+    }
+    let (Some(then), Some(otherwise)) = (
+      block_bool(&expr_if.then_branch),
+      block_bool(&else_block.block),
+    ) else {
+      return;
+    };
+    if then == otherwise {
+      context.emit_report(Report {
+        message: format!(
+          "this if-then-else expression always returns {}",
+          then
+        ),
+        node,
+        suggestion: None,
+      });
+      return;
+    }
+    let (text, precedence) = if then {
+      let Some(text) = context.snippet(&*expr_if.cond) else {
+        return;
+      };
+      (text.to_owned(), sugg::precedence(&expr_if.cond))
+    } else {
+      let Some(text) = sugg::snippet(context, &expr_if.cond, sugg::UNARY)
+      else {
+        return;
+      };
+      (format!("!{}", text), sugg::UNARY)
+    };
+    let replacement = if self.else_ifs.contains(&span_key(node)) {
+      format!("{{ {} }}", text)
+    } else {
+      self.positions.fit(node, &text, precedence)
+    };
+    report_rewrite(
+      context,
+      "this if-then-else expression returns a bool literal",
+      node,
+      replacement,
+    );
+  }
+}
+
+/// Reports `if a { if b { .. } }` for `if a && b { .. }`.
+pub struct CollapsibleIf;
+
+impl<'ast> Rule<'ast> for CollapsibleIf {
+  fn name(&self) -> &'static str {
+    "collapsible_if"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let syn::Expr::If(outer) = node else {
+      return;
+    };
+    let inner = match outer.then_branch.stmts.as_slice() {
+      [syn::Stmt::Expr(syn::Expr::If(inner))] => inner,
+      _ => return,
+    };
+    if outer.else_branch.is_some()
+      || inner.else_branch.is_some()
+      || matches!(*outer.cond, syn::Expr::Let(_))
+      || matches!(*inner.cond, syn::Expr::Let(_))
+      || has_comments(context, &outer.then_branch, &[inner])
+    {
+      return;
+    }
+    let (Some(a), Some(b), Some(body)) = (
+      sugg::snippet(context, &outer.cond, 4),
+      sugg::snippet(context, &inner.cond, 5),
+      context.snippet(&inner.then_branch),
+    ) else {
+      return;
+    };
+    report_rewrite(
+      context,
+      "this `if` statement can be collapsed",
+      node,
+      format!("if {} && {} {}", a, b, body),
+    );
+  }
+}
+
+/// Reports `else { if b { .. } }` for `else if b { .. }`.
+pub struct CollapsibleElseIf;
+
+impl<'ast> Rule<'ast> for CollapsibleElseIf {
+  fn name(&self) -> &'static str {
+    "collapsible_else_if"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let syn::Expr::If(outer) = node else {
+      return;
+    };
+    let Some((_, syn::Expr::Block(else_block))) =
+      outer.else_branch.as_ref().map(|(e, b)| (e, &**b))
+    else {
+      return;
+    };
+    let inner = match else_block.block.stmts.as_slice() {
+      [syn::Stmt::Expr(inner @ syn::Expr::If(_))] => inner,
+      _ => return,
+    };
+    if has_comments(context, else_block, &[inner]) {
+      return;
+    }
+    let Some(text) = context.snippet(inner) else {
+      return;
+    };
+    report_rewrite(
+      context,
+      "this `else { if .. }` block can be collapsed",
+      else_block,
+      text.to_owned(),
+    );
+  }
+}
+
+/// Reports `if` chains with two structurally equal branches next to each
+/// other, which are likely a copy-paste mistake.
+#[derive(Default)]
+pub struct IfSameThenElse {
+  /// `else if`s already checked as part of their chain.
+  chained: HashSet<(Position, Position)>,
+}
+
+impl<'ast> Rule<'ast> for IfSameThenElse {
+  fn name(&self) -> &'static str {
+    "if_same_then_else"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let syn::Expr::If(expr_if) = node else {
+      return;
+    };
+    if self.chained.contains(&span_key(expr_if)) {
+      return;
+    }
+    let mut blocks = vec![&expr_if.then_branch];
+    let mut next = &expr_if.else_branch;
+    while let Some((_, branch)) = next {
+      match &**branch {
+        syn::Expr::If(else_if) => {
+          self.chained.insert(span_key(else_if));
+          blocks.push(&else_if.then_branch);
+          next = &else_if.else_branch;
+        }
+        syn::Expr::Block(block) => {
+          blocks.push(&block.block);
+          next = &None;
+        }
+        _ => next = &None,
+      }
+    }
+    if let Some(pair) = blocks
+      .windows(2)
+      .find(|pair| !pair[0].stmts.is_empty() && pair[0] == pair[1])
+    {
+      context.emit_report(Report {
+        message: "this `if` has identical blocks".into(),
+        node: pair[1],
+        suggestion: None,
+      });
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::clippy::fixes;
+
+  #[test]
+  fn test_needless_return() {
+    let source = "
+fn f(x: u8) -> u8 {
+  if x > 1 {
+    return 1;
+  }
+  return x;
+}
+
+impl A {
+  fn g(&self) {
+    return;
+  }
+}
+
+fn h(x: Option<u8>) -> u8 {
+  let c = |y: u8| -> u8 { return y };
+  match x {
+    Some(x) => return x,
+    None => {
+      return 0;
+    }
+  }
+}
+";
+    assert_eq!(
+      fixes("needless_return", source),
+      [
+        (6, "x".into()),
+        (11, String::new()),
+        (16, "y".into()),
+        (18, "x".into()),
+        (20, "0".into()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_needless_continue() {
+    let source = "
+fn f(v: Vec<u8>) {
+  for x in v {
+    g(x);
+    continue;
+  }
+  'outer: loop {
+    while h() {
+      if x {
+        g(1);
+      } else {
+        continue;
+      }
+    }
+    while h() {
+      continue 'outer;
+    }
+  }
+}
+";
+    assert_eq!(
+      fixes("needless_continue", source),
+      [(5, String::new()), (11, String::new())]
+    );
+  }
+
+  #[test]
+  fn test_needless_bool() {
+    let source = "
+fn f(a: bool, b: bool) -> bool {
+  let x = if a && b { true } else { false };
+  let y = if a || b { false } else { true };
+  let z = if a { true } else { true };
+  let w = x && if a || b { true } else { false };
+  let v = if a { true } else if b { false } else { true };
+  if a { 1 } else { 0 }
+}
+";
+    assert_eq!(
+      fixes("needless_bool", source),
+      [
+        (3, "a && b".into()),
+        (4, "!(a || b)".into()),
+        (5, String::new()),
+        (6, "(a || b)".into()),
+        (7, "{ !b }".into()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_collapsible_if() {
+    let source = "
+fn f(a: bool, b: bool) {
+  if a || c {
+    if b {
+      g();
+    }
+  }
+  if a {
+    // Why b matters.
+    if b {
+      g();
+    }
+  }
+  if a {
+    if b {
+      g();
+    }
+  } else {
+    if b {
+      h();
+    } else {
+      g();
+    }
+  }
+}
+";
+    assert_eq!(
+      fixes("collapsible_if", source),
+      [(3, "if (a || c) && b {\n      g();\n    }".into())]
+    );
+    assert_eq!(
+      fixes("collapsible_else_if", source),
+      [(
+        18,
+        "if b {\n      h();\n    } else {\n      g();\n    }".into()
+      )]
+    );
+  }
+
+  #[test]
+  fn test_if_same_then_else() {
+    let source = "
+fn f(a: bool, b: bool) {
+  if a {
+    g(1);
+  } else if b {
+    g(2);
+  } else {
+    g(2);
+  }
+  if a {
+    g(1);
+  } else {
+    g(2);
+  }
+  if a {} else {}
+}
+";
+    assert_eq!(fixes("if_same_then_else", source), [(7, String::new())]);
+  }
+}
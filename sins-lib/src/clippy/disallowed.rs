@@ -0,0 +1,402 @@
+//! Rules configured in `sins.toml` with lists of things not to use.
+
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+use crate::config::DisallowedPath;
+use crate::imports::Imports;
+use crate::{Applicability, Edit, NodeKind, Report, RuleContext, Suggestion};
+
+/// Reports calls to the methods and functions listed in `disallowed-methods`
+/// and `disallowed-functions`.
+///
+/// The receiver's type of a method call isn't known, so method calls are only
+/// matched by entries that are a bare name; an entry with a full path such as
+/// `std::vec::Vec::leak` would otherwise ban every `.leak()`. Calls through a
+/// path, including `Type::method(receiver)`, are matched by their full path,
+/// resolved through the file's imports.
+pub struct DisallowedMethods {
+  methods: Vec<DisallowedPath>,
+  functions: Vec<DisallowedPath>,
+  imports: Imports,
+}
+
+impl DisallowedMethods {
+  pub fn new(methods: &[DisallowedPath], functions: &[DisallowedPath]) -> Self {
+    Self {
+      methods: methods.to_vec(),
+      functions: functions.to_vec(),
+      imports: Imports::default(),
+    }
+  }
+
+  fn report(
+    context: &RuleContext,
+    kind: &str,
+    disallowed: &DisallowedPath,
+    node: &dyn Spanned,
+    replaced: &dyn Spanned,
+  ) {
+    let mut message =
+      format!("use of disallowed {} `{}`", kind, disallowed.path);
+    if let Some(reason) = &disallowed.reason {
+      message = format!("{}: {}", message, reason);
+    }
+    let suggestion =
+      disallowed
+        .replacement
+        .as_deref()
+        .map(|replacement| Suggestion {
+          message: format!("use `{}` instead", replacement),
+          applicability: Applicability::MaybeIncorrect,
+          edits: vec![Edit::replace(replaced, replacement)],
+        });
+    context.emit_report(Report {
+      message,
+      node,
+      suggestion,
+    });
+  }
+}
+
+/// Whether `disallowed` is the bare name `name`.
+fn matches_name(disallowed: &DisallowedPath, name: &str) -> bool {
+  disallowed.path.trim_start_matches("::") == name
+}
+
+/// Whether `disallowed` names the function at one of the resolved `paths`.
+fn matches_path(disallowed: &DisallowedPath, paths: &[String]) -> bool {
+  let path = disallowed.path.trim_start_matches("::");
+  if path.contains("::") {
+    paths.iter().any(|candidate| candidate == path)
+  } else {
+    paths
+      .iter()
+      .any(|candidate| candidate.rsplit("::").next() == Some(path))
+  }
+}
+
+impl<'ast> crate::Rule<'ast> for DisallowedMethods {
+  fn name(&self) -> &'static str {
+    "disallowed_methods"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::File, NodeKind::Expr]
+  }
+
+  fn apply_file(&mut self, _context: &RuleContext, node: &'ast syn::File) {
+    self.imports = Imports::new(node);
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    match node {
+      syn::Expr::MethodCall(call) => {
+        let name = call.method.to_string();
+        if let Some(disallowed) =
+          self.methods.iter().find(|d| matches_name(d, &name))
+        {
+          Self::report(context, "method", disallowed, node, &call.method);
+        }
+      }
+      syn::Expr::Call(call) => {
+        let path = match &*call.func {
+          syn::Expr::Path(path) if path.qself.is_none() => &path.path,
+          _ => return,
+        };
+        let paths = self.imports.resolve(path);
+        let disallowed = self
+          .methods
+          .iter()
+          .map(|d| ("method", d))
+          .chain(self.functions.iter().map(|d| ("function", d)))
+          .find(|(_, d)| matches_path(d, &paths));
+        if let Some((kind, disallowed)) = disallowed {
+          Self::report(context, kind, disallowed, node, &*call.func);
+        }
+      }
+      _ => {}
+    }
+  }
+}
+
+/// Reports mentions of the types listed in `disallowed-types`: as types, in
+/// paths such as `HashMap::new`, and in `use` declarations, resolving renames
+/// and glob imports through the file's imports.
+pub struct DisallowedTypes {
+  types: Vec<DisallowedPath>,
+}
+
+impl DisallowedTypes {
+  pub fn new(types: &[DisallowedPath]) -> Self {
+    Self {
+      types: types.to_vec(),
+    }
+  }
+
+  /// The disallowed type at one of the resolved `paths`, or containing the
+  /// item at one of them, and whether it's the type itself.
+  fn find(&self, paths: &[String]) -> Option<(&DisallowedPath, bool)> {
+    self.types.iter().find_map(|disallowed| {
+      let path = disallowed.path.trim_start_matches("::");
+      if !path.contains("::") {
+        let last =
+          |candidate: &String| candidate.rsplit("::").next() == Some(path);
+        return if paths.iter().any(last) {
+          Some((disallowed, true))
+        } else if paths
+          .iter()
+          .any(|candidate| candidate.split("::").any(|segment| segment == path))
+        {
+          Some((disallowed, false))
+        } else {
+          None
+        };
+      }
+      if paths.iter().any(|candidate| candidate == path) {
+        return Some((disallowed, true));
+      }
+      let prefix = format!("{}::", path);
+      paths
+        .iter()
+        .any(|candidate| candidate.starts_with(&prefix))
+        .then_some((disallowed, false))
+    })
+  }
+}
+
+impl<'ast> crate::Rule<'ast> for DisallowedTypes {
+  fn name(&self) -> &'static str {
+    "disallowed_types"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::File]
+  }
+
+  fn apply_file(&mut self, context: &RuleContext, node: &'ast syn::File) {
+    let imports = Imports::new(node);
+    TypeFinder {
+      rule: self,
+      imports: &imports,
+      context,
+    }
+    .visit_file(node);
+  }
+}
+
+/// Walks a file for `DisallowedTypes`, which has no hook for types.
+struct TypeFinder<'a> {
+  rule: &'a DisallowedTypes,
+  imports: &'a Imports,
+  context: &'a RuleContext,
+}
+
+impl TypeFinder<'_> {
+  fn report(
+    &self,
+    disallowed: &DisallowedPath,
+    node: &dyn Spanned,
+    edit: Option<Edit>,
+  ) {
+    let mut message = format!("use of disallowed type `{}`", disallowed.path);
+    if let Some(reason) = &disallowed.reason {
+      message = format!("{}: {}", message, reason);
+    }
+    let suggestion = match (&disallowed.replacement, edit) {
+      (Some(replacement), Some(edit)) => Some(Suggestion {
+        message: format!("use `{}` instead", replacement),
+        applicability: Applicability::MaybeIncorrect,
+        edits: vec![edit],
+      }),
+      _ => None,
+    };
+    self.context.emit_report(Report {
+      message,
+      node,
+      suggestion,
+    });
+  }
+
+  fn check_path(&self, path: &syn::Path, node: &dyn Spanned) {
+    let Some((disallowed, exact)) = self.rule.find(&self.imports.resolve(path))
+    else {
+      return;
+    };
+    // Replaces the path up to its generic arguments, if it names the type
+    // itself rather than something within it.
+    let edit = match (exact, &disallowed.replacement, path.segments.last()) {
+      (true, Some(replacement), Some(last)) => Some(Edit {
+        start: path.span().start().into(),
+        end: last.ident.span().end().into(),
+        replacement: replacement.clone(),
+      }),
+      _ => None,
+    };
+    self.report(disallowed, node, edit);
+  }
+
+  fn check_use_tree(&self, tree: &syn::UseTree, prefix: &mut Vec<String>) {
+    let ident = match tree {
+      syn::UseTree::Path(path) => {
+        prefix.push(path.ident.to_string());
+        self.check_use_tree(&path.tree, prefix);
+        prefix.pop();
+        return;
+      }
+      syn::UseTree::Group(group) => {
+        for tree in &group.items {
+          self.check_use_tree(tree, prefix);
+        }
+        return;
+      }
+      syn::UseTree::Name(name) => &name.ident,
+      syn::UseTree::Rename(rename) => &rename.ident,
+      // Only what's used from a glob is reported.
+      syn::UseTree::Glob(_) => return,
+    };
+    let mut path = prefix.clone();
+    if ident != "self" {
+      path.push(ident.to_string());
+    }
+    let paths = self.imports.resolve_segments(&path);
+    if let Some((disallowed, _)) = self.rule.find(&paths) {
+      self.report(disallowed, tree, None);
+    }
+  }
+}
+
+impl<'ast> Visit<'ast> for TypeFinder<'_> {
+  fn visit_type_path(&mut self, node: &'ast syn::TypePath) {
+    if node.qself.is_none() {
+      self.check_path(&node.path, node);
+    }
+    syn::visit::visit_type_path(self, node);
+  }
+
+  fn visit_expr_path(&mut self, node: &'ast syn::ExprPath) {
+    if node.qself.is_none() {
+      self.check_path(&node.path, node);
+    }
+    syn::visit::visit_expr_path(self, node);
+  }
+
+  fn visit_item_use(&mut self, node: &'ast syn::ItemUse) {
+    self.check_use_tree(&node.tree, &mut vec![]);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::config::Config;
+
+  #[test]
+  fn test_disallowed_methods() {
+    let config = Config::from_toml(
+      r#"
+      disallowed-methods = [
+        { path = "std::vec::Vec::leak", reason = "leaks memory" },
+        { path = "unwrap_unchecked", replacement = "expect" },
+      ]
+      disallowed-functions = [
+        { path = "std::process::exit", replacement = "std::process::abort" },
+      ]
+
+      [rules]
+      disable = ["absurd_extreme_comparisons"]
+      "#,
+    )
+    .unwrap();
+    let source = "
+use std::process::{self, exit as quit};
+
+fn main() {
+  s.leak();
+  Vec::leak(v);
+  o.unwrap_unchecked();
+  process::exit(1);
+  quit(1);
+  exit(1);
+  other::exit(1);
+  String::leak(s);
+}
+";
+    let diagnostics = crate::lint(source, &config).unwrap();
+    let reports: Vec<_> = diagnostics
+      .iter()
+      .map(|d| (d.message.as_str(), d.start.line))
+      .collect();
+    assert_eq!(
+      reports,
+      [
+        (
+          "use of disallowed method `std::vec::Vec::leak`: leaks memory",
+          6
+        ),
+        ("use of disallowed method `unwrap_unchecked`", 7),
+        ("use of disallowed function `std::process::exit`", 8),
+        ("use of disallowed function `std::process::exit`", 9),
+      ]
+    );
+    let edit = &diagnostics[1].suggestion.as_ref().unwrap().edits[0];
+    assert_eq!(edit.replacement, "expect");
+  }
+
+  #[test]
+  fn test_disallowed_types() {
+    let config = Config::from_toml(
+      r#"
+      disallowed-types = [
+        { path = "std::collections::HashMap", replacement = "BTreeMap" },
+        { path = "std::sync::Mutex", reason = "use parking_lot" },
+      ]
+
+      [rules]
+      disable = ["absurd_extreme_comparisons"]
+      "#,
+    )
+    .unwrap();
+    let source = "
+use std::collections::{BTreeMap, HashMap as Map};
+use std::sync::*;
+
+fn f(a: Map<u8, u8>, b: BTreeMap<u8, u8>) -> Mutex<()> {
+  std::collections::HashMap::<u8, u8>::new();
+  Mutex::new(())
+}
+";
+    let diagnostics = crate::lint(source, &config).unwrap();
+    let reports: Vec<_> = diagnostics
+      .iter()
+      .map(|d| (d.message.as_str(), d.start.line, d.start.column))
+      .collect();
+    assert_eq!(
+      reports,
+      [
+        ("use of disallowed type `std::collections::HashMap`", 2, 33),
+        ("use of disallowed type `std::collections::HashMap`", 5, 8),
+        (
+          "use of disallowed type `std::sync::Mutex`: use parking_lot",
+          5,
+          45
+        ),
+        ("use of disallowed type `std::collections::HashMap`", 6, 2),
+        (
+          "use of disallowed type `std::sync::Mutex`: use parking_lot",
+          7,
+          2
+        ),
+      ]
+    );
+    let edit = &diagnostics[1].suggestion.as_ref().unwrap().edits[0];
+    assert_eq!(
+      (
+        edit.start.column,
+        edit.end.column,
+        edit.replacement.as_str()
+      ),
+      (8, 11, "BTreeMap")
+    );
+    assert!(diagnostics[3].suggestion.is_none());
+  }
+}
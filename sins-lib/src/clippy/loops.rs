@@ -0,0 +1,900 @@
+//! Rules reporting loops whose shape a simpler loop, or no loop, would do the
+//! same as.
+
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+use super::matches::block_snippet;
+use super::methods::is_iterator_chain;
+use super::operators::is_pure;
+use super::sugg::{self, has_comments};
+use super::visitors::{bindings, macro_args, mentions};
+use crate::{
+  Applicability, Edit, NodeKind, Report, Rule, RuleContext, Suggestion,
+};
+
+/// Whether `expr` is `name`.
+fn is_ident(expr: &syn::Expr, name: &syn::Ident) -> bool {
+  matches!(expr, syn::Expr::Path(path) if path.path.is_ident(name))
+}
+
+/// Whether `expr` is a `break` without a value out of the loop labelled
+/// `label`, alone or as the only statement of a block.
+fn is_break(expr: &syn::Expr, label: Option<&syn::Label>) -> bool {
+  match expr {
+    syn::Expr::Break(expr_break) => {
+      expr_break.expr.is_none()
+        && expr_break.label.as_ref().is_none_or(|lifetime| {
+          label.is_some_and(|label| label.name == *lifetime)
+        })
+    }
+    syn::Expr::Block(block) if block.label.is_none() => {
+      match &block.block.stmts[..] {
+        [syn::Stmt::Expr(expr) | syn::Stmt::Semi(expr, _)] => {
+          is_break(expr, label)
+        }
+        _ => false,
+      }
+    }
+    _ => false,
+  }
+}
+
+/// The only statement of `block`, as an expression.
+fn single_expr(block: &syn::Block) -> Option<&syn::Expr> {
+  match &block.stmts[..] {
+    [syn::Stmt::Expr(expr) | syn::Stmt::Semi(expr, _)] => Some(expr),
+    _ => None,
+  }
+}
+
+/// Reports `for i in 0..v.len()` loops that only use `i` to index `v`, for
+/// iterating over `v`.
+pub struct NeedlessRangeLoop;
+
+impl<'ast> Rule<'ast> for NeedlessRangeLoop {
+  fn name(&self) -> &'static str {
+    "needless_range_loop"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let syn::Expr::ForLoop(for_loop) = node else {
+      return;
+    };
+    let syn::Pat::Ident(pat) = &for_loop.pat else {
+      return;
+    };
+    let syn::Expr::Range(range) = &*for_loop.expr else {
+      return;
+    };
+    let Some(syn::Expr::MethodCall(len)) = range.to.as_deref() else {
+      return;
+    };
+    if pat.by_ref.is_some()
+      || pat.mutability.is_some()
+      || !matches!(range.limits, syn::RangeLimits::HalfOpen(_))
+      || len.method != "len"
+      || !len.args.is_empty()
+      || !is_pure(&len.receiver)
+    {
+      return;
+    }
+    let mut uses = IndexUses {
+      var: &pat.ident,
+      seq: &len.receiver,
+      indexed: 0,
+      indexed_mut: false,
+      var_used: false,
+      seq_used: false,
+    };
+    uses.visit_block(&for_loop.body);
+    if uses.indexed == 0 || uses.seq_used {
+      return;
+    }
+    let start = range.from.as_deref().filter(|start| {
+      !matches!(
+        start,
+        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(int), .. })
+          if int.base10_digits() == "0"
+      )
+    });
+    let Some(seq) = sugg::snippet(context, &len.receiver, sugg::POSTFIX) else {
+      return;
+    };
+    let iter = if uses.indexed_mut { "iter_mut" } else { "iter" };
+    let header = match (uses.var_used, start) {
+      (false, None) => {
+        let Some(seq) = sugg::snippet(context, &len.receiver, sugg::UNARY)
+        else {
+          return;
+        };
+        let borrow = if uses.indexed_mut { "&mut " } else { "&" };
+        format!("<item> in {}{}", borrow, seq)
+      }
+      (false, Some(start)) => {
+        let Some(start) = context.snippet(start) else {
+          return;
+        };
+        format!("<item> in {}.{}().skip({})", seq, iter, start)
+      }
+      (true, None) => {
+        format!("({}, <item>) in {}.{}().enumerate()", pat.ident, seq, iter)
+      }
+      (true, Some(start)) => {
+        let Some(start) = context.snippet(start) else {
+          return;
+        };
+        format!(
+          "({}, <item>) in {}.{}().enumerate().skip({})",
+          pat.ident, seq, iter, start
+        )
+      }
+    };
+    let only = if uses.var_used { "" } else { "only " };
+    context.emit_report(Report {
+      message: format!(
+        "the loop variable `{}` is {}used to index `{}`; consider `for {}`",
+        pat.ident,
+        only,
+        context.snippet(&*len.receiver).unwrap_or_default(),
+        header
+      ),
+      node,
+      suggestion: None,
+    });
+  }
+}
+
+/// Counts the places a loop body indexes `seq` by `var`, and notes any other
+/// uses of either.
+struct IndexUses<'a> {
+  var: &'a syn::Ident,
+  seq: &'a syn::Expr,
+  indexed: usize,
+  indexed_mut: bool,
+  var_used: bool,
+  seq_used: bool,
+}
+
+impl IndexUses<'_> {
+  fn is_index(&self, expr: &syn::Expr) -> bool {
+    matches!(
+      expr,
+      syn::Expr::Index(index)
+        if *index.expr == *self.seq && is_ident(&index.index, self.var)
+    )
+  }
+}
+
+impl<'ast> Visit<'ast> for IndexUses<'_> {
+  fn visit_expr(&mut self, node: &'ast syn::Expr) {
+    if self.is_index(node) {
+      self.indexed += 1;
+      return;
+    }
+    if node == self.seq {
+      self.seq_used = true;
+      return;
+    }
+    match node {
+      syn::Expr::Assign(assign) if self.is_index(&assign.left) => {
+        self.indexed_mut = true;
+      }
+      syn::Expr::AssignOp(assign) if self.is_index(&assign.left) => {
+        self.indexed_mut = true;
+      }
+      syn::Expr::Reference(reference)
+        if reference.mutability.is_some() && self.is_index(&reference.expr) =>
+      {
+        self.indexed_mut = true;
+      }
+      _ => {}
+    }
+    syn::visit::visit_expr(self, node);
+  }
+
+  fn visit_ident(&mut self, node: &'ast syn::Ident) {
+    if node == self.var {
+      self.var_used = true;
+    }
+  }
+
+  fn visit_macro(&mut self, node: &'ast syn::Macro) {
+    match macro_args(node) {
+      Some(args) => args.iter().for_each(|arg| self.visit_expr(arg)),
+      // Arguments that aren't expressions can't be told apart.
+      None => {
+        self.var_used = true;
+        self.seq_used = true;
+      }
+    }
+  }
+}
+
+/// Reports a `loop` that starts by matching a value and breaking unless it
+/// matches a pattern, for `while let`.
+pub struct WhileLetLoop;
+
+impl<'ast> Rule<'ast> for WhileLetLoop {
+  fn name(&self) -> &'static str {
+    "while_let_loop"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let syn::Expr::Loop(expr_loop) = node else {
+      return;
+    };
+    let label = expr_loop.label.as_ref();
+    let message = "this loop could be written as a `while let` loop";
+    // `loop { if let P = E { .. } else { break } }` and the `match` to that
+    // effect.
+    let (pat, expr, body, body_text): (_, _, &dyn Spanned, _) =
+      match single_expr(&expr_loop.body) {
+        Some(syn::Expr::If(expr_if)) => {
+          let (syn::Expr::Let(expr_let), Some((_, else_branch))) =
+            (&*expr_if.cond, &expr_if.else_branch)
+          else {
+            return;
+          };
+          if !is_break(else_branch, label) {
+            return;
+          }
+          let body = &expr_if.then_branch;
+          let body_text = context.snippet(body).map(str::to_owned);
+          (&expr_let.pat, &*expr_let.expr, body, body_text)
+        }
+        Some(syn::Expr::Match(expr_match)) => {
+          let [arm, other] = &expr_match.arms[..] else {
+            return;
+          };
+          if arm.guard.is_some() || !is_break(&other.body, label) {
+            return;
+          }
+          let body_text = block_snippet(context, &arm.body);
+          (&arm.pat, &*expr_match.expr, &*arm.body, body_text)
+        }
+        _ => {
+          // `let x = match E { P => x, _ => break };` starting a longer body,
+          // which would need the rest of the body moved into the arm.
+          if let Some(syn::Stmt::Local(local)) = expr_loop.body.stmts.first() {
+            if let Some((_, init)) = &local.init {
+              if breaks_unless_matched(init, label) {
+                context.emit_report(Report {
+                  message: message.into(),
+                  node,
+                  suggestion: None,
+                });
+              }
+            }
+          }
+          return;
+        }
+      };
+    let snippets = (
+      context.snippet(pat),
+      // `&&` and `||` would be read as a let chain.
+      sugg::snippet(context, expr, 5),
+      body_text,
+    );
+    let suggestion = match snippets {
+      (Some(pat_text), Some(expr_text), Some(body_text))
+        if !has_comments(context, node, &[pat, expr, body]) =>
+      {
+        Some(Suggestion {
+          message: format!(
+            "try `while let {} = {} {{ .. }}`",
+            pat_text, expr_text
+          ),
+          applicability: Applicability::MachineApplicable,
+          edits: vec![Edit {
+            start: expr_loop.loop_token.span.start().into(),
+            end: node.span().end().into(),
+            replacement: format!(
+              "while let {} = {} {}",
+              pat_text, expr_text, body_text
+            ),
+          }],
+        })
+      }
+      _ => None,
+    };
+    context.emit_report(Report {
+      message: message.into(),
+      node,
+      suggestion,
+    });
+  }
+}
+
+/// Whether `expr` is a `match` or `if let` that breaks out of the loop
+/// labelled `label` unless its first pattern matches.
+fn breaks_unless_matched(expr: &syn::Expr, label: Option<&syn::Label>) -> bool {
+  match expr {
+    syn::Expr::Match(expr_match) => match &expr_match.arms[..] {
+      [arm, other] => arm.guard.is_none() && is_break(&other.body, label),
+      _ => false,
+    },
+    syn::Expr::If(expr_if) => {
+      matches!(&*expr_if.cond, syn::Expr::Let(_))
+        && expr_if
+          .else_branch
+          .as_ref()
+          .is_some_and(|(_, else_branch)| is_break(else_branch, label))
+    }
+    _ => false,
+  }
+}
+
+/// The variable a place expression such as `self.iter` starts from.
+fn root_ident(expr: &syn::Expr) -> Option<&syn::Ident> {
+  match expr {
+    syn::Expr::Path(path) if path.qself.is_none() => path.path.get_ident(),
+    syn::Expr::Field(field) => root_ident(&field.base),
+    _ => None,
+  }
+}
+
+/// Whether `pat` matches every value, as a `for` loop's pattern has to.
+/// Capitalized names are taken to be constants or unit variants.
+fn is_irrefutable(pat: &syn::Pat) -> bool {
+  match pat {
+    syn::Pat::Ident(ident) => {
+      !ident.ident.to_string().starts_with(char::is_uppercase)
+        && ident
+          .subpat
+          .as_ref()
+          .is_none_or(|(_, pat)| is_irrefutable(pat))
+    }
+    syn::Pat::Wild(_) | syn::Pat::Rest(_) => true,
+    syn::Pat::Tuple(tuple) => tuple.elems.iter().all(is_irrefutable),
+    syn::Pat::Reference(reference) => is_irrefutable(&reference.pat),
+    syn::Pat::Type(pat) => is_irrefutable(&pat.pat),
+    _ => false,
+  }
+}
+
+/// Reports `while let Some(x) = iter.next()` loops, for `for x in iter`.
+pub struct WhileLetOnIterator;
+
+impl<'ast> Rule<'ast> for WhileLetOnIterator {
+  fn name(&self) -> &'static str {
+    "while_let_on_iterator"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::File]
+  }
+
+  fn apply_file(&mut self, context: &RuleContext, node: &'ast syn::File) {
+    IteratorLoopFinder { context }.visit_file(node);
+  }
+}
+
+/// Walks a file's blocks for `WhileLetOnIterator`, which needs the statements
+/// around loops to tell whether the iterator can be moved into a `for`.
+struct IteratorLoopFinder<'a> {
+  context: &'a RuleContext,
+}
+
+impl IteratorLoopFinder<'_> {
+  fn check(
+    &self,
+    stmts: &[syn::Stmt],
+    index: usize,
+    expr_while: &syn::ExprWhile,
+  ) {
+    let syn::Expr::Let(expr_let) = &*expr_while.cond else {
+      return;
+    };
+    let (syn::Pat::TupleStruct(some), syn::Expr::MethodCall(next)) =
+      (&expr_let.pat, &*expr_let.expr)
+    else {
+      return;
+    };
+    let Some(root) = root_ident(&next.receiver) else {
+      return;
+    };
+    if !some.path.is_ident("Some")
+      || some.pat.elems.len() != 1
+      || !is_irrefutable(&some.pat.elems[0])
+      || next.method != "next"
+      || !next.args.is_empty()
+      || expr_while
+        .body
+        .stmts
+        .iter()
+        .any(|stmt| mentions(stmt, root))
+    {
+      return;
+    }
+    // The closest `let` before the loop binding the iterator, if it's a
+    // local of this block.
+    let name = root.to_string();
+    let local = stmts[..index].iter().rev().find_map(|stmt| match stmt {
+      syn::Stmt::Local(local) if bindings(&local.pat).contains(&name) => {
+        Some(local)
+      }
+      _ => None,
+    });
+    let context = self.context;
+    // A local unused after the loop can be moved into it.
+    let moved = is_ident(&next.receiver, root)
+      && local.is_some()
+      && !stmts[index + 1..].iter().any(|stmt| mentions(stmt, root));
+    let iter = if moved {
+      context.snippet(&*next.receiver).map(str::to_owned)
+    } else {
+      sugg::snippet(context, &next.receiver, sugg::POSTFIX)
+        .map(|receiver| format!("{}.by_ref()", receiver))
+    };
+    let (Some(pat), Some(iter)) = (context.snippet(&some.pat.elems[0]), iter)
+    else {
+      return;
+    };
+    // Only a local known to hold an iterator is sure to have the `next` of
+    // `Iterator`.
+    let applicability = match local.and_then(|local| local.init.as_ref()) {
+      Some((_, init)) if is_iterator_chain(init) => {
+        Applicability::MachineApplicable
+      }
+      _ => Applicability::MaybeIncorrect,
+    };
+    let replacement = format!("for {} in {}", pat, iter);
+    context.emit_report(Report {
+      message: "this loop could be written as a `for` loop".into(),
+      node: expr_while,
+      suggestion: Some(Suggestion {
+        message: format!("try `{}`", replacement),
+        applicability,
+        edits: vec![Edit {
+          start: expr_while.while_token.span.start().into(),
+          end: expr_while.cond.span().end().into(),
+          replacement,
+        }],
+      }),
+    });
+  }
+}
+
+impl<'ast> Visit<'ast> for IteratorLoopFinder<'_> {
+  fn visit_block(&mut self, node: &'ast syn::Block) {
+    for (index, stmt) in node.stmts.iter().enumerate() {
+      if let syn::Stmt::Expr(syn::Expr::While(expr_while))
+      | syn::Stmt::Semi(syn::Expr::While(expr_while), _) = stmt
+      {
+        self.check(&node.stmts, index, expr_while);
+      }
+    }
+    syn::visit::visit_block(self, node);
+  }
+}
+
+/// Reports `loop {}`, which spins without yielding.
+pub struct EmptyLoop;
+
+impl<'ast> Rule<'ast> for EmptyLoop {
+  fn name(&self) -> &'static str {
+    "empty_loop"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let syn::Expr::Loop(expr_loop) = node else {
+      return;
+    };
+    if expr_loop.body.stmts.is_empty() {
+      context.emit_report(Report {
+        message: "empty `loop {}` wastes CPU cycles".into(),
+        node,
+        suggestion: None,
+      });
+    }
+  }
+}
+
+/// Whether evaluating `expr` always leaves the enclosing loop (or function).
+/// Inner loops and closures, whose `break`s and `return`s stay inside them,
+/// are taken not to.
+fn diverges(expr: &syn::Expr) -> bool {
+  match expr {
+    syn::Expr::Break(_) | syn::Expr::Return(_) => true,
+    syn::Expr::Block(block) if block.label.is_none() => {
+      block_diverges(&block.block)
+    }
+    syn::Expr::Unsafe(block) => block_diverges(&block.block),
+    syn::Expr::Paren(paren) => diverges(&paren.expr),
+    syn::Expr::If(expr_if) => {
+      block_diverges(&expr_if.then_branch)
+        && expr_if
+          .else_branch
+          .as_ref()
+          .is_some_and(|(_, else_branch)| diverges(else_branch))
+    }
+    syn::Expr::Match(expr_match) => {
+      expr_match.arms.iter().all(|arm| diverges(&arm.body))
+    }
+    _ => false,
+  }
+}
+
+fn block_diverges(block: &syn::Block) -> bool {
+  block.stmts.iter().any(|stmt| match stmt {
+    syn::Stmt::Expr(expr) | syn::Stmt::Semi(expr, _) => diverges(expr),
+    _ => false,
+  })
+}
+
+/// Finds a `continue` of the loop labelled `label` whose body is visited.
+struct Continues<'a> {
+  label: Option<&'a syn::Label>,
+  depth: usize,
+  found: bool,
+}
+
+impl<'ast> Visit<'ast> for Continues<'_> {
+  fn visit_expr(&mut self, node: &'ast syn::Expr) {
+    match node {
+      syn::Expr::Loop(_) | syn::Expr::While(_) | syn::Expr::ForLoop(_) => {
+        self.depth += 1;
+        syn::visit::visit_expr(self, node);
+        self.depth -= 1;
+      }
+      syn::Expr::Closure(_) | syn::Expr::Async(_) => {}
+      _ => syn::visit::visit_expr(self, node),
+    }
+  }
+
+  fn visit_expr_continue(&mut self, node: &'ast syn::ExprContinue) {
+    self.found |= match &node.label {
+      Some(lifetime) => self.label.is_some_and(|label| label.name == *lifetime),
+      None => self.depth == 0,
+    };
+  }
+
+  fn visit_item(&mut self, _node: &'ast syn::Item) {}
+}
+
+/// Reports loops whose body always breaks or returns before it can loop.
+pub struct NeverLoop;
+
+impl<'ast> Rule<'ast> for NeverLoop {
+  fn name(&self) -> &'static str {
+    "never_loop"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let (label, body) = match node {
+      syn::Expr::Loop(expr_loop) => (&expr_loop.label, &expr_loop.body),
+      syn::Expr::While(expr_while) => (&expr_while.label, &expr_while.body),
+      syn::Expr::ForLoop(for_loop) => (&for_loop.label, &for_loop.body),
+      _ => return,
+    };
+    if !block_diverges(body) {
+      return;
+    }
+    let mut continues = Continues {
+      label: label.as_ref(),
+      depth: 0,
+      found: false,
+    };
+    continues.visit_block(body);
+    if !continues.found {
+      context.emit_report(Report {
+        message: "this loop never actually loops".into(),
+        node,
+        suggestion: None,
+      });
+    }
+  }
+}
+
+/// Reports `for` loops that only push the same value onto a `Vec`, for
+/// `vec![x; n]` or `resize`.
+pub struct SameItemPush;
+
+impl<'ast> Rule<'ast> for SameItemPush {
+  fn name(&self) -> &'static str {
+    "same_item_push"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let syn::Expr::ForLoop(for_loop) = node else {
+      return;
+    };
+    let Some(syn::Expr::MethodCall(push)) = single_expr(&for_loop.body) else {
+      return;
+    };
+    if push.method != "push" || push.args.len() != 1 || !is_pure(&push.receiver)
+    {
+      return;
+    }
+    // A literal, a constant, or a variable the loop doesn't bind.
+    let names = bindings(&for_loop.pat);
+    let same = match &push.args[0] {
+      syn::Expr::Lit(_) => true,
+      syn::Expr::Path(path) => {
+        path.qself.is_none()
+          && path
+            .path
+            .get_ident()
+            .is_none_or(|ident| !names.contains(&ident.to_string()))
+      }
+      _ => false,
+    };
+    let (Some(item), Some(vec)) = (
+      context.snippet(&push.args[0]),
+      sugg::snippet(context, &push.receiver, sugg::POSTFIX),
+    ) else {
+      return;
+    };
+    if same {
+      context.emit_report(Report {
+        message: format!(
+          "it looks like the same item is being pushed into this `Vec`; \
+           consider `vec![{}; n]` or `{}.resize(n, {})`",
+          item, vec, item
+        ),
+        node: push,
+        suggestion: None,
+      });
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::clippy::fixes;
+  use crate::{Rule, RuleEngine};
+
+  fn messages(
+    rule: impl for<'a> Rule<'a> + 'static,
+    source: &str,
+  ) -> Vec<String> {
+    let mut rule_engine = RuleEngine::with_source(source);
+    rule_engine.add_rule(Box::new(rule));
+    rule_engine
+      .apply_rules(&syn::parse_file(source).unwrap())
+      .into_iter()
+      .map(|d| d.message)
+      .collect()
+  }
+
+  #[test]
+  fn test_needless_range_loop() {
+    let source = "
+fn f(v: Vec<u8>, w: &mut [u8]) {
+  for i in 0..v.len() {
+    g(v[i]);
+  }
+  for i in 1..w.len() {
+    w[i] += 1;
+  }
+  for i in 0..v.len() {
+    println!(\"{} {}\", i, v[i]);
+  }
+  for i in 0..v.len() {
+    g(v[i + 1]);
+  }
+  for i in 0..v.len() {
+    v.push(v[i]);
+  }
+  for i in 0..v.len() {
+    g(i);
+  }
+  for i in 0..=v.len() {
+    g(v[i]);
+  }
+}
+";
+    assert_eq!(
+      fixes("needless_range_loop", source),
+      [(3, "".into()), (6, "".into()), (9, "".into())]
+    );
+    assert_eq!(
+      messages(super::NeedlessRangeLoop, source),
+      [
+        "the loop variable `i` is only used to index `v`; consider \
+         `for <item> in &v`",
+        "the loop variable `i` is only used to index `w`; consider \
+         `for <item> in w.iter_mut().skip(1)`",
+        "the loop variable `i` is used to index `v`; consider \
+         `for (i, <item>) in v.iter().enumerate()`",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_while_let_loop() {
+    let source = "
+fn f(mut it: std::vec::IntoIter<u8>) {
+  loop {
+    if let Some(x) = it.next() {
+      g(x);
+    } else {
+      break;
+    }
+  }
+  'outer: loop {
+    match it.next() {
+      Some(x) => g(x),
+      None => break 'outer,
+    }
+  }
+  loop {
+    let x = match it.next() {
+      Some(x) => x,
+      None => break,
+    };
+    g(x);
+  }
+  loop {
+    if let Some(x) = it.next() {
+      g(x);
+    } else {
+      return;
+    }
+  }
+  loop {
+    match it.next() {
+      None => break,
+      Some(x) => g(x),
+    }
+  }
+}
+";
+    assert_eq!(
+      fixes("while_let_loop", source),
+      [
+        (
+          3,
+          "while let Some(x) = it.next() {\n      g(x);\n    }".into()
+        ),
+        (10, "while let Some(x) = it.next() { g(x) }".into()),
+        (16, "".into()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_while_let_on_iterator() {
+    let source = "
+fn f(v: Vec<u8>, mut it: std::vec::IntoIter<u8>) {
+  let mut chars = s.chars();
+  while let Some(c) = chars.next() {
+    g(c);
+  }
+  let mut iter = v.iter();
+  'a: while let Some(x) = iter.next() {
+    g(x);
+  }
+  iter.len();
+  while let Some(x) = it.next() {
+    g(x);
+  }
+  while let Some(x) = it.next() {
+    it.next();
+  }
+  while let Some(x) = self.iter.next() {
+    g(x);
+  }
+  let mut ones = v.iter();
+  while let Some(&1) = ones.next() {}
+  let mut pairs = v.iter().zip(&v);
+  while let Some((&a, _)) = pairs.next() {
+    g(a);
+  }
+}
+";
+    assert_eq!(
+      fixes("while_let_on_iterator", source),
+      [
+        (4, "for c in chars".into()),
+        (8, "for x in iter.by_ref()".into()),
+        (12, "for x in it.by_ref()".into()),
+        (18, "for x in self.iter.by_ref()".into()),
+        (24, "for (&a, _) in pairs".into()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_empty_loop_and_never_loop() {
+    let source = "
+fn f(v: Vec<u8>) -> u8 {
+  loop {}
+  loop {
+    g();
+    break;
+  }
+  for x in &v {
+    if *x > 1 { return *x; } else { break; }
+  }
+  loop {
+    match g() {
+      Some(x) => return x,
+      None => continue,
+    }
+  }
+  'a: loop {
+    for x in &v {
+      continue 'a;
+    }
+    break;
+  }
+  loop {
+    for x in &v {
+      continue;
+    }
+    let c = || loop { break };
+    break;
+  }
+  while c {
+    if d { break; }
+  }
+  0
+}
+";
+    assert_eq!(fixes("empty_loop", source), [(3, "".into())]);
+    assert_eq!(
+      fixes("never_loop", source),
+      [
+        (4, "".into()),
+        (8, "".into()),
+        (23, "".into()),
+        (27, "".into())
+      ]
+    );
+  }
+
+  #[test]
+  fn test_same_item_push() {
+    let source = "
+fn f(mut v: Vec<u8>, x: u8) {
+  for _ in 0..10 {
+    v.push(0);
+  }
+  for i in 0..10 {
+    v.push(x);
+  }
+  for x in 0..10 {
+    v.push(x);
+  }
+  for _ in 0..10 {
+    v.push(g());
+  }
+  for _ in 0..10 {
+    v.push(1);
+    g();
+  }
+}
+";
+    assert_eq!(
+      fixes("same_item_push", source),
+      [(4, "".into()), (7, "".into())]
+    );
+    assert_eq!(
+      messages(super::SameItemPush, source)[0],
+      "it looks like the same item is being pushed into this `Vec`; consider \
+       `vec![0; n]` or `v.resize(n, 0)`"
+    );
+  }
+}
@@ -0,0 +1,712 @@
+//! Rules reporting `match`es (and `if let`s) that a simpler construct could
+//! replace, or whose arms hide mistakes.
+
+use syn::spanned::Spanned;
+
+use super::sugg::{self, has_comments, report_rewrite, Positions};
+use super::visitors::bindings;
+use crate::{
+  Applicability, Edit, NodeKind, Report, Rule, RuleContext, Suggestion,
+};
+
+fn is_wild(pat: &syn::Pat) -> bool {
+  matches!(pat, syn::Pat::Wild(_))
+}
+
+/// Whether `body` does nothing: `{}` or `()`.
+fn is_unit(body: &syn::Expr) -> bool {
+  match body {
+    syn::Expr::Block(block) => block.block.stmts.is_empty(),
+    syn::Expr::Tuple(tuple) => tuple.elems.is_empty(),
+    _ => false,
+  }
+}
+
+/// The boolean literal `body` evaluates to, possibly in a block.
+fn bool_body(body: &syn::Expr) -> Option<bool> {
+  match body {
+    syn::Expr::Lit(syn::ExprLit {
+      lit: syn::Lit::Bool(lit),
+      ..
+    }) => Some(lit.value),
+    syn::Expr::Block(block) => match block.block.stmts.as_slice() {
+      [syn::Stmt::Expr(expr)] => bool_body(expr),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+/// The boolean literal `pat` matches.
+fn bool_pat(pat: &syn::Pat) -> Option<bool> {
+  match pat {
+    syn::Pat::Lit(lit) => bool_body(&lit.expr),
+    _ => None,
+  }
+}
+
+/// The source of an arm's body as a block.
+pub(crate) fn block_snippet(
+  context: &RuleContext,
+  body: &syn::Expr,
+) -> Option<String> {
+  let text = context.snippet(body)?;
+  Some(match body {
+    syn::Expr::Block(block) if block.label.is_none() => text.to_owned(),
+    _ => format!("{{ {} }}", text),
+  })
+}
+
+/// The `Option` or `Result` method testing for what `pat` matches, if it only
+/// tells variants apart: `Some(_)` for `is_some`.
+fn variant_test(pat: &syn::Pat) -> Option<&'static str> {
+  match pat {
+    syn::Pat::TupleStruct(pat) => {
+      if pat.pat.elems.len() != 1 || !is_wild(&pat.pat.elems[0]) {
+        return None;
+      }
+      match pat.path.get_ident()?.to_string().as_str() {
+        "Some" => Some("is_some"),
+        "Ok" => Some("is_ok"),
+        "Err" => Some("is_err"),
+        _ => None,
+      }
+    }
+    syn::Pat::Ident(ident)
+      if ident.ident == "None" && ident.subpat.is_none() =>
+    {
+      Some("is_none")
+    }
+    syn::Pat::Path(path) if path.path.is_ident("None") => Some("is_none"),
+    _ => None,
+  }
+}
+
+/// Reports a `match` with one arm of interest and a `_` arm that does nothing,
+/// for `if let`.
+pub struct SingleMatch;
+
+impl<'ast> Rule<'ast> for SingleMatch {
+  fn name(&self) -> &'static str {
+    "single_match"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let syn::Expr::Match(expr_match) = node else {
+      return;
+    };
+    let [arm, wild] = &expr_match.arms[..] else {
+      return;
+    };
+    if arm.guard.is_some()
+      || !is_wild(&wild.pat)
+      || !is_unit(&wild.body)
+      || is_unit(&arm.body)
+      || bool_pat(&arm.pat).is_some()
+      || has_comments(context, node, &[&*expr_match.expr, &arm.pat, &*arm.body])
+    {
+      return;
+    }
+    let (Some(pat), Some(expr), Some(body)) = (
+      context.snippet(&arm.pat),
+      // `&&` and `||` would be read as a let chain.
+      sugg::snippet(context, &expr_match.expr, 5),
+      block_snippet(context, &arm.body),
+    ) else {
+      return;
+    };
+    report_rewrite(
+      context,
+      "this `match` has a single arm of interest, use `if let`",
+      node,
+      format!("if let {} = {} {}", pat, expr, body),
+    );
+  }
+}
+
+/// Reports a `match` on `true` and `false`, for `if`. Pedantic.
+pub struct MatchBool;
+
+impl<'ast> Rule<'ast> for MatchBool {
+  fn name(&self) -> &'static str {
+    "match_bool"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let syn::Expr::Match(expr_match) = node else {
+      return;
+    };
+    let [first, second] = &expr_match.arms[..] else {
+      return;
+    };
+    if first.guard.is_some() || second.guard.is_some() {
+      return;
+    }
+    let (then, otherwise) = match (bool_pat(&first.pat), bool_pat(&second.pat))
+    {
+      (Some(true), Some(false)) => (first, second),
+      (Some(false), Some(true)) => (second, first),
+      (Some(value), None) if is_wild(&second.pat) => {
+        if value {
+          (first, second)
+        } else {
+          (second, first)
+        }
+      }
+      _ => return,
+    };
+    let message = "`match` on a boolean expression";
+    let fixable = !has_comments(
+      context,
+      node,
+      &[&*expr_match.expr, &*first.body, &*second.body],
+    );
+    let replacement = fixable
+      .then(|| {
+        let cond = || sugg::snippet(context, &expr_match.expr, 2);
+        let then_body = block_snippet(context, &then.body)?;
+        let else_body = block_snippet(context, &otherwise.body)?;
+        Some(if is_unit(&otherwise.body) {
+          format!("if {} {}", cond()?, then_body)
+        } else if is_unit(&then.body) {
+          let cond = sugg::snippet(context, &expr_match.expr, sugg::UNARY)?;
+          format!("if !{} {}", cond, else_body)
+        } else {
+          format!("if {} {} else {}", cond()?, then_body, else_body)
+        })
+      })
+      .flatten();
+    match replacement {
+      Some(replacement) => report_rewrite(context, message, node, replacement),
+      None => context.emit_report(Report {
+        message: message.into(),
+        node,
+        suggestion: None,
+      }),
+    }
+  }
+}
+
+/// Reports a `match` giving `true` for some patterns and `false` for the rest,
+/// for `matches!`.
+#[derive(Default)]
+pub struct MatchLikeMatchesMacro {
+  positions: Positions,
+}
+
+impl<'ast> Rule<'ast> for MatchLikeMatchesMacro {
+  fn name(&self) -> &'static str {
+    "match_like_matches_macro"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    self.positions.enter(node);
+    let syn::Expr::Match(expr_match) = node else {
+      return;
+    };
+    let Some((last, arms)) = expr_match.arms.split_last() else {
+      return;
+    };
+    let Some(otherwise) = bool_body(&last.body) else {
+      return;
+    };
+    if arms.is_empty()
+      || !is_wild(&last.pat)
+      || last.guard.is_some()
+      || arms.iter().any(|arm| bool_body(&arm.body) != Some(!otherwise))
+      || arms.len() > 1 && arms.iter().any(|arm| arm.guard.is_some())
+      // Left to `redundant_pattern_matching`.
+      || arms.len() == 1 && variant_test(&arms[0].pat).is_some()
+      || has_comments(
+        context,
+        node,
+        &arms
+          .iter()
+          .map(|arm| &arm.pat as &dyn Spanned)
+          .chain([&*expr_match.expr as &dyn Spanned])
+          .chain(arms[0].guard.iter().map(|(_, guard)| &**guard as _))
+          .collect::<Vec<_>>(),
+      )
+    {
+      return;
+    }
+    let Some(expr) = context.snippet(&expr_match.expr) else {
+      return;
+    };
+    let mut pats = vec![];
+    for arm in arms {
+      let Some(pat) = context.snippet(&arm.pat) else {
+        return;
+      };
+      pats.push(pat);
+    }
+    let mut text = format!("matches!({}, {}", expr, pats.join(" | "));
+    if let Some((_, guard)) = &arms[0].guard {
+      let Some(guard) = context.snippet(&**guard) else {
+        return;
+      };
+      text = format!("{} if {}", text, guard);
+    }
+    text.push(')');
+    let (text, precedence) = if otherwise {
+      (format!("!{}", text), sugg::UNARY)
+    } else {
+      (text, sugg::POSTFIX)
+    };
+    report_rewrite(
+      context,
+      "`match` expression looks like `matches!` macro",
+      node,
+      self.positions.fit(node, &text, precedence),
+    );
+  }
+}
+
+/// Reports adjacent `match` arms with identical bodies, which can share one
+/// arm with both patterns. Pedantic. Bindings of the same name may differ in
+/// type, so merging patterns that bind anything isn't applied automatically.
+pub struct MatchSameArms;
+
+impl<'ast> Rule<'ast> for MatchSameArms {
+  fn name(&self) -> &'static str {
+    "match_same_arms"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let syn::Expr::Match(expr_match) = node else {
+      return;
+    };
+    for pair in expr_match.arms.windows(2) {
+      let [first, second] = pair else {
+        continue;
+      };
+      let names = bindings(&first.pat);
+      if first.body != second.body
+        || first.guard.is_some()
+        || second.guard.is_some()
+        || is_wild(&first.pat)
+        || is_wild(&second.pat)
+        || names != bindings(&second.pat)
+      {
+        continue;
+      }
+      let applicability = if names.is_empty() {
+        Applicability::MachineApplicable
+      } else {
+        Applicability::MaybeIncorrect
+      };
+      let suggestion =
+        match (context.snippet(&first.pat), context.snippet(&second.pat)) {
+          (Some(a), Some(b))
+            if !has_comments(context, first, &[&first.pat]) =>
+          {
+            let replacement = format!("{} | {}", a, b);
+            Some(Suggestion {
+              message: format!("try `{}`", replacement),
+              applicability,
+              edits: vec![Edit {
+                start: first.pat.span().start().into(),
+                end: second.pat.span().end().into(),
+                replacement,
+              }],
+            })
+          }
+          _ => None,
+        };
+      context.emit_report(Report {
+        message: "this match arm has an identical body to the previous arm"
+          .into(),
+        node: second,
+        suggestion,
+      });
+    }
+  }
+}
+
+/// Whether `pat` names an enum variant by path, as `Enum::Variant`.
+fn is_variant_path(pat: &syn::Pat) -> bool {
+  let path = match pat {
+    syn::Pat::Path(pat) => &pat.path,
+    syn::Pat::TupleStruct(pat) => &pat.path,
+    syn::Pat::Struct(pat) => &pat.path,
+    syn::Pat::Or(pat) => return pat.cases.iter().all(is_variant_path),
+    _ => return false,
+  };
+  path.segments.len() >= 2
+}
+
+/// Reports `_` arms in `match`es over enum variants, which will silently
+/// match variants added later. Restriction.
+pub struct WildcardEnumMatchArm;
+
+impl<'ast> Rule<'ast> for WildcardEnumMatchArm {
+  fn name(&self) -> &'static str {
+    "wildcard_enum_match_arm"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let syn::Expr::Match(expr_match) = node else {
+      return;
+    };
+    let (wild, variants): (Vec<_>, Vec<_>) =
+      expr_match.arms.iter().partition(|arm| is_wild(&arm.pat));
+    if variants.is_empty()
+      || !variants.iter().all(|arm| is_variant_path(&arm.pat))
+    {
+      return;
+    }
+    for arm in wild {
+      context.emit_report(Report {
+        message: "wildcard match will also match any future added variants"
+          .into(),
+        node: &arm.pat,
+        suggestion: None,
+      });
+    }
+  }
+}
+
+/// Whether `body` only panics.
+fn panics(body: &syn::Expr) -> bool {
+  let is_panic = |mac: &syn::Macro| {
+    mac.path.segments.last().is_some_and(|last| {
+      ["panic", "unreachable", "todo", "unimplemented"]
+        .iter()
+        .any(|name| last.ident == name)
+    })
+  };
+  match body {
+    syn::Expr::Macro(expr) => is_panic(&expr.mac),
+    syn::Expr::Block(block) => match block.block.stmts.as_slice() {
+      [syn::Stmt::Expr(expr)] | [syn::Stmt::Semi(expr, _)] => panics(expr),
+      [syn::Stmt::Item(syn::Item::Macro(item))] => is_panic(&item.mac),
+      _ => false,
+    },
+    _ => false,
+  }
+}
+
+/// Reports `Err(_)` arms that panic, discarding the error that would explain
+/// the panic.
+pub struct MatchWildErrArm;
+
+impl<'ast> Rule<'ast> for MatchWildErrArm {
+  fn name(&self) -> &'static str {
+    "match_wild_err_arm"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let syn::Expr::Match(expr_match) = node else {
+      return;
+    };
+    for arm in &expr_match.arms {
+      if variant_test(&arm.pat) == Some("is_err") && panics(&arm.body) {
+        context.emit_report(Report {
+          message: "`Err(_)` matches all errors, and the panic discards them"
+            .into(),
+          node: &arm.pat,
+          suggestion: None,
+        });
+      }
+    }
+  }
+}
+
+/// Reports `if let Some(_) = x` and the like, and `match`es giving booleans
+/// for such patterns, for `x.is_some()`.
+pub struct RedundantPatternMatching;
+
+impl<'ast> Rule<'ast> for RedundantPatternMatching {
+  fn name(&self) -> &'static str {
+    "redundant_pattern_matching"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let (method, expr) = match node {
+      syn::Expr::If(syn::ExprIf { cond, .. })
+      | syn::Expr::While(syn::ExprWhile { cond, .. }) => {
+        let syn::Expr::Let(expr_let) = &**cond else {
+          return;
+        };
+        let Some(method) = variant_test(&expr_let.pat) else {
+          return;
+        };
+        (method, &*expr_let.expr)
+      }
+      syn::Expr::Match(expr_match) => {
+        let [first, second] = &expr_match.arms[..] else {
+          return;
+        };
+        if first.guard.is_some() || second.guard.is_some() {
+          return;
+        }
+        let (Some(method), Some(value), Some(other)) = (
+          variant_test(&first.pat),
+          bool_body(&first.body),
+          bool_body(&second.body),
+        ) else {
+          return;
+        };
+        let opposite = variant_test(&second.pat).is_some_and(|other| {
+          other != method
+            && matches!(
+              (method, other),
+              ("is_some", "is_none")
+                | ("is_none", "is_some")
+                | ("is_ok", "is_err")
+                | ("is_err", "is_ok")
+            )
+        });
+        if value == other || !(opposite || is_wild(&second.pat)) {
+          return;
+        }
+        let method = if value {
+          method
+        } else {
+          match method {
+            "is_some" => "is_none",
+            "is_none" => "is_some",
+            "is_ok" => "is_err",
+            _ => "is_ok",
+          }
+        };
+        (method, &*expr_match.expr)
+      }
+      _ => return,
+    };
+    let Some(receiver) = sugg::snippet(context, expr, sugg::POSTFIX) else {
+      return;
+    };
+    let replaced: &dyn Spanned = match node {
+      syn::Expr::If(expr_if) => &*expr_if.cond,
+      syn::Expr::While(expr_while) => &*expr_while.cond,
+      _ => node,
+    };
+    report_rewrite(
+      context,
+      format!("redundant pattern matching, consider using `{}()`", method),
+      replaced,
+      format!("{}.{}()", receiver, method),
+    );
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::clippy::{fixes, machine_applicable};
+
+  #[test]
+  fn test_single_match() {
+    let source = "
+fn f(x: Option<u8>) {
+  match x {
+    Some(y) => g(y),
+    _ => {}
+  }
+  match x {
+    Some(y) => {
+      g(y);
+    }
+    _ => (),
+  }
+  match x {
+    Some(y) => g(y),
+    None => {}
+  }
+}
+";
+    assert_eq!(
+      fixes("single_match", source),
+      [
+        (3, "if let Some(y) = x { g(y) }".into()),
+        (7, "if let Some(y) = x {\n      g(y);\n    }".into()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_match_bool() {
+    let source = "
+fn f(a: bool) {
+  match a {
+    true => g(1),
+    false => g(2),
+  }
+  match a && b {
+    false => {}
+    _ => g(2),
+  }
+  match a {
+    false => { g(1); }
+    true => {}
+  }
+}
+";
+    assert_eq!(
+      fixes("match_bool", source),
+      [
+        (3, "if a { g(1) } else { g(2) }".into()),
+        (7, "if a && b { g(2) }".into()),
+        (11, "if !a { g(1); }".into()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_match_like_matches_macro() {
+    let source = "
+fn f(x: E) -> bool {
+  let a = match x {
+    E::A | E::B => true,
+    E::C(_) => true,
+    _ => false,
+  };
+  let b = match x {
+    E::C(n) if n > 1 => false,
+    _ => true,
+  };
+  match x {
+    Some(_) => true,
+    _ => false,
+  }
+}
+";
+    assert_eq!(
+      fixes("match_like_matches_macro", source),
+      [
+        (3, "matches!(x, E::A | E::B | E::C(_))".into()),
+        (8, "!matches!(x, E::C(n) if n > 1)".into()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_match_same_arms() {
+    let source = "
+fn f(x: E) -> u8 {
+  match x {
+    E::A => 1,
+    E::B => 1,
+    E::C(n) => n,
+    E::D(n) => n,
+    E::E(m) => n,
+    _ => 1,
+  }
+}
+fn g(r: Result<u8, E>) -> String {
+  match r {
+    Ok(x) => x.to_string(),
+    Err(x) => x.to_string(),
+  }
+}
+";
+    assert_eq!(
+      fixes("match_same_arms", source),
+      [
+        (5, "E::A | E::B".into()),
+        (7, "E::C(n) | E::D(n)".into()),
+        (15, "Ok(x) | Err(x)".into()),
+      ]
+    );
+    assert_eq!(machine_applicable("match_same_arms", source), [5]);
+  }
+
+  #[test]
+  fn test_wildcard_enum_match_arm() {
+    let source = "
+fn f(x: E, n: u8) {
+  match x {
+    E::A => {}
+    _ => {}
+  }
+  match n {
+    1 => {}
+    _ => {}
+  }
+}
+";
+    assert_eq!(
+      fixes("wildcard_enum_match_arm", source),
+      [(5, String::new())]
+    );
+  }
+
+  #[test]
+  fn test_match_wild_err_arm() {
+    let source = "
+fn f(x: Result<u8, E>) {
+  match x {
+    Ok(n) => g(n),
+    Err(_) => panic!(),
+  }
+  match x {
+    Ok(n) => g(n),
+    Err(_) => {
+      unreachable!(\"no errors\");
+    }
+  }
+  match x {
+    Ok(n) => g(n),
+    Err(_) => g(0),
+  }
+}
+";
+    assert_eq!(
+      fixes("match_wild_err_arm", source),
+      [(5, String::new()), (9, String::new())]
+    );
+  }
+
+  #[test]
+  fn test_redundant_pattern_matching() {
+    let source = "
+fn f(x: Option<u8>, r: Result<u8, E>) {
+  if let Some(_) = x {}
+  while let None = *x {}
+  if let Ok(_) = g() {}
+  let a = match r {
+    Ok(_) => false,
+    Err(_) => true,
+  };
+  if let Some(y) = x {}
+}
+";
+    assert_eq!(
+      fixes("redundant_pattern_matching", source),
+      [
+        (3, "x.is_some()".into()),
+        (4, "(*x).is_none()".into()),
+        (5, "g().is_ok()".into()),
+        (6, "r.is_err()".into()),
+      ]
+    );
+  }
+}
@@ -0,0 +1,742 @@
+//! Rules reporting method-call chains that a shorter chain does the same as.
+
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+use super::sugg::{self, report_replacement, report_rewrite, Positions};
+use super::visitors::{mentions, writes};
+use crate::{
+  Applicability, Edit, NodeKind, Report, Rule, RuleContext, Suggestion,
+};
+
+/// `expr` as a call of the method `name` with `args` arguments.
+fn method_call<'a>(
+  expr: &'a syn::Expr,
+  name: &str,
+  args: usize,
+) -> Option<&'a syn::ExprMethodCall> {
+  match expr {
+    syn::Expr::MethodCall(call)
+      if call.method == name && call.args.len() == args =>
+    {
+      Some(call)
+    }
+    _ => None,
+  }
+}
+
+/// The source of a call's arguments.
+fn args_snippet(
+  context: &RuleContext,
+  call: &syn::ExprMethodCall,
+) -> Option<String> {
+  let args = call
+    .args
+    .iter()
+    .map(|arg| context.snippet(arg))
+    .collect::<Option<Vec<_>>>()?;
+  Some(args.join(", "))
+}
+
+/// Whether `expr` is a chain of method calls that starts by making an
+/// iterator.
+pub(crate) fn is_iterator_chain(expr: &syn::Expr) -> bool {
+  match expr {
+    syn::Expr::MethodCall(call) => {
+      [
+        "iter",
+        "iter_mut",
+        "into_iter",
+        "chars",
+        "bytes",
+        "lines",
+        "keys",
+      ]
+      .iter()
+      .any(|name| call.method == name)
+        || is_iterator_chain(&call.receiver)
+    }
+    _ => false,
+  }
+}
+
+/// Reports `x[a..].iter().next()` for `x.get(a)`, and the like on slices.
+pub struct IterNextSlice;
+
+impl<'ast> Rule<'ast> for IterNextSlice {
+  fn name(&self) -> &'static str {
+    "iter_next_slice"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::ExprMethodCall]
+  }
+
+  fn apply_expr_method_call(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprMethodCall,
+  ) {
+    if node.method != "next" || !node.args.is_empty() {
+      return;
+    }
+    let Some(iter) = method_call(&node.receiver, "iter", 0) else {
+      return;
+    };
+    let replacement = match &*iter.receiver {
+      syn::Expr::Index(index) => {
+        let syn::Expr::Range(range) = &*index.index else {
+          return;
+        };
+        if range.to.is_some() {
+          return;
+        }
+        let Some(slice) = context.snippet(&*index.expr) else {
+          return;
+        };
+        match &range.from {
+          Some(from) => {
+            let Some(from) = context.snippet(&**from) else {
+              return;
+            };
+            format!("{}.get({})", slice, from)
+          }
+          None => format!("{}.first()", slice),
+        }
+      }
+      array @ syn::Expr::Array(_) => {
+        let Some(array) = context.snippet(array) else {
+          return;
+        };
+        format!("{}.first()", array)
+      }
+      _ => return,
+    };
+    report_rewrite(
+      context,
+      "using `.iter().next()` on a slice",
+      node,
+      replacement,
+    );
+  }
+}
+
+/// Reports `x.filter(p).next()` for `x.find(p)`.
+pub struct FilterNext;
+
+impl<'ast> Rule<'ast> for FilterNext {
+  fn name(&self) -> &'static str {
+    "filter_next"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::ExprMethodCall]
+  }
+
+  fn apply_expr_method_call(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprMethodCall,
+  ) {
+    if node.method != "next" || !node.args.is_empty() {
+      return;
+    }
+    let Some(filter) = method_call(&node.receiver, "filter", 1) else {
+      return;
+    };
+    let (Some(receiver), Some(predicate)) = (
+      context.snippet(&*filter.receiver),
+      args_snippet(context, filter),
+    ) else {
+      return;
+    };
+    report_rewrite(
+      context,
+      "called `filter(..).next()` on an iterator, use `find(..)` instead",
+      node,
+      format!("{}.find({})", receiver, predicate),
+    );
+  }
+}
+
+/// Reports `x.map(f).flatten()` for `x.flat_map(f)`. The fix is only
+/// machine-applicable on chains known to be iterators, as `Option` calls it
+/// `and_then`.
+pub struct MapFlatten;
+
+impl<'ast> Rule<'ast> for MapFlatten {
+  fn name(&self) -> &'static str {
+    "map_flatten"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::ExprMethodCall]
+  }
+
+  fn apply_expr_method_call(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprMethodCall,
+  ) {
+    if node.method != "flatten" || !node.args.is_empty() {
+      return;
+    }
+    let Some(map) = method_call(&node.receiver, "map", 1) else {
+      return;
+    };
+    let (Some(receiver), Some(f)) =
+      (context.snippet(&*map.receiver), args_snippet(context, map))
+    else {
+      return;
+    };
+    let applicability = if is_iterator_chain(&map.receiver) {
+      Applicability::MachineApplicable
+    } else {
+      Applicability::MaybeIncorrect
+    };
+    report_replacement(
+      context,
+      "called `map(..).flatten()`, use `flat_map(..)` instead",
+      node,
+      format!("{}.flat_map({})", receiver, f),
+      applicability,
+    );
+  }
+}
+
+/// Whether `expr` returns its argument unchanged: `|x| x` or `identity`.
+fn is_identity(expr: &syn::Expr) -> bool {
+  match expr {
+    syn::Expr::Closure(closure) => {
+      let [syn::Pat::Ident(param)] =
+        &closure.inputs.iter().collect::<Vec<_>>()[..]
+      else {
+        return false;
+      };
+      param.by_ref.is_none()
+        && param.subpat.is_none()
+        && matches!(
+          &*closure.body,
+          syn::Expr::Path(path) if path.path.is_ident(&param.ident)
+        )
+    }
+    syn::Expr::Path(path) => path
+      .path
+      .segments
+      .last()
+      .is_some_and(|last| last.ident == "identity"),
+    _ => false,
+  }
+}
+
+/// Reports `x.filter_map(|x| x)` for `x.flatten()`.
+pub struct FilterMapIdentity;
+
+impl<'ast> Rule<'ast> for FilterMapIdentity {
+  fn name(&self) -> &'static str {
+    "filter_map_identity"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::ExprMethodCall]
+  }
+
+  fn apply_expr_method_call(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprMethodCall,
+  ) {
+    if node.method != "filter_map"
+      || node.args.len() != 1
+      || !is_identity(&node.args[0])
+    {
+      return;
+    }
+    let Some(receiver) = context.snippet(&*node.receiver) else {
+      return;
+    };
+    report_rewrite(
+      context,
+      "use of `filter_map` with an identity function",
+      node,
+      format!("{}.flatten()", receiver),
+    );
+  }
+}
+
+/// Reports `x.collect::<Vec<_>>()` only to take its `len()`, check
+/// `is_empty()` or iterate it again, which the iterator can do without
+/// allocating.
+pub struct NeedlessCollect;
+
+impl<'ast> Rule<'ast> for NeedlessCollect {
+  fn name(&self) -> &'static str {
+    "needless_collect"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::ExprMethodCall]
+  }
+
+  fn apply_expr_method_call(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprMethodCall,
+  ) {
+    let Some(collect) = method_call(&node.receiver, "collect", 0) else {
+      return;
+    };
+    // Other collections, such as sets, may hold fewer items than the
+    // iterator gives.
+    let into_vec = collect.turbofish.as_ref().is_some_and(|turbofish| {
+      matches!(
+        turbofish.args.first(),
+        Some(syn::GenericMethodArgument::Type(syn::Type::Path(path)))
+          if path.path.segments.last().is_some_and(|last| last.ident == "Vec")
+      )
+    });
+    if !into_vec || !node.args.is_empty() {
+      return;
+    }
+    let Some(iter) = context.snippet(&*collect.receiver) else {
+      return;
+    };
+    // Calling `next` on a variable or field needs it to be mutable, and a
+    // lazy iterator may keep borrowing what the collected one copied.
+    let place =
+      matches!(&*collect.receiver, syn::Expr::Path(_) | syn::Expr::Field(_));
+    let (replacement, applicability) = match node.method.to_string().as_str() {
+      "len" => (
+        format!("{}.count()", iter),
+        Applicability::MachineApplicable,
+      ),
+      "is_empty" => (
+        format!("{}.next().is_none()", iter),
+        if place {
+          Applicability::MaybeIncorrect
+        } else {
+          Applicability::MachineApplicable
+        },
+      ),
+      "into_iter" => (iter.to_owned(), Applicability::MaybeIncorrect),
+      _ => return,
+    };
+    report_replacement(
+      context,
+      "avoid using `collect()` when not needed",
+      node,
+      replacement,
+      applicability,
+    );
+  }
+}
+
+/// Reports `x.iter().count()` for `x.len()`. As `x`'s type isn't known, the
+/// fix isn't machine-applicable.
+pub struct IterCount;
+
+impl<'ast> Rule<'ast> for IterCount {
+  fn name(&self) -> &'static str {
+    "iter_count"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::ExprMethodCall]
+  }
+
+  fn apply_expr_method_call(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprMethodCall,
+  ) {
+    if node.method != "count" || !node.args.is_empty() {
+      return;
+    }
+    let Some(iter) = ["iter", "iter_mut", "into_iter"]
+      .iter()
+      .find_map(|name| method_call(&node.receiver, name, 0))
+    else {
+      return;
+    };
+    let Some(receiver) = context.snippet(&*iter.receiver) else {
+      return;
+    };
+    report_replacement(
+      context,
+      format!("called `.{}().count()`, use `.len()` instead", iter.method),
+      node,
+      format!("{}.len()", receiver),
+      Applicability::MaybeIncorrect,
+    );
+  }
+}
+
+/// Whether `expr` is a string or character literal, which `str::find` takes.
+fn is_str_pattern(expr: &syn::Expr) -> bool {
+  matches!(
+    expr,
+    syn::Expr::Lit(syn::ExprLit {
+      lit: syn::Lit::Str(_) | syn::Lit::Char(_),
+      ..
+    })
+  )
+}
+
+/// Reports `x.find(p).is_some()` and the like for `x.any(p)`, or for
+/// `s.contains(p)` on strings.
+#[derive(Default)]
+pub struct SearchIsSome {
+  positions: Positions,
+}
+
+impl<'ast> Rule<'ast> for SearchIsSome {
+  fn name(&self) -> &'static str {
+    "search_is_some"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr, NodeKind::ExprMethodCall]
+  }
+
+  fn apply_expr(&mut self, _context: &RuleContext, node: &'ast syn::Expr) {
+    self.positions.enter(node);
+  }
+
+  fn apply_expr_method_call(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprMethodCall,
+  ) {
+    let (negation, precedence) = match node.method.to_string().as_str() {
+      "is_some" => ("", sugg::POSTFIX),
+      "is_none" => ("!", sugg::UNARY),
+      _ => return,
+    };
+    if !node.args.is_empty() {
+      return;
+    }
+    let Some(search) = ["find", "position", "rposition"]
+      .iter()
+      .find_map(|name| method_call(&node.receiver, name, 1))
+    else {
+      return;
+    };
+    let (Some(receiver), Some(arg)) = (
+      context.snippet(&*search.receiver),
+      context.snippet(&search.args[0]),
+    ) else {
+      return;
+    };
+    // `find`'s predicate takes a reference to each item, where `any`'s takes
+    // the item.
+    let (method, applicability) = if is_str_pattern(&search.args[0]) {
+      ("contains", Applicability::MachineApplicable)
+    } else if search.method == "find" {
+      ("any", Applicability::MaybeIncorrect)
+    } else {
+      ("any", Applicability::MachineApplicable)
+    };
+    let text = format!("{}{}.{}({})", negation, receiver, method, arg);
+    report_replacement(
+      context,
+      format!(
+        "called `{}(..).{}()`, use `{}{}(..)` instead",
+        search.method, node.method, negation, method
+      ),
+      node,
+      self.positions.fit(node, &text, precedence),
+      applicability,
+    );
+  }
+}
+
+/// Whether `stmt` is `name += 1;`.
+fn is_increment(stmt: &syn::Stmt, name: &syn::Ident) -> bool {
+  let syn::Stmt::Semi(syn::Expr::AssignOp(assign), _) = stmt else {
+    return false;
+  };
+  matches!(assign.op, syn::BinOp::AddEq(_))
+    && matches!(&*assign.left, syn::Expr::Path(path) if path.path.is_ident(name))
+    && matches!(
+      &*assign.right,
+      syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(int), .. })
+        if int.base10_digits() == "1"
+    )
+}
+
+/// `let mut name = 0;`, giving `name`.
+fn counter(stmt: &syn::Stmt) -> Option<&syn::Ident> {
+  let syn::Stmt::Local(local) = stmt else {
+    return None;
+  };
+  let syn::Pat::Ident(pat) = &local.pat else {
+    return None;
+  };
+  let (_, init) = local.init.as_ref()?;
+  let zero = matches!(
+    &**init,
+    syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(int), .. })
+      if int.base10_digits() == "0"
+        && matches!(int.suffix(), "" | "usize")
+  );
+  (zero && pat.mutability.is_some() && pat.by_ref.is_none())
+    .then_some(&pat.ident)
+}
+
+/// Reports `for` loops counting their iterations in a variable incremented at
+/// the end of the body, for `.enumerate()`.
+pub struct ExplicitCounterLoop;
+
+impl<'ast> Rule<'ast> for ExplicitCounterLoop {
+  fn name(&self) -> &'static str {
+    "explicit_counter_loop"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::File]
+  }
+
+  fn apply_file(&mut self, context: &RuleContext, node: &'ast syn::File) {
+    CounterFinder { context }.visit_file(node);
+  }
+}
+
+/// Walks a file's blocks for `ExplicitCounterLoop`, which needs the
+/// statements around loops.
+struct CounterFinder<'a> {
+  context: &'a RuleContext,
+}
+
+impl CounterFinder<'_> {
+  fn check(
+    &self,
+    stmts: &[syn::Stmt],
+    index: usize,
+    for_loop: &syn::ExprForLoop,
+  ) {
+    let body = &for_loop.body.stmts;
+    let Some((last, rest)) = body.split_last() else {
+      return;
+    };
+    // The counter declared closest before the loop, unused in between and
+    // after, and in the body only incremented at its end.
+    let Some((local, name)) = stmts[..index]
+      .iter()
+      .enumerate()
+      .rev()
+      .find_map(|(i, stmt)| counter(stmt).map(|name| (i, name)))
+    else {
+      return;
+    };
+    let mentioned = |stmt: &syn::Stmt| mentions(stmt, name);
+    if !is_increment(last, name)
+      || stmts[local + 1..index].iter().any(mentioned)
+      || stmts[index + 1..].iter().any(mentioned)
+      || rest.iter().any(|stmt| writes(stmt, name))
+      || matches!(&for_loop.pat, syn::Pat::Ident(pat) if pat.ident == *name)
+    {
+      return;
+    }
+    let context = self.context;
+    let (Some(pat), Some(iter)) = (
+      context.snippet(&for_loop.pat),
+      self.iter_snippet(&for_loop.expr),
+    ) else {
+      return;
+    };
+    let replacement = format!("({}, {}) in {}.enumerate()", name, pat, iter);
+    context.emit_report(Report {
+      message: format!("the variable `{}` is used as a loop counter", name),
+      node: for_loop,
+      suggestion: Some(Suggestion {
+        message: format!("try `for {}`", replacement),
+        applicability: Applicability::MaybeIncorrect,
+        edits: vec![
+          Edit::replace(&stmts[local], ""),
+          Edit {
+            start: for_loop.pat.span().start().into(),
+            end: for_loop.expr.span().end().into(),
+            replacement,
+          },
+          Edit::replace(last, ""),
+        ],
+      }),
+    });
+  }
+
+  /// The source of `expr` as an iterator to call `enumerate` on.
+  fn iter_snippet(&self, expr: &syn::Expr) -> Option<String> {
+    match expr {
+      syn::Expr::MethodCall(_) => self.context.snippet(expr).map(str::to_owned),
+      syn::Expr::Reference(reference) => {
+        let method = if reference.mutability.is_some() {
+          "iter_mut"
+        } else {
+          "iter"
+        };
+        let receiver =
+          sugg::snippet(self.context, &reference.expr, sugg::POSTFIX)?;
+        Some(format!("{}.{}()", receiver, method))
+      }
+      syn::Expr::Range(_) => sugg::snippet(self.context, expr, sugg::POSTFIX),
+      _ => {
+        let receiver = sugg::snippet(self.context, expr, sugg::POSTFIX)?;
+        Some(format!("{}.into_iter()", receiver))
+      }
+    }
+  }
+}
+
+impl<'ast> Visit<'ast> for CounterFinder<'_> {
+  fn visit_block(&mut self, node: &'ast syn::Block) {
+    for (index, stmt) in node.stmts.iter().enumerate() {
+      if let syn::Stmt::Expr(syn::Expr::ForLoop(for_loop))
+      | syn::Stmt::Semi(syn::Expr::ForLoop(for_loop), _) = stmt
+      {
+        self.check(&node.stmts, index, for_loop);
+      }
+    }
+    syn::visit::visit_block(self, node);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::clippy::{fixes, machine_applicable};
+
+  #[test]
+  fn test_iter_next_slice() {
+    let source = "
+fn f(v: Vec<u8>) {
+  v[2..].iter().next();
+  v[..].iter().next();
+  [1, 2].iter().next();
+  v.iter().next();
+  v[..2].iter().next();
+}
+";
+    assert_eq!(
+      fixes("iter_next_slice", source),
+      [
+        (3, "v.get(2)".into()),
+        (4, "v.first()".into()),
+        (5, "[1, 2].first()".into()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_filter_next_and_map_flatten() {
+    let source = "
+fn f(v: Vec<u8>) {
+  v.iter().filter(|x| **x > 1).next();
+  v.iter().map(|x| g(x)).flatten();
+  v.into_iter().filter_map(|x| x);
+  v.into_iter().filter_map(std::convert::identity);
+  v.into_iter().filter_map(|x| x.ok());
+}
+";
+    assert_eq!(
+      fixes("filter_next", source),
+      [(3, "v.iter().find(|x| **x > 1)".into())]
+    );
+    assert_eq!(
+      fixes("map_flatten", source),
+      [(4, "v.iter().flat_map(|x| g(x))".into())]
+    );
+    assert_eq!(
+      fixes("filter_map_identity", source),
+      [
+        (5, "v.into_iter().flatten()".into()),
+        (6, "v.into_iter().flatten()".into()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_needless_collect_and_iter_count() {
+    let source = "
+fn f(v: Vec<u8>) {
+  v.iter().map(g).collect::<Vec<_>>().len();
+  v.iter().collect::<Vec<_>>().is_empty();
+  v.iter().collect::<Vec<_>>().into_iter();
+  v.iter().collect::<HashSet<_>>().len();
+  v.iter().count();
+  v.chars().count();
+  it.collect::<Vec<_>>().is_empty();
+}
+";
+    assert_eq!(
+      fixes("needless_collect", source),
+      [
+        (3, "v.iter().map(g).count()".into()),
+        (4, "v.iter().next().is_none()".into()),
+        (5, "v.iter()".into()),
+        (9, "it.next().is_none()".into()),
+      ]
+    );
+    assert_eq!(machine_applicable("needless_collect", source), [3, 4]);
+    assert_eq!(fixes("iter_count", source), [(7, "v.len()".into())]);
+  }
+
+  #[test]
+  fn test_search_is_some() {
+    let source = "
+fn f(v: Vec<u8>, s: &str) {
+  v.iter().find(|x| **x == 1).is_some();
+  v.iter().position(|x| *x == 1).is_none();
+  s.find('a').is_some();
+  v.iter().find(|x| **x == 1).unwrap();
+  v.iter().position(|x| *x == 1).is_none().then(|| 1);
+  v.iter().position(|x| *x == 1).is_some().then(|| 1);
+}
+";
+    assert_eq!(
+      fixes("search_is_some", source),
+      [
+        (3, "v.iter().any(|x| **x == 1)".into()),
+        (4, "!v.iter().any(|x| *x == 1)".into()),
+        (5, "s.contains('a')".into()),
+        (7, "(!v.iter().any(|x| *x == 1))".into()),
+        (8, "v.iter().any(|x| *x == 1)".into()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_explicit_counter_loop() {
+    let source = "
+fn f(v: Vec<u8>) {
+  let mut i = 0;
+  for x in &v {
+    g(i, x);
+    i += 1;
+  }
+  let mut j = 0;
+  for x in v.iter() {
+    if x > 1 {
+      continue;
+    }
+    j += 1;
+  }
+  let mut k = 0;
+  for x in 0..10 {
+    k += 1;
+  }
+  g(k);
+}
+";
+    let config =
+      crate::config::Config::from_toml("[rules]\ndisable = []").unwrap();
+    let diagnostics: Vec<_> = crate::lint(source, &config)
+      .unwrap()
+      .into_iter()
+      .filter(|d| d.rule == "explicit_counter_loop")
+      .collect();
+    assert_eq!(diagnostics.len(), 1);
+    let edits = &diagnostics[0].suggestion.as_ref().unwrap().edits;
+    assert_eq!(edits[0].start.line, 3);
+    assert_eq!(edits[1].replacement, "(i, x) in v.iter().enumerate()");
+    assert_eq!(edits[2].start.line, 6);
+  }
+}
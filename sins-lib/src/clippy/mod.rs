@@ -1,19 +1,267 @@
 // https://rust-lang.github.io/rust-clippy/v0.0.212/
 
-struct AbsurdExtremeComparisons;
+pub mod booleans;
+pub mod casts;
+pub mod control_flow;
+pub mod disallowed;
+pub mod loops;
+pub mod matches;
+pub mod methods;
+pub mod operators;
+pub mod panic;
+mod sugg;
+pub(crate) mod visitors;
+
+/// All built-in rules, freshly instantiated.
+pub fn rules<'ast>() -> Vec<Box<dyn crate::Rule<'ast>>> {
+  use casts::{Cast, CastLint};
+  vec![
+    Box::new(AbsurdExtremeComparisons::default()),
+    Box::new(Cast::new(CastLint::PossibleTruncation)),
+    Box::new(Cast::new(CastLint::SignLoss)),
+    Box::new(Cast::new(CastLint::PossibleWrap)),
+    Box::new(Cast::new(CastLint::PrecisionLoss)),
+    Box::new(booleans::BoolComparison::default()),
+    Box::new(booleans::NonminimalBool::default()),
+    Box::new(operators::EqOp::default()),
+    Box::new(operators::DoubleComparisons::default()),
+    Box::new(operators::IdentityOp::default()),
+    Box::new(operators::FloatCmp::default()),
+    Box::new(operators::CmpNan::default()),
+    Box::new(operators::FloatEqualityWithoutAbs),
+    Box::new(operators::ZeroDividedByZero),
+    Box::new(matches::SingleMatch),
+    Box::new(matches::MatchLikeMatchesMacro::default()),
+    Box::new(matches::MatchWildErrArm),
+    Box::new(matches::RedundantPatternMatching),
+    Box::new(methods::IterNextSlice),
+    Box::new(methods::FilterNext),
+    Box::new(methods::MapFlatten),
+    Box::new(methods::FilterMapIdentity),
+    Box::new(methods::NeedlessCollect),
+    Box::new(methods::IterCount),
+    Box::new(methods::SearchIsSome::default()),
+    Box::new(methods::ExplicitCounterLoop),
+    Box::new(loops::NeedlessRangeLoop),
+    Box::new(loops::WhileLetLoop),
+    Box::new(loops::WhileLetOnIterator),
+    Box::new(loops::EmptyLoop),
+    Box::new(loops::NeverLoop),
+    Box::new(loops::SameItemPush),
+    Box::new(control_flow::NeedlessReturn),
+    Box::new(control_flow::NeedlessContinue),
+    Box::new(control_flow::NeedlessBool::default()),
+    Box::new(control_flow::CollapsibleIf),
+    Box::new(control_flow::CollapsibleElseIf),
+    Box::new(control_flow::IfSameThenElse::default()),
+    Box::new(operators::NegCmpOpOnPartialOrd::default()),
+  ]
+}
+
+/// Built-in rules that are off unless `pedantic` is set, freshly
+/// instantiated.
+pub fn pedantic_rules<'ast>() -> Vec<Box<dyn crate::Rule<'ast>>> {
+  vec![
+    Box::new(casts::Cast::new(casts::CastLint::UnknownSource)),
+    Box::new(matches::MatchBool),
+    Box::new(matches::MatchSameArms),
+  ]
+}
+
+/// Built-in rules that are off unless listed in `restriction`, freshly
+/// instantiated.
+pub fn restriction_rules<'ast>(
+  allow_panics_in_main: bool,
+) -> Vec<Box<dyn crate::Rule<'ast>>> {
+  let mut rules: Vec<Box<dyn crate::Rule<'ast>>> = vec![
+    Box::new(panic::UnwrapUsed {
+      allow_in_main: allow_panics_in_main,
+    }),
+    Box::new(panic::ExpectUsed {
+      allow_in_main: allow_panics_in_main,
+    }),
+    Box::new(panic::IndexingSlicing {
+      allow_in_main: allow_panics_in_main,
+    }),
+    Box::new(matches::WildcardEnumMatchArm),
+  ];
+  for lint in panic::PANIC_MACROS {
+    rules.push(Box::new(panic::PanicMacro {
+      lint,
+      allow_in_main: allow_panics_in_main,
+    }));
+  }
+  rules
+}
+
+/// Lints `source` with only the built-in rule `rule`, whether default,
+/// pedantic or restriction, giving its diagnostics by line.
+#[cfg(test)]
+pub(crate) fn lint_with(rule: &str, source: &str) -> Vec<crate::Diagnostic> {
+  let restriction = restriction_rules(false);
+  let disable: Vec<_> = rules()
+    .iter()
+    .chain(&pedantic_rules())
+    .chain(&restriction)
+    .map(|r| r.name())
+    .filter(|&name| name != rule)
+    .map(|name| format!("{:?}", name))
+    .collect();
+  let enable = if restriction.iter().any(|r| r.name() == rule) {
+    format!("{:?}", rule)
+  } else {
+    String::new()
+  };
+  let config = crate::config::Config::from_toml(&format!(
+    "[rules]\ndisable = [{}]\npedantic = true\nrestriction = [{}]",
+    disable.join(", "),
+    enable
+  ))
+  .unwrap();
+  let mut diagnostics = crate::lint(source, &config).unwrap();
+  diagnostics.sort_by_key(|d| d.start.line);
+  diagnostics
+}
+
+/// The line and first suggested replacement (or nothing) of each diagnostic
+/// of `rule` in `source`, by line.
+#[cfg(test)]
+pub(crate) fn fixes(rule: &str, source: &str) -> Vec<(usize, String)> {
+  lint_with(rule, source)
+    .into_iter()
+    .map(|d| {
+      let suggestion = d.suggestion.map(|s| s.edits[0].replacement.clone());
+      (d.start.line, suggestion.unwrap_or_default())
+    })
+    .collect()
+}
+
+/// The line of each diagnostic of `rule` in `source` with a suggestion
+/// `sins fix` would apply, by line.
+#[cfg(test)]
+pub(crate) fn machine_applicable(rule: &str, source: &str) -> Vec<usize> {
+  lint_with(rule, source)
+    .into_iter()
+    .filter(|d| {
+      d.suggestion.as_ref().is_some_and(|s| {
+        s.applicability == crate::Applicability::MachineApplicable
+      })
+    })
+    .map(|d| d.start.line)
+    .collect()
+}
+
+/// The smallest or largest value of an integer type.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Extreme {
+  Min,
+  Max,
+}
+
+/// Which extreme of `ty` the integer `expr` is, if it's `0` for an unsigned
+/// type or a constant like `u8::MAX`.
+fn extreme(expr: &syn::Expr, ty: crate::types::NumType) -> Option<Extreme> {
+  match expr {
+    syn::Expr::Lit(syn::ExprLit {
+      lit: syn::Lit::Int(int),
+      ..
+    }) => {
+      let zero = int.base10_digits() == "0"
+        && (int.suffix().is_empty() || int.suffix() == ty.name());
+      (zero && !ty.is_signed()).then_some(Extreme::Min)
+    }
+    syn::Expr::Path(path) if path.qself.is_none() => {
+      let mut segments = path.path.segments.iter().rev();
+      let extreme = match segments.next()?.ident.to_string().as_str() {
+        "MIN" => Extreme::Min,
+        "MAX" => Extreme::Max,
+        _ => return None,
+      };
+      (segments.next()?.ident == ty.name()).then_some(extreme)
+    }
+    syn::Expr::Paren(paren) => extreme(&paren.expr, ty),
+    _ => None,
+  }
+}
+
+/// Reports comparisons with the minimum or maximum of an integer type that
+/// are always true or false, such as `x < 0` for unsigned `x`, or that can
+/// only hold with equality, such as `x >= u8::MAX`.
+#[derive(Default)]
+pub struct AbsurdExtremeComparisons {
+  types: std::rc::Rc<crate::types::LocalTypes>,
+}
+
 impl<'ast> crate::Rule<'ast> for AbsurdExtremeComparisons {
+  fn name(&self) -> &'static str {
+    "absurd_extreme_comparisons"
+  }
+
+  fn node_kinds(&self) -> &'static [crate::NodeKind] {
+    &[crate::NodeKind::File, crate::NodeKind::ExprBinary]
+  }
+
+  fn apply_file(
+    &mut self,
+    context: &crate::RuleContext,
+    node: &'ast syn::File,
+  ) {
+    self.types = context.types(node);
+  }
+
   fn apply_expr_binary(
     &mut self,
     context: &crate::RuleContext,
     node: &'ast syn::ExprBinary,
   ) {
-    // let left = node.left;
-    // match node.op {
-    //   syn::BinOp::Le(_) => {
-    //     node.left
-    //   }
-    //   _ => {}
-    // }
+    let (left, right) = (&*node.left, &*node.right);
+    let Some(ty) = self
+      .types
+      .type_of(left)
+      .or_else(|| self.types.type_of(right))
+      .filter(|ty| !ty.is_float())
+    else {
+      return;
+    };
+    // Written as `x op extreme`, flipping the comparison if the extreme is
+    // on the left.
+    let (extreme, less, equal) = match (extreme(left, ty), extreme(right, ty)) {
+      (None, Some(extreme)) => match node.op {
+        syn::BinOp::Lt(_) => (extreme, true, false),
+        syn::BinOp::Le(_) => (extreme, true, true),
+        syn::BinOp::Gt(_) => (extreme, false, false),
+        syn::BinOp::Ge(_) => (extreme, false, true),
+        _ => return,
+      },
+      (Some(extreme), None) => match node.op {
+        syn::BinOp::Lt(_) => (extreme, false, false),
+        syn::BinOp::Le(_) => (extreme, false, true),
+        syn::BinOp::Gt(_) => (extreme, true, false),
+        syn::BinOp::Ge(_) => (extreme, true, true),
+        _ => return,
+      },
+      _ => return,
+    };
+    let (bound, outside) = match extreme {
+      Extreme::Min => ("minimum", less),
+      Extreme::Max => ("maximum", !less),
+    };
+    let outcome = match (outside, equal) {
+      (true, false) => "is always false",
+      (true, true) => "is the same as `==`",
+      (false, true) => "is always true",
+      (false, false) => "is the same as `!=`",
+    };
+    context.emit_report(crate::Report {
+      message: format!(
+        "this comparison with the {} of `{}` {}",
+        bound,
+        ty.name(),
+        outcome
+      ),
+      node,
+      suggestion: None,
+    });
   }
 }
 
@@ -24,18 +272,59 @@ mod tests {
 
   #[test]
   fn test_absurd_extreme_comparisons() {
-    let mut content = "
-    fn main() {
-      const Z: u32 = 0;
-      let u: u32 = 42;
-      u <= 0;
-      u <= Z;
-    }
-    ";
-    let syntax_tree = syn::parse_file(&content).unwrap();
-    println!("{:#?}", syntax_tree);
-    let mut rule_engine = crate::RuleEngine::new();
-    rule_engine.add_rule(Box::new(AbsurdExtremeComparisons {}));
-    rule_engine.apply_rules(&syntax_tree);
+    let source = "
+fn f(u: u32, i: i8, x: f64) {
+  u < 0;
+  u <= 0;
+  0 >= u;
+  u > 0;
+  i < 0;
+  i > i8::MAX;
+  i <= std::i8::MAX;
+  i8::MIN == i;
+  u32::MAX > u;
+  x <= f64::MAX;
+  y < u8::MAX;
+}
+";
+    let messages: Vec<_> = lint_with("absurd_extreme_comparisons", source)
+      .into_iter()
+      .map(|d| (d.start.line, d.message))
+      .collect();
+    let report = |line, message: &str| (line, message.to_owned());
+    assert_eq!(
+      messages,
+      [
+        report(
+          3,
+          "this comparison with the minimum of `u32` is always false"
+        ),
+        report(
+          4,
+          "this comparison with the minimum of `u32` is the same as `==`"
+        ),
+        report(
+          5,
+          "this comparison with the minimum of `u32` is the same as `==`"
+        ),
+        report(
+          6,
+          "this comparison with the minimum of `u32` is the same as `!=`"
+        ),
+        report(
+          8,
+          "this comparison with the maximum of `i8` is always false"
+        ),
+        report(9, "this comparison with the maximum of `i8` is always true"),
+        report(
+          11,
+          "this comparison with the maximum of `u32` is the same as `!=`"
+        ),
+        report(
+          13,
+          "this comparison with the maximum of `u8` is the same as `!=`"
+        ),
+      ]
+    );
   }
 }
@@ -0,0 +1,868 @@
+//! Rules reporting binary operations that are redundant or always give the
+//! same result.
+
+use std::rc::Rc;
+
+use syn::visit::Visit;
+
+use super::sugg::{self, report_replacement, report_rewrite, Positions};
+use crate::types::{LocalTypes, NumType};
+use crate::{
+  Applicability, Edit, NodeKind, Report, Rule, RuleContext, Suggestion,
+};
+
+/// Whether evaluating `expr` twice gives the same value as far as can be told:
+/// it calls nothing, expands no macros and assigns nothing.
+pub(crate) fn is_pure(expr: &syn::Expr) -> bool {
+  struct Impure(bool);
+  impl<'ast> Visit<'ast> for Impure {
+    fn visit_expr(&mut self, node: &'ast syn::Expr) {
+      match node {
+        syn::Expr::Call(_)
+        | syn::Expr::MethodCall(_)
+        | syn::Expr::Macro(_)
+        | syn::Expr::Assign(_)
+        | syn::Expr::AssignOp(_)
+        | syn::Expr::Await(_)
+        | syn::Expr::Yield(_) => self.0 = true,
+        _ => syn::visit::visit_expr(self, node),
+      }
+    }
+  }
+  let mut impure = Impure(false);
+  impure.visit_expr(expr);
+  !impure.0
+}
+
+/// Whether `expr` is known to evaluate to a value rather than a reference, so
+/// that a rewrite dropping the operation around it keeps its type: a number
+/// of known type, or the result of an operator, a cast or a method asking a
+/// yes-or-no question.
+pub(crate) fn is_value(types: &LocalTypes, expr: &syn::Expr) -> bool {
+  match expr {
+    syn::Expr::Lit(_)
+    | syn::Expr::Binary(_)
+    | syn::Expr::Cast(_)
+    | syn::Expr::Unary(syn::ExprUnary {
+      op: syn::UnOp::Not(_) | syn::UnOp::Neg(_),
+      ..
+    }) => true,
+    syn::Expr::Paren(paren) => is_value(types, &paren.expr),
+    syn::Expr::MethodCall(call) => {
+      let method = call.method.to_string();
+      method.starts_with("is_")
+        || method.starts_with("has_")
+        || [
+          "contains",
+          "starts_with",
+          "ends_with",
+          "any",
+          "all",
+          "eq",
+          "ne",
+        ]
+        .contains(&method.as_str())
+    }
+    _ => types.type_of(expr).is_some(),
+  }
+}
+
+/// Machine-applicable if `operand` is known to be a value, as a rewrite to a
+/// reference would change the type.
+fn applicability(types: &LocalTypes, operand: &syn::Expr) -> Applicability {
+  if is_value(types, operand) {
+    Applicability::MachineApplicable
+  } else {
+    Applicability::MaybeIncorrect
+  }
+}
+
+fn op_str(op: &syn::BinOp) -> String {
+  quote::ToTokens::to_token_stream(op).to_string()
+}
+
+/// Reports binary operations with equal operands, such as `a == a` or
+/// `x - x`, which are always the same or a mistake. `&&`, `||`, `&` and `|`
+/// are rewritten to their operand.
+#[derive(Default)]
+pub struct EqOp {
+  positions: Positions,
+  types: Rc<LocalTypes>,
+}
+
+impl<'ast> Rule<'ast> for EqOp {
+  fn name(&self) -> &'static str {
+    "eq_op"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::File, NodeKind::Expr, NodeKind::ExprBinary]
+  }
+
+  fn apply_file(&mut self, context: &RuleContext, node: &'ast syn::File) {
+    self.types = context.types(node);
+  }
+
+  fn apply_expr(&mut self, _context: &RuleContext, node: &'ast syn::Expr) {
+    self.positions.enter(node);
+  }
+
+  fn apply_expr_binary(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprBinary,
+  ) {
+    use syn::BinOp::*;
+    // `&&` and `||` take and give `bool`s, but `&` and `|` give a value for
+    // references too.
+    let idempotent = match node.op {
+      And(_) | Or(_) => Some(Applicability::MachineApplicable),
+      BitAnd(_) | BitOr(_) => Some(applicability(&self.types, &node.left)),
+      Eq(_) | Ne(_) | Lt(_) | Le(_) | Gt(_) | Ge(_) | BitXor(_) | Sub(_)
+      | Div(_) => None,
+      _ => return,
+    };
+    if node.left != node.right || !is_pure(&node.left) {
+      return;
+    }
+    let message =
+      format!("equal expressions as operands to `{}`", op_str(&node.op));
+    let text = context.snippet(&*node.left);
+    match (text, idempotent) {
+      (Some(text), Some(applicability)) => {
+        let replacement =
+          self.positions.fit(node, text, sugg::precedence(&node.left));
+        report_replacement(context, message, node, replacement, applicability);
+      }
+      _ => context.emit_report(Report {
+        message,
+        node,
+        suggestion: None,
+      }),
+    }
+  }
+}
+
+/// A comparison `left op right`, with `op` as written if the operands are in
+/// the order of `left` and `right`, or mirrored.
+fn comparison(
+  expr: &syn::Expr,
+  left: &syn::Expr,
+  right: &syn::Expr,
+) -> Option<&'static str> {
+  let syn::Expr::Binary(binary) = expr else {
+    return None;
+  };
+  let (op, mirrored) = match binary.op {
+    syn::BinOp::Eq(_) => ("==", "=="),
+    syn::BinOp::Lt(_) => ("<", ">"),
+    syn::BinOp::Le(_) => ("<=", ">="),
+    syn::BinOp::Gt(_) => (">", "<"),
+    syn::BinOp::Ge(_) => (">=", "<="),
+    _ => return None,
+  };
+  if *binary.left == *left && *binary.right == *right {
+    Some(op)
+  } else if *binary.left == *right && *binary.right == *left {
+    Some(mirrored)
+  } else {
+    None
+  }
+}
+
+/// Reports two comparisons of the same operands that make one, such as
+/// `a == b || a < b` for `a <= b`.
+#[derive(Default)]
+pub struct DoubleComparisons {
+  positions: Positions,
+}
+
+impl<'ast> Rule<'ast> for DoubleComparisons {
+  fn name(&self) -> &'static str {
+    "double_comparisons"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr, NodeKind::ExprBinary]
+  }
+
+  fn apply_expr(&mut self, _context: &RuleContext, node: &'ast syn::Expr) {
+    self.positions.enter(node);
+  }
+
+  fn apply_expr_binary(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprBinary,
+  ) {
+    let syn::Expr::Binary(first) = &*node.left else {
+      return;
+    };
+    let (left, right) = (&*first.left, &*first.right);
+    if !is_pure(left) || !is_pure(right) {
+      return;
+    }
+    let (Some(a), Some(b)) = (
+      comparison(&node.left, left, right),
+      comparison(&node.right, left, right),
+    ) else {
+      return;
+    };
+    let mut ops = [a, b];
+    ops.sort_unstable();
+    let op = match (&node.op, ops) {
+      (syn::BinOp::Or(_), ["<", "=="]) => "<=",
+      (syn::BinOp::Or(_), ["==", ">"]) => ">=",
+      (syn::BinOp::Or(_), ["<", ">"]) => "!=",
+      (syn::BinOp::And(_), ["<=", ">="]) => "==",
+      _ => return,
+    };
+    let (Some(left), Some(right)) =
+      (context.snippet(left), context.snippet(right))
+    else {
+      return;
+    };
+    let text = format!("{} {} {}", left, op, right);
+    report_rewrite(
+      context,
+      "this binary expression can be simplified",
+      node,
+      self.positions.fit(node, &text, 5),
+    );
+  }
+}
+
+/// The value of an integer literal, looking through parentheses.
+fn int_literal(expr: &syn::Expr) -> Option<u128> {
+  match expr {
+    syn::Expr::Lit(syn::ExprLit {
+      lit: syn::Lit::Int(int),
+      ..
+    }) => int.base10_parse().ok(),
+    syn::Expr::Paren(paren) => int_literal(&paren.expr),
+    _ => None,
+  }
+}
+
+/// Reports operations that leave their operand unchanged, such as `x * 1` or
+/// `x + 0`. The fix is only machine-applicable where `x` is known not to be
+/// a reference, which the operation would have dereferenced.
+#[derive(Default)]
+pub struct IdentityOp {
+  positions: Positions,
+  types: Rc<LocalTypes>,
+}
+
+impl<'ast> Rule<'ast> for IdentityOp {
+  fn name(&self) -> &'static str {
+    "identity_op"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::File, NodeKind::Expr, NodeKind::ExprBinary]
+  }
+
+  fn apply_file(&mut self, context: &RuleContext, node: &'ast syn::File) {
+    self.types = context.types(node);
+  }
+
+  fn apply_expr(&mut self, _context: &RuleContext, node: &'ast syn::Expr) {
+    self.positions.enter(node);
+  }
+
+  fn apply_expr_binary(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprBinary,
+  ) {
+    use syn::BinOp::*;
+    let (left, right) = (int_literal(&node.left), int_literal(&node.right));
+    let operand = match (&node.op, left, right) {
+      (Add(_) | BitOr(_) | BitXor(_), Some(0), _) => &node.right,
+      (Mul(_), Some(1), _) => &node.right,
+      (
+        Add(_) | Sub(_) | BitOr(_) | BitXor(_) | Shl(_) | Shr(_),
+        _,
+        Some(0),
+      ) => &node.left,
+      (Mul(_) | Div(_), _, Some(1)) => &node.left,
+      _ => return,
+    };
+    let Some(text) = context.snippet(&**operand) else {
+      return;
+    };
+    let replacement = self.positions.fit(node, text, sugg::precedence(operand));
+    report_replacement(
+      context,
+      "this operation has no effect",
+      node,
+      replacement,
+      applicability(&self.types, operand),
+    );
+  }
+}
+
+/// Whether `expr` is a float literal equal to zero, possibly negated.
+fn is_float_zero(expr: &syn::Expr) -> bool {
+  match expr {
+    syn::Expr::Lit(syn::ExprLit {
+      lit: syn::Lit::Float(float),
+      ..
+    }) => float.base10_parse::<f64>().is_ok_and(|value| value == 0.0),
+    syn::Expr::Unary(syn::ExprUnary {
+      op: syn::UnOp::Neg(_),
+      expr,
+      ..
+    }) => is_float_zero(expr),
+    syn::Expr::Paren(paren) => is_float_zero(&paren.expr),
+    _ => false,
+  }
+}
+
+/// Whether `expr` is one of the float types' constants `names`, such as
+/// `f64::NAN` or `std::f32::NAN`.
+fn is_float_constant(expr: &syn::Expr, names: &[&str]) -> bool {
+  match expr {
+    syn::Expr::Path(path) if path.qself.is_none() => {
+      let mut segments = path.path.segments.iter().rev();
+      segments
+        .next()
+        .is_some_and(|last| names.iter().any(|name| last.ident == name))
+        && segments
+          .any(|segment| segment.ident == "f32" || segment.ident == "f64")
+    }
+    syn::Expr::Paren(paren) => is_float_constant(&paren.expr, names),
+    _ => false,
+  }
+}
+
+/// Whether the node being visited is in an implementation of `PartialEq`,
+/// which has to compare floats exactly.
+fn in_partial_eq(context: &RuleContext) -> bool {
+  context.ancestors.iter().any(|ancestor| {
+    ancestor
+      .downcast_ref::<crate::ItemHeader>()
+      .filter(|item| item.kind == crate::ItemKind::Method)
+      .and_then(|item| item.ident.as_ref())
+      .is_some_and(|ident| ident == "eq" || ident == "ne")
+  })
+}
+
+/// Reports `==` and `!=` on floats, whose rounding errors make exact
+/// comparisons unreliable. Comparisons with zero and infinities are allowed.
+#[derive(Default)]
+pub struct FloatCmp {
+  positions: Positions,
+  types: Rc<LocalTypes>,
+}
+
+impl<'ast> Rule<'ast> for FloatCmp {
+  fn name(&self) -> &'static str {
+    "float_cmp"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::File, NodeKind::Expr, NodeKind::ExprBinary]
+  }
+
+  fn apply_file(&mut self, context: &RuleContext, node: &'ast syn::File) {
+    self.types = context.types(node);
+  }
+
+  fn apply_expr(&mut self, _context: &RuleContext, node: &'ast syn::Expr) {
+    self.positions.enter(node);
+  }
+
+  fn apply_expr_binary(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprBinary,
+  ) {
+    let within = match node.op {
+      syn::BinOp::Eq(_) => "<",
+      syn::BinOp::Ne(_) => ">=",
+      _ => return,
+    };
+    let (left, right) = (&*node.left, &*node.right);
+    let Some(ty) = self
+      .types
+      .type_of(left)
+      .or_else(|| self.types.type_of(right))
+      .filter(|ty| ty.is_float())
+    else {
+      return;
+    };
+    let exact = |expr: &syn::Expr| {
+      is_float_zero(expr)
+        || is_float_constant(expr, &["INFINITY", "NEG_INFINITY", "NAN"])
+    };
+    if exact(left) || exact(right) || in_partial_eq(context) {
+      return;
+    }
+    let message = format!("strict comparison of `{}` values", ty.name());
+    let (Some(a), Some(b)) = (
+      sugg::snippet(context, left, 10),
+      sugg::snippet(context, right, 11),
+    ) else {
+      return;
+    };
+    let text =
+      format!("({} - {}).abs() {} {}::EPSILON", a, b, within, ty.name());
+    report_replacement(
+      context,
+      message,
+      node,
+      self.positions.fit(node, &text, 5),
+      Applicability::MaybeIncorrect,
+    );
+  }
+}
+
+/// Reports comparisons with `NAN`, which is unequal to everything including
+/// itself, suggesting `is_nan` for `==` and `!=`.
+#[derive(Default)]
+pub struct CmpNan {
+  positions: Positions,
+}
+
+impl<'ast> Rule<'ast> for CmpNan {
+  fn name(&self) -> &'static str {
+    "cmp_nan"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr, NodeKind::ExprBinary]
+  }
+
+  fn apply_expr(&mut self, _context: &RuleContext, node: &'ast syn::Expr) {
+    self.positions.enter(node);
+  }
+
+  fn apply_expr_binary(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprBinary,
+  ) {
+    use syn::BinOp::*;
+    if !matches!(node.op, Eq(_) | Ne(_) | Lt(_) | Le(_) | Gt(_) | Ge(_)) {
+      return;
+    }
+    let other = if is_float_constant(&node.right, &["NAN"]) {
+      &*node.left
+    } else if is_float_constant(&node.left, &["NAN"]) {
+      &*node.right
+    } else {
+      return;
+    };
+    let (always, negation) = match node.op {
+      Ne(_) => ("true", "!"),
+      Eq(_) => ("false", ""),
+      _ => {
+        return context.emit_report(Report {
+          message: "comparisons with NaN always return false".into(),
+          node,
+          suggestion: None,
+        })
+      }
+    };
+    let message = format!("comparisons with NaN always return {}", always);
+    let Some(operand) = sugg::snippet(context, other, sugg::POSTFIX) else {
+      return;
+    };
+    let text = format!("{}{}.is_nan()", negation, operand);
+    let precedence = if negation.is_empty() {
+      sugg::POSTFIX
+    } else {
+      sugg::UNARY
+    };
+    report_replacement(
+      context,
+      message,
+      node,
+      self.positions.fit(node, &text, precedence),
+      Applicability::MaybeIncorrect,
+    );
+  }
+}
+
+/// Whether `expr` is a subtraction, looking through parentheses.
+fn is_subtraction(expr: &syn::Expr) -> bool {
+  match expr {
+    syn::Expr::Binary(binary) => matches!(binary.op, syn::BinOp::Sub(_)),
+    syn::Expr::Paren(paren) => is_subtraction(&paren.expr),
+    _ => false,
+  }
+}
+
+/// Reports `a - b < f64::EPSILON`, which holds whenever `a` is less than `b`
+/// rather than only when they're about equal, suggesting `(a - b).abs()`.
+pub struct FloatEqualityWithoutAbs;
+
+impl<'ast> Rule<'ast> for FloatEqualityWithoutAbs {
+  fn name(&self) -> &'static str {
+    "float_equality_without_abs"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::ExprBinary]
+  }
+
+  fn apply_expr_binary(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprBinary,
+  ) {
+    let difference = match node.op {
+      syn::BinOp::Lt(_) | syn::BinOp::Le(_)
+        if is_float_constant(&node.right, &["EPSILON"]) =>
+      {
+        &*node.left
+      }
+      syn::BinOp::Gt(_) | syn::BinOp::Ge(_)
+        if is_float_constant(&node.left, &["EPSILON"]) =>
+      {
+        &*node.right
+      }
+      _ => return,
+    };
+    if !is_subtraction(difference) {
+      return;
+    }
+    let suggestion =
+      sugg::snippet(context, difference, sugg::POSTFIX).map(|text| {
+        let replacement = format!("{}.abs()", text);
+        Suggestion {
+          message: format!("try `{}`", replacement),
+          applicability: Applicability::MaybeIncorrect,
+          edits: vec![Edit::replace(difference, replacement)],
+        }
+      });
+    context.emit_report(Report {
+      message: "float equality check without `.abs()`".into(),
+      node,
+      suggestion,
+    });
+  }
+}
+
+/// Reports `0.0 / 0.0`, suggesting the `NAN` constant it evaluates to.
+pub struct ZeroDividedByZero;
+
+impl<'ast> Rule<'ast> for ZeroDividedByZero {
+  fn name(&self) -> &'static str {
+    "zero_divided_by_zero"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::ExprBinary]
+  }
+
+  fn apply_expr_binary(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprBinary,
+  ) {
+    if !matches!(node.op, syn::BinOp::Div(_))
+      || !is_float_zero(&node.left)
+      || !is_float_zero(&node.right)
+    {
+      return;
+    }
+    let suffixed = literal_suffix(&node.left).or(literal_suffix(&node.right));
+    // Unsuffixed literals may be inferred to be `f32`.
+    let (ty, applicability) = match suffixed {
+      Some(ty) => (ty.name(), Applicability::MachineApplicable),
+      None => ("f64", Applicability::MaybeIncorrect),
+    };
+    report_replacement(
+      context,
+      "constant division of `0.0` with `0.0` will always result in NaN",
+      node,
+      format!("{}::NAN", ty),
+      applicability,
+    );
+  }
+}
+
+/// The type suffix of a float literal, looking through negation and
+/// parentheses.
+fn literal_suffix(expr: &syn::Expr) -> Option<NumType> {
+  match expr {
+    syn::Expr::Lit(syn::ExprLit {
+      lit: syn::Lit::Float(float),
+      ..
+    }) => NumType::from_name(float.suffix()),
+    syn::Expr::Unary(unary) => literal_suffix(&unary.expr),
+    syn::Expr::Paren(paren) => literal_suffix(&paren.expr),
+    _ => None,
+  }
+}
+
+/// Reports `!(a < b)` and the like, which isn't `a >= b` if the operands
+/// can be incomparable. Only operands known to be floats are reported, as
+/// most other types are totally ordered.
+#[derive(Default)]
+pub struct NegCmpOpOnPartialOrd {
+  types: Rc<LocalTypes>,
+}
+
+impl<'ast> Rule<'ast> for NegCmpOpOnPartialOrd {
+  fn name(&self) -> &'static str {
+    "neg_cmp_op_on_partial_ord"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::File, NodeKind::Expr]
+  }
+
+  fn apply_file(&mut self, context: &RuleContext, node: &'ast syn::File) {
+    self.types = context.types(node);
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let syn::Expr::Unary(syn::ExprUnary {
+      op: syn::UnOp::Not(_),
+      expr,
+      ..
+    }) = node
+    else {
+      return;
+    };
+    let syn::Expr::Paren(paren) = &**expr else {
+      return;
+    };
+    let syn::Expr::Binary(binary) = &*paren.expr else {
+      return;
+    };
+    use syn::BinOp::*;
+    if !matches!(binary.op, Lt(_) | Le(_) | Gt(_) | Ge(_)) {
+      return;
+    }
+    let float = |expr: &syn::Expr| {
+      self.types.type_of(expr).is_some_and(NumType::is_float)
+    };
+    if !float(&binary.left) && !float(&binary.right) {
+      return;
+    }
+    context.emit_report(Report {
+      message: "negated comparison of possibly incomparable values, which \
+                differs from the opposite comparison for NaN; use \
+                `partial_cmp` to make that explicit"
+        .into(),
+      node,
+      suggestion: None,
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::clippy::{fixes, machine_applicable};
+
+  #[test]
+  fn test_eq_op() {
+    let source = "
+fn f(a: u8, b: bool) {
+  a == a;
+  a.0 - a.0;
+  c || b && b;
+  f() == f();
+  a == b;
+}
+";
+    assert_eq!(
+      fixes("eq_op", source),
+      [(3, String::new()), (4, String::new()), (5, "b".into())]
+    );
+  }
+
+  #[test]
+  fn test_double_comparisons() {
+    let source = "
+fn f(a: u8, b: u8) {
+  a == b || a < b;
+  b > a || a == b;
+  a < b || a > b;
+  a <= b && b <= a;
+  a < b && a == b;
+  a == c || a < b;
+}
+";
+    assert_eq!(
+      fixes("double_comparisons", source),
+      [
+        (3, "a <= b".into()),
+        (4, "b >= a".into()),
+        (5, "a != b".into()),
+        (6, "a == b".into()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_identity_op() {
+    let source = "
+fn f(x: u8, y: u8) {
+  x * 1;
+  0 + x;
+  x - 0;
+  2 * (x + y) / 1;
+  2 * (x + y | 0);
+  x << 0u8;
+  x * 0;
+  1 - x;
+}
+";
+    assert_eq!(
+      fixes("identity_op", source),
+      [
+        (3, "x".into()),
+        (4, "x".into()),
+        (5, "x".into()),
+        (6, "2 * (x + y)".into()),
+        (7, "x + y".into()),
+        (8, "x".into()),
+      ]
+    );
+    assert_eq!(
+      machine_applicable("identity_op", source),
+      [3, 4, 5, 6, 7, 8]
+    );
+  }
+
+  #[test]
+  fn test_identity_op_on_reference() {
+    let source = "
+fn f(r: &u8, b: &bool, x: u8) -> u8 {
+  let x: u8 = 0 + r;
+  b & b;
+  x | x;
+  x
+}
+";
+    assert_eq!(fixes("identity_op", source), [(3, "r".into())]);
+    assert_eq!(machine_applicable("identity_op", source), [0usize; 0]);
+    assert_eq!(fixes("eq_op", source), [(4, "b".into()), (5, "x".into())]);
+    assert_eq!(machine_applicable("eq_op", source), [5]);
+  }
+
+  #[test]
+  fn test_identity_op_on_shadowed_reference() {
+    let source = "
+fn f(x: u8, v: Vec<&u8>) {
+  for x in v {
+    let y: u8 = x | x;
+    let z: u8 = 0 + x;
+    let w: u8 = !!x;
+  }
+  (x | x, 0 + x, !!x);
+}
+";
+    assert_eq!(machine_applicable("eq_op", source), [8]);
+    assert_eq!(machine_applicable("identity_op", source), [8]);
+    assert_eq!(machine_applicable("nonminimal_bool", source), [8]);
+    assert_eq!(fixes("identity_op", source)[0], (5, "x".into()));
+  }
+
+  #[test]
+  fn test_float_cmp() {
+    let source = "
+const EPS: f32 = 0.1;
+
+fn f(a: f64, b: f64, n: u8) {
+  a == b;
+  a.abs() != 1.5;
+  EPS == x;
+  a == 0.0;
+  a == f64::INFINITY;
+  n == 1;
+  y == z;
+}
+
+impl PartialEq for A {
+  fn eq(&self, other: &Self) -> bool {
+    let a: f64 = self.0;
+    a == other.0
+  }
+}
+";
+    assert_eq!(
+      fixes("float_cmp", source),
+      [
+        (5, "(a - b).abs() < f64::EPSILON".into()),
+        (6, "(a.abs() - 1.5).abs() >= f64::EPSILON".into()),
+        (7, "(EPS - x).abs() < f32::EPSILON".into()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_cmp_nan() {
+    let source = "
+fn f(x: f64) {
+  x == f64::NAN;
+  std::f32::NAN != x + 1.0;
+  x < f64::NAN;
+  x == NAN;
+}
+";
+    assert_eq!(
+      fixes("cmp_nan", source),
+      [
+        (3, "x.is_nan()".into()),
+        (4, "!(x + 1.0).is_nan()".into()),
+        (5, String::new()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_float_equality_without_abs() {
+    let source = "
+fn f(a: f64, b: f64) {
+  a - b < f64::EPSILON;
+  std::f32::EPSILON > (a - b);
+  (a - b).abs() < f64::EPSILON;
+}
+";
+    assert_eq!(
+      fixes("float_equality_without_abs", source),
+      [(3, "(a - b).abs()".into()), (4, "(a - b).abs()".into())]
+    );
+  }
+
+  #[test]
+  fn test_zero_divided_by_zero() {
+    let source = "
+fn f() {
+  0.0 / 0.0;
+  0.0f32 / -0.0;
+  0.0 / 1.0;
+}
+";
+    assert_eq!(
+      fixes("zero_divided_by_zero", source),
+      [(3, "f64::NAN".into()), (4, "f32::NAN".into())]
+    );
+  }
+
+  #[test]
+  fn test_neg_cmp_op_on_partial_ord() {
+    let source = "
+fn f(a: f64, b: f64, n: u8) {
+  !(a < b);
+  !(x >= y);
+  !(n > 1);
+  !(a == b);
+  !(s.count > 3);
+  !(-1 <= s.count);
+  !(s.ratio > 1f32);
+  !(s.ratio <= 0.5);
+}
+";
+    assert_eq!(
+      fixes("neg_cmp_op_on_partial_ord", source),
+      [(3, String::new()), (9, String::new()), (10, String::new())]
+    );
+  }
+}
@@ -0,0 +1,310 @@
+//! Rules reporting code that may panic, for programs that mustn't. Code in
+//! tests is exempt, as is `main` with `allow-panics-in-main`.
+
+use crate::{ItemHeader, ItemKind, NodeKind, Report, Rule, RuleContext};
+
+/// Whether the node being visited is in test code (or in `main`, if
+/// `allow_in_main`), judging by the engine's ancestors.
+fn is_exempt(context: &RuleContext, allow_in_main: bool) -> bool {
+  context.ancestors.iter().any(|ancestor| {
+    ancestor.downcast_ref::<ItemHeader>().is_some_and(|item| {
+      is_test(&item.attrs)
+        || allow_in_main
+          && item.kind == ItemKind::Fn
+          && item.ident.as_ref().is_some_and(|ident| ident == "main")
+    })
+  })
+}
+
+/// Whether `attrs` mark a test (`#[test]`, `#[tokio::test]`...) or code only
+/// compiled for tests (`#[cfg(test)]`, `#[cfg(all(test, unix))]`...).
+fn is_test(attrs: &[syn::Attribute]) -> bool {
+  fn mentions_test(meta: &syn::NestedMeta) -> bool {
+    match meta {
+      syn::NestedMeta::Meta(syn::Meta::Path(path)) => path.is_ident("test"),
+      syn::NestedMeta::Meta(syn::Meta::List(list)) => {
+        !list.path.is_ident("not") && list.nested.iter().any(mentions_test)
+      }
+      _ => false,
+    }
+  }
+  attrs.iter().any(|attr| {
+    if attr
+      .path
+      .segments
+      .last()
+      .is_some_and(|last| last.ident == "test")
+    {
+      return true;
+    }
+    match attr.parse_meta() {
+      Ok(syn::Meta::List(list)) if list.path.is_ident("cfg") => {
+        list.nested.iter().any(mentions_test)
+      }
+      _ => false,
+    }
+  })
+}
+
+pub struct UnwrapUsed {
+  pub allow_in_main: bool,
+}
+
+impl<'ast> Rule<'ast> for UnwrapUsed {
+  fn name(&self) -> &'static str {
+    "unwrap_used"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::ExprMethodCall]
+  }
+
+  fn apply_expr_method_call(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprMethodCall,
+  ) {
+    let method = node.method.to_string();
+    if (method == "unwrap" || method == "unwrap_err")
+      && node.args.is_empty()
+      && !is_exempt(context, self.allow_in_main)
+    {
+      context.emit_report(Report {
+        message: format!(
+          "`{}()` panics on the other variant; handle it or propagate it \
+           with `?`",
+          method
+        ),
+        node,
+        suggestion: None,
+      });
+    }
+  }
+}
+
+pub struct ExpectUsed {
+  pub allow_in_main: bool,
+}
+
+impl<'ast> Rule<'ast> for ExpectUsed {
+  fn name(&self) -> &'static str {
+    "expect_used"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::ExprMethodCall]
+  }
+
+  fn apply_expr_method_call(
+    &mut self,
+    context: &RuleContext,
+    node: &'ast syn::ExprMethodCall,
+  ) {
+    let method = node.method.to_string();
+    if (method == "expect" || method == "expect_err")
+      && node.args.len() == 1
+      && !is_exempt(context, self.allow_in_main)
+    {
+      context.emit_report(Report {
+        message: format!(
+          "`{}(..)` panics on the other variant; handle it or propagate it \
+           with `?`",
+          method
+        ),
+        node,
+        suggestion: None,
+      });
+    }
+  }
+}
+
+/// Reports `a[i]` and `a[i..j]`, which panic when out of bounds. `a[..]`
+/// can't, and is allowed.
+pub struct IndexingSlicing {
+  pub allow_in_main: bool,
+}
+
+impl<'ast> Rule<'ast> for IndexingSlicing {
+  fn name(&self) -> &'static str {
+    "indexing_slicing"
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Expr]
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    let syn::Expr::Index(index) = node else {
+      return;
+    };
+    if let syn::Expr::Range(range) = &*index.index {
+      if range.from.is_none() && range.to.is_none() {
+        return;
+      }
+    }
+    if is_exempt(context, self.allow_in_main) {
+      return;
+    }
+    let message = match &*index.index {
+      syn::Expr::Range(_) => "slicing may panic; use `.get(..)` instead",
+      _ => "indexing may panic; use `.get(..)` instead",
+    };
+    context.emit_report(Report {
+      message: message.into(),
+      node,
+      suggestion: None,
+    });
+  }
+}
+
+/// A rule reporting invocations of macros that panic.
+pub struct PanicMacroLint {
+  pub name: &'static str,
+  pub macros: &'static [&'static str],
+  pub message: &'static str,
+}
+
+pub const PANIC_MACROS: &[PanicMacroLint] = &[
+  PanicMacroLint {
+    name: "panic",
+    macros: &["panic"],
+    message: "`panic!` in non-test code",
+  },
+  PanicMacroLint {
+    name: "todo",
+    macros: &["todo"],
+    message: "`todo!` left in code",
+  },
+  PanicMacroLint {
+    name: "unimplemented",
+    macros: &["unimplemented"],
+    message: "`unimplemented!` left in code",
+  },
+  PanicMacroLint {
+    name: "unreachable",
+    macros: &["unreachable"],
+    message: "`unreachable!` panics if it's reached after all",
+  },
+  PanicMacroLint {
+    name: "assert_used",
+    macros: &["assert"],
+    message: "`assert!` in non-test code panics when it fails",
+  },
+];
+
+pub struct PanicMacro {
+  pub lint: &'static PanicMacroLint,
+  pub allow_in_main: bool,
+}
+
+impl PanicMacro {
+  fn apply(&self, context: &RuleContext, mac: &syn::Macro) {
+    let is_match = mac
+      .path
+      .segments
+      .last()
+      .is_some_and(|last| self.lint.macros.iter().any(|m| last.ident == m));
+    if is_match && !is_exempt(context, self.allow_in_main) {
+      context.emit_report(Report {
+        message: self.lint.message.into(),
+        node: mac,
+        suggestion: None,
+      });
+    }
+  }
+}
+
+impl<'ast> Rule<'ast> for PanicMacro {
+  fn name(&self) -> &'static str {
+    self.lint.name
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Item, NodeKind::Expr]
+  }
+
+  // Macros in statement position, like `panic!();`, parse as items.
+  fn apply_item(&mut self, context: &RuleContext, node: &'ast syn::Item) {
+    if let syn::Item::Macro(item) = node {
+      self.apply(context, &item.mac);
+    }
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    if let syn::Expr::Macro(expr) = node {
+      self.apply(context, &expr.mac);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::config::Config;
+
+  #[test]
+  fn test_panic_rules() {
+    let config = Config::from_toml(
+      r#"
+      [rules]
+      disable = ["absurd_extreme_comparisons"]
+      restriction = [
+        "unwrap_used", "expect_used", "indexing_slicing", "panic", "todo",
+        "unimplemented", "unreachable", "assert_used",
+      ]
+      allow-panics-in-main = true
+      "#,
+    )
+    .unwrap();
+    let source = "
+fn f(v: &[u8]) -> u8 {
+  a.unwrap();
+  b.expect(\"b\");
+  c.unwrap_or(0);
+  &v[..];
+  &v[1..];
+  assert!(v.len() > 1); assert_eq!(v.len(), 2); assert_ne!(v.len(), 3);
+  if v.is_empty() { panic!(\"empty\") }
+  match v[0] { 0 => todo!(), 1 => unimplemented!(), _ => unreachable!() }
+}
+
+fn main() {
+  a.unwrap();
+}
+
+#[test]
+fn test() {
+  assert_eq!(a.unwrap(), v[0]);
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  fn helper() { a.unwrap(); }
+}
+
+#[cfg(not(test))]
+mod not_tests {
+  fn helper() { a.unwrap(); }
+}
+";
+    let diagnostics = crate::lint(source, &config).unwrap();
+    let reports: Vec<_> = diagnostics
+      .iter()
+      .map(|d| (d.rule.as_str(), d.start.line))
+      .collect();
+    assert_eq!(
+      reports,
+      [
+        ("unwrap_used", 3),
+        ("expect_used", 4),
+        ("indexing_slicing", 7),
+        ("assert_used", 8),
+        ("panic", 9),
+        ("indexing_slicing", 10),
+        ("todo", 10),
+        ("unimplemented", 10),
+        ("unreachable", 10),
+        ("unwrap_used", 29),
+      ]
+    );
+  }
+}
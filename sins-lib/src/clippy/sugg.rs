@@ -0,0 +1,185 @@
+//! Helpers for suggesting rewritten expressions, after clippy's `sugg`: a
+//! replacement must be parenthesized if it binds more loosely than what its
+//! position requires.
+
+use std::collections::HashMap;
+
+use syn::spanned::Spanned;
+
+use crate::{Applicability, Edit, Position, Report, RuleContext, Suggestion};
+
+/// Precedence of unary operators, which is what `!x` needs of `x`.
+pub const UNARY: u8 = 13;
+/// Precedence of method calls, fields and indexing, what `x.f()` needs of `x`.
+pub const POSTFIX: u8 = 14;
+/// Precedence of paths, literals and anything delimited.
+pub const ATOM: u8 = 15;
+
+pub fn bin_op_precedence(op: &syn::BinOp) -> u8 {
+  use syn::BinOp::*;
+  match op {
+    Or(_) => 3,
+    And(_) => 4,
+    Eq(_) | Ne(_) | Lt(_) | Le(_) | Gt(_) | Ge(_) => 5,
+    BitOr(_) => 6,
+    BitXor(_) => 7,
+    BitAnd(_) => 8,
+    Shl(_) | Shr(_) => 9,
+    Add(_) | Sub(_) => 10,
+    Mul(_) | Div(_) | Rem(_) => 11,
+    // Compound assignments.
+    _ => 1,
+  }
+}
+
+pub fn precedence(expr: &syn::Expr) -> u8 {
+  match expr {
+    syn::Expr::Assign(_)
+    | syn::Expr::AssignOp(_)
+    | syn::Expr::Range(_)
+    | syn::Expr::Closure(_)
+    | syn::Expr::Return(_)
+    | syn::Expr::Break(_)
+    | syn::Expr::Yield(_) => 1,
+    syn::Expr::Binary(binary) => bin_op_precedence(&binary.op),
+    syn::Expr::Cast(_) | syn::Expr::Type(_) => 12,
+    syn::Expr::Unary(_) | syn::Expr::Reference(_) => UNARY,
+    syn::Expr::MethodCall(_)
+    | syn::Expr::Field(_)
+    | syn::Expr::Index(_)
+    | syn::Expr::Call(_)
+    | syn::Expr::Try(_)
+    | syn::Expr::Await(_) => POSTFIX,
+    _ => ATOM,
+  }
+}
+
+/// Returns `text` in parentheses if its precedence is below `required`.
+pub fn parenthesize(text: &str, precedence: u8, required: u8) -> String {
+  if precedence < required {
+    format!("({})", text)
+  } else {
+    text.to_owned()
+  }
+}
+
+/// The precedence each expression's position requires of it, recorded as
+/// rules see their parents. `Rule::apply_expr` is called on parents before
+/// their children, so a rule calling `enter` from it knows the requirement of
+/// every expression it's given.
+#[derive(Default)]
+pub struct Positions {
+  required: HashMap<(Position, Position), u8>,
+}
+
+pub fn span_key(node: &dyn Spanned) -> (Position, Position) {
+  let span = node.span();
+  (span.start().into(), span.end().into())
+}
+
+impl Positions {
+  pub fn enter(&mut self, expr: &syn::Expr) {
+    let mut require = |node: &syn::Expr, precedence: u8| {
+      self.required.insert(span_key(node), precedence);
+    };
+    match expr {
+      syn::Expr::Binary(binary) => {
+        let precedence = bin_op_precedence(&binary.op);
+        // Comparisons don't chain, and other operators are left associative.
+        let left = if precedence == 5 {
+          precedence + 1
+        } else {
+          precedence
+        };
+        require(&binary.left, left);
+        require(&binary.right, precedence + 1);
+      }
+      syn::Expr::Cast(cast) => require(&cast.expr, UNARY),
+      syn::Expr::Unary(unary) => require(&unary.expr, UNARY),
+      syn::Expr::Reference(reference) => require(&reference.expr, UNARY),
+      syn::Expr::MethodCall(call) => require(&call.receiver, POSTFIX),
+      syn::Expr::Field(field) => require(&field.base, POSTFIX),
+      syn::Expr::Index(index) => require(&index.expr, POSTFIX),
+      syn::Expr::Call(call) => require(&call.func, POSTFIX),
+      syn::Expr::Try(try_) => require(&try_.expr, POSTFIX),
+      syn::Expr::Await(await_) => require(&await_.base, POSTFIX),
+      _ => {}
+    }
+  }
+
+  /// The precedence `expr`'s position requires.
+  pub fn required(&self, expr: &dyn Spanned) -> u8 {
+    self.required.get(&span_key(expr)).copied().unwrap_or(0)
+  }
+
+  /// `text`, of precedence `precedence`, parenthesized as needed to replace
+  /// `expr`.
+  pub fn fit(&self, expr: &dyn Spanned, text: &str, precedence: u8) -> String {
+    parenthesize(text, precedence, self.required(expr))
+  }
+}
+
+/// The source of `expr`, parenthesized if it binds more loosely than
+/// `required`.
+pub fn snippet(
+  context: &RuleContext,
+  expr: &syn::Expr,
+  required: u8,
+) -> Option<String> {
+  let text = context.snippet(expr)?;
+  Some(parenthesize(text, precedence(expr), required))
+}
+
+/// Whether the source of `outer` has comments outside those of `inner`, which
+/// rewriting `outer` around them would lose.
+pub fn has_comments(
+  context: &RuleContext,
+  outer: &dyn Spanned,
+  inner: &[&dyn Spanned],
+) -> bool {
+  let Some(mut rest) = context.snippet(outer).map(str::to_owned) else {
+    return true;
+  };
+  for node in inner {
+    let Some(inner) = context.snippet(*node) else {
+      return true;
+    };
+    rest = rest.replacen(inner, "", 1);
+  }
+  rest.contains("//") || rest.contains("/*")
+}
+
+/// Reports `node` with a machine-applicable rewrite to `replacement`.
+pub fn report_rewrite(
+  context: &RuleContext,
+  message: impl Into<String>,
+  node: &dyn Spanned,
+  replacement: String,
+) {
+  report_replacement(
+    context,
+    message,
+    node,
+    replacement,
+    Applicability::MachineApplicable,
+  );
+}
+
+/// Reports `node` with a suggestion to replace it with `replacement`.
+pub fn report_replacement(
+  context: &RuleContext,
+  message: impl Into<String>,
+  node: &dyn Spanned,
+  replacement: String,
+  applicability: Applicability,
+) {
+  context.emit_report(Report {
+    message: message.into(),
+    node,
+    suggestion: Some(Suggestion {
+      message: format!("try `{}`", replacement),
+      applicability,
+      edits: vec![Edit::replace(node, replacement)],
+    }),
+  });
+}
@@ -0,0 +1,98 @@
+//! Visitors finding what parts of the syntax tree mention or bind.
+
+use std::collections::BTreeSet;
+
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::visit::Visit;
+
+/// Finds mentions of the variable `name`.
+pub struct Mentions<'a> {
+  name: &'a syn::Ident,
+  pub found: bool,
+}
+
+impl<'ast> Visit<'ast> for Mentions<'_> {
+  fn visit_ident(&mut self, node: &'ast syn::Ident) {
+    if node == self.name {
+      self.found = true;
+    }
+  }
+
+  fn visit_macro(&mut self, node: &'ast syn::Macro) {
+    // Macro arguments aren't parsed, so tokens stand for mentions.
+    let name = self.name.to_string();
+    if node
+      .tokens
+      .clone()
+      .into_iter()
+      .any(|token| token.to_string() == name)
+    {
+      self.found = true;
+    }
+  }
+}
+
+/// Finds assignments and mutable borrows of a variable, or `continue`s that
+/// would skip the end of a loop body.
+struct Writes<'a>(Mentions<'a>);
+
+impl<'ast> Visit<'ast> for Writes<'_> {
+  fn visit_expr_assign(&mut self, node: &'ast syn::ExprAssign) {
+    self.0.visit_expr(&node.left);
+    syn::visit::visit_expr_assign(self, node);
+  }
+
+  fn visit_expr_assign_op(&mut self, node: &'ast syn::ExprAssignOp) {
+    self.0.visit_expr(&node.left);
+    syn::visit::visit_expr_assign_op(self, node);
+  }
+
+  fn visit_expr_reference(&mut self, node: &'ast syn::ExprReference) {
+    if node.mutability.is_some() {
+      self.0.visit_expr(&node.expr);
+    }
+    syn::visit::visit_expr_reference(self, node);
+  }
+
+  fn visit_expr_continue(&mut self, _node: &'ast syn::ExprContinue) {
+    self.0.found = true;
+  }
+}
+
+/// Whether `stmt` mentions the variable `name`.
+pub fn mentions(stmt: &syn::Stmt, name: &syn::Ident) -> bool {
+  let mut mentions = Mentions { name, found: false };
+  mentions.visit_stmt(stmt);
+  mentions.found
+}
+
+/// Whether `stmt` writes to the variable `name` or skips to the next
+/// iteration.
+pub fn writes(stmt: &syn::Stmt, name: &syn::Ident) -> bool {
+  let mut writes = Writes(Mentions { name, found: false });
+  writes.visit_stmt(stmt);
+  writes.0.found
+}
+
+/// The names `pat` binds.
+pub fn bindings(pat: &syn::Pat) -> BTreeSet<String> {
+  struct Bindings(BTreeSet<String>);
+  impl<'ast> Visit<'ast> for Bindings {
+    fn visit_pat_ident(&mut self, node: &'ast syn::PatIdent) {
+      self.0.insert(node.ident.to_string());
+      syn::visit::visit_pat_ident(self, node);
+    }
+  }
+  let mut bindings = Bindings(BTreeSet::new());
+  bindings.visit_pat(pat);
+  bindings.0
+}
+
+/// The arguments of a macro invoked like a function, such as `println!` or
+/// `vec!`, if they parse as expressions.
+pub fn macro_args(
+  mac: &syn::Macro,
+) -> Option<Punctuated<syn::Expr, syn::Token![,]>> {
+  Punctuated::parse_terminated.parse2(mac.tokens.clone()).ok()
+}
@@ -0,0 +1,215 @@
+use crate::clippy::disallowed::{DisallowedMethods, DisallowedTypes};
+use crate::declarative::PatternRule;
+use crate::{RuleEngine, RuleFactory};
+
+/// Name of the configuration file, looked up in a file's directory and its
+/// ancestors.
+pub const FILE_NAME: &str = "sins.toml";
+
+/// Contents of a `sins.toml`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+  pub rules: RulesConfig,
+  /// Rules defined by patterns, in `[[rule]]` tables.
+  #[serde(rename = "rule")]
+  pub pattern_rules: Vec<PatternRuleConfig>,
+  /// Native rule plugins to load, relative to the directory of the
+  /// `sins.toml`. Loading them is up to the host, see `sins-plugin`.
+  pub plugins: Vec<std::path::PathBuf>,
+  /// Methods reported by `disallowed_methods`.
+  #[serde(rename = "disallowed-methods")]
+  pub disallowed_methods: Vec<DisallowedPath>,
+  /// Functions reported by `disallowed_methods`.
+  #[serde(rename = "disallowed-functions")]
+  pub disallowed_functions: Vec<DisallowedPath>,
+  /// Types reported by `disallowed_types`.
+  #[serde(rename = "disallowed-types")]
+  pub disallowed_types: Vec<DisallowedPath>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RulesConfig {
+  /// Names of built-in rules to turn off.
+  pub disable: Vec<String>,
+  /// Names of built-in rules to turn on which are off by default, being too
+  /// strict for most code, like clippy's `restriction` group.
+  pub restriction: Vec<String>,
+  /// Whether to turn on pedantic rules too, which report code that may well
+  /// be fine but can't be checked.
+  pub pedantic: bool,
+  /// Whether the panic rules allow panicking in `main`, as they always do in
+  /// tests.
+  pub allow_panics_in_main: bool,
+}
+
+/// A rule reporting expressions that match `pattern`, see `crate::pattern`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct PatternRuleConfig {
+  pub name: String,
+  pub pattern: String,
+  /// Only report matches inside an expression matching this pattern.
+  pub inside: Option<String>,
+  /// Only report matches not inside an expression matching this pattern.
+  pub not_inside: Option<String>,
+  /// Reported for each match, with metavariables replaced by what they
+  /// matched.
+  pub message: Option<String>,
+}
+
+/// A method, function or type whose uses `disallowed_methods` or
+/// `disallowed_types` reports.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DisallowedPath {
+  /// Either a bare name, matching anything so named, or a full path such as
+  /// `std::process::exit`, matched after resolving the file's imports.
+  pub path: String,
+  /// Why it's disallowed, included in diagnostics.
+  pub reason: Option<String>,
+  /// What to use instead, suggested as a replacement.
+  pub replacement: Option<String>,
+}
+
+impl Config {
+  /// Parses a configuration, checking that its patterns compile.
+  pub fn from_toml(text: &str) -> Result<Self, toml::de::Error> {
+    let config: Self = toml::from_str(text)?;
+    for rule in &config.pattern_rules {
+      PatternRule::new(rule).map_err(|e| {
+        serde::de::Error::custom(format!("rule `{}`: {}", rule.name, e))
+      })?;
+    }
+    let restriction = crate::clippy::restriction_rules(false);
+    for name in &config.rules.restriction {
+      if !restriction.iter().any(|rule| rule.name() == name) {
+        return Err(serde::de::Error::custom(format!(
+          "unknown restriction rule `{}`",
+          name
+        )));
+      }
+    }
+    let disallowed = config
+      .disallowed_methods
+      .iter()
+      .chain(&config.disallowed_functions)
+      .chain(&config.disallowed_types);
+    for disallowed in disallowed {
+      syn::parse_str::<syn::Path>(&disallowed.path).map_err(|e| {
+        serde::de::Error::custom(format!(
+          "invalid path `{}`: {}",
+          disallowed.path, e
+        ))
+      })?;
+    }
+    Ok(config)
+  }
+}
+
+/// Adds every built-in rule that isn't disabled.
+impl RuleFactory for Config {
+  fn add_rules(&self, rule_engine: &mut RuleEngine<'_>) {
+    for rule in crate::clippy::rules() {
+      if !self.rules.disable.iter().any(|name| name == rule.name()) {
+        rule_engine.add_rule(rule);
+      }
+    }
+    if self.rules.pedantic {
+      for rule in crate::clippy::pedantic_rules() {
+        if !self.rules.disable.iter().any(|name| name == rule.name()) {
+          rule_engine.add_rule(rule);
+        }
+      }
+    }
+    let restriction =
+      crate::clippy::restriction_rules(self.rules.allow_panics_in_main);
+    for rule in restriction {
+      let enabled = |name: &String| name == rule.name();
+      if self.rules.restriction.iter().any(enabled)
+        && !self.rules.disable.iter().any(enabled)
+      {
+        rule_engine.add_rule(rule);
+      }
+    }
+    for rule in &self.pattern_rules {
+      if self.rules.disable.contains(&rule.name) {
+        continue;
+      }
+      // Checked to compile by `from_toml`.
+      if let Ok(rule) = PatternRule::new(rule) {
+        rule_engine.add_rule(Box::new(rule));
+      }
+    }
+    let disallowed_methods = !self.disallowed_methods.is_empty()
+      || !self.disallowed_functions.is_empty();
+    if disallowed_methods
+      && !self
+        .rules
+        .disable
+        .iter()
+        .any(|name| name == "disallowed_methods")
+    {
+      rule_engine.add_rule(Box::new(DisallowedMethods::new(
+        &self.disallowed_methods,
+        &self.disallowed_functions,
+      )));
+    }
+    if !self.disallowed_types.is_empty()
+      && !self
+        .rules
+        .disable
+        .iter()
+        .any(|name| name == "disallowed_types")
+    {
+      rule_engine
+        .add_rule(Box::new(DisallowedTypes::new(&self.disallowed_types)));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_from_toml() {
+    assert_eq!(Config::from_toml("").unwrap(), Config::default());
+
+    let config = Config::from_toml(
+      r#"
+      [rules]
+      disable = [
+        "absurd_extreme_comparisons", "cast_possible_truncation",
+        "cast_sign_loss", "cast_possible_wrap", "cast_precision_loss",
+        "bool_comparison", "nonminimal_bool", "eq_op", "double_comparisons",
+        "identity_op", "float_cmp", "cmp_nan", "float_equality_without_abs",
+        "zero_divided_by_zero", "needless_return", "needless_continue",
+        "needless_bool", "collapsible_if", "collapsible_else_if",
+        "if_same_then_else", "neg_cmp_op_on_partial_ord", "single_match",
+        "match_like_matches_macro", "match_wild_err_arm",
+        "redundant_pattern_matching", "iter_next_slice", "filter_next",
+        "map_flatten", "filter_map_identity", "needless_collect", "iter_count",
+        "search_is_some", "explicit_counter_loop", "needless_range_loop",
+        "while_let_loop", "while_let_on_iterator", "empty_loop", "never_loop",
+        "same_item_push",
+      ]
+      "#,
+    )
+    .unwrap();
+    let mut rule_engine = RuleEngine::new();
+    config.add_rules(&mut rule_engine);
+    assert!(rule_engine.rule_versions().is_empty());
+
+    assert!(Config::from_toml("[rules]\nenable = []").is_err());
+    assert!(Config::from_toml(
+      "[[rule]]\nname = \"broken\"\npattern = \"$x.unwrap(\""
+    )
+    .is_err());
+    assert!(
+      Config::from_toml("disallowed-methods = [{ path = \"a::\" }]").is_err()
+    );
+    assert!(Config::from_toml("[rules]\nrestriction = [\"unwrap\"]").is_err());
+  }
+}
@@ -0,0 +1,224 @@
+use crate::config::PatternRuleConfig;
+use crate::pattern::{self, Node, Pattern};
+use crate::{NodeKind, Position, Report, Rule, RuleContext};
+
+/// A rule defined in `sins.toml` by a pattern, reporting every node the
+/// pattern matches.
+pub struct PatternRule {
+  name: &'static str,
+  pattern: Pattern,
+  inside: Option<Pattern>,
+  not_inside: Option<Pattern>,
+  /// Template for reports, or `None` to report the pattern itself.
+  message: Option<String>,
+  source: String,
+  /// Nodes enclosing the current one, outermost first.
+  ancestors: Vec<Ancestor>,
+}
+
+struct Ancestor {
+  start: Position,
+  end: Position,
+  /// Whether the node matches `inside` and `not_inside`.
+  inside: bool,
+  not_inside: bool,
+}
+
+impl PatternRule {
+  pub fn new(config: &PatternRuleConfig) -> Result<Self, String> {
+    let compile = |pattern: &Option<String>| {
+      pattern.as_deref().map(Pattern::parse).transpose()
+    };
+    Ok(Self {
+      name: intern(&config.name),
+      pattern: Pattern::parse(&config.pattern)?,
+      inside: compile(&config.inside)?,
+      not_inside: compile(&config.not_inside)?,
+      message: config.message.clone(),
+      source: config.pattern.clone(),
+      ancestors: vec![],
+    })
+  }
+}
+
+impl PatternRule {
+  fn apply(&mut self, context: &RuleContext, node: Node) {
+    // Nodes are visited depth first, so the ones still enclosing this node
+    // are exactly those whose span contains it.
+    let span = node.as_spanned().span();
+    let (start, end) = (span.start().into(), span.end().into());
+    while let Some(ancestor) = self.ancestors.last() {
+      if ancestor.start <= start && end <= ancestor.end {
+        break;
+      }
+      self.ancestors.pop();
+    }
+
+    if let Some(bindings) = self.pattern.matches(node) {
+      let inside = self.inside.is_none()
+        || self.ancestors.iter().any(|ancestor| ancestor.inside);
+      let not_inside =
+        !self.ancestors.iter().any(|ancestor| ancestor.not_inside);
+      if inside && not_inside {
+        let message = match &self.message {
+          Some(message) => pattern::substitute(
+            message,
+            &pattern::texts(&bindings, context.source()),
+          ),
+          None => format!("matches `{}`", self.source),
+        };
+        context.emit_report(Report {
+          message,
+          node: node.as_spanned(),
+          suggestion: None,
+        });
+      }
+    }
+
+    let matches = |pattern: &Option<Pattern>| {
+      pattern
+        .as_ref()
+        .is_some_and(|pattern| pattern.matches(node).is_some())
+    };
+    self.ancestors.push(Ancestor {
+      start,
+      end,
+      inside: matches(&self.inside),
+      not_inside: matches(&self.not_inside),
+    });
+  }
+}
+
+impl<'ast> Rule<'ast> for PatternRule {
+  fn name(&self) -> &'static str {
+    self.name
+  }
+
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    &[NodeKind::Item, NodeKind::Stmt, NodeKind::Expr]
+  }
+
+  fn apply_item(&mut self, context: &RuleContext, node: &'ast syn::Item) {
+    self.apply(context, Node::Item(node))
+  }
+
+  fn apply_stmt(&mut self, context: &RuleContext, node: &'ast syn::Stmt) {
+    self.apply(context, Node::Stmt(node))
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    self.apply(context, Node::Expr(node))
+  }
+}
+
+/// Rule names are `'static`, but pattern rules are named at runtime. Each
+/// distinct name is leaked once, so the leak is bounded by the configuration.
+fn intern(name: &str) -> &'static str {
+  use std::collections::HashSet;
+  use std::sync::Mutex;
+  static NAMES: Mutex<Option<HashSet<&'static str>>> = Mutex::new(None);
+  let mut names = NAMES.lock().unwrap_or_else(|e| e.into_inner());
+  let names = names.get_or_insert_with(HashSet::new);
+  if let Some(name) = names.get(name) {
+    return name;
+  }
+  let name: &'static str = Box::leak(name.into());
+  names.insert(name);
+  name
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::config::Config;
+
+  #[test]
+  fn test_pattern_rule() {
+    let config = Config::from_toml(
+      r##"
+      [rules]
+      disable = ["absurd_extreme_comparisons"]
+
+      [[rule]]
+      name = "unwrap_outside_tests"
+      pattern = "$x.unwrap()"
+      not-inside = "#[test] fn $name() { $...body }"
+      message = "`$x` may be `None`"
+
+      [[rule]]
+      name = "unsafe_transmute"
+      pattern = "transmute($...args)"
+      inside = "unsafe { $...body }"
+      "##,
+    )
+    .unwrap();
+    let source = "
+fn main() {
+  a.unwrap();
+}
+
+#[test]
+fn test() {
+  b.unwrap();
+}
+
+fn other() {
+  transmute(c);
+  unsafe { f(transmute(d)) };
+}
+";
+    let diagnostics = crate::lint(source, &config).unwrap();
+    let reports: Vec<_> = diagnostics
+      .iter()
+      .map(|d| (d.rule.as_str(), d.message.as_str(), d.start.line))
+      .collect();
+    assert_eq!(
+      reports,
+      [
+        ("unwrap_outside_tests", "`a` may be `None`", 3),
+        ("unsafe_transmute", "matches `transmute($...args)`", 13),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_bare_ellipsis_in_context() {
+    let config = Config::from_toml(
+      r##"
+      [rules]
+      disable = ["absurd_extreme_comparisons"]
+
+      [[rule]]
+      name = "unwrap_in_main"
+      pattern = "$x.unwrap()"
+      inside = "fn main() { ... }"
+
+      [[rule]]
+      name = "expect_outside_tests"
+      pattern = "$x.expect($msg)"
+      not-inside = "#[test] fn $f() { ... }"
+      "##,
+    )
+    .unwrap();
+    let source = "
+fn main() {
+  a.unwrap();
+  b.expect(\"b\");
+}
+
+#[test]
+fn test() {
+  c.unwrap();
+  d.expect(\"d\");
+}
+";
+    let diagnostics = crate::lint(source, &config).unwrap();
+    let reports: Vec<_> = diagnostics
+      .iter()
+      .map(|d| (d.rule.as_str(), d.start.line))
+      .collect();
+    assert_eq!(
+      reports,
+      [("unwrap_in_main", 3), ("expect_outside_tests", 4)]
+    );
+  }
+}
@@ -0,0 +1,333 @@
+use crate::source::{SourceFile, Splice};
+use crate::{
+  Applicability, Diagnostic, Edit, ParseError, Position, RuleFactory,
+  Suggestion,
+};
+
+/// Upper bound on lint/fix rounds, guarding against rules whose suggestions
+/// keep producing new suggestions.
+pub const MAX_ITERATIONS: usize = 10;
+
+#[derive(Debug)]
+pub struct FixOutcome {
+  pub source: String,
+  /// Number of suggestions applied across all iterations.
+  pub applied: usize,
+  /// Diagnostics reported against the final `source`.
+  pub remaining: Vec<Diagnostic>,
+}
+
+/// Repeatedly lints `source` and applies every machine-applicable suggestion
+/// until no more apply, or `max_iterations` rounds have run.
+///
+/// `factory` populates a fresh engine for each round, as every round re-parses
+/// the source into a new syntax tree. If applying a round of edits
+/// would leave the file unparseable the round is discarded and the last good
+/// source is returned.
+pub fn fix(
+  source: &str,
+  max_iterations: usize,
+  factory: &(impl RuleFactory + ?Sized),
+) -> Result<FixOutcome, ParseError> {
+  let mut source = source.to_owned();
+  let mut applied = 0;
+  let mut iteration = 0;
+  loop {
+    let diagnostics = crate::lint(&source, factory)?;
+    if iteration == max_iterations {
+      return Ok(FixOutcome {
+        source,
+        applied,
+        remaining: diagnostics,
+      });
+    }
+
+    let suggestions = diagnostics.iter().filter_map(|d| d.suggestion.as_ref());
+    let (fixed, count) = apply_suggestions(&source, suggestions);
+    if count == 0 || syn::parse_file(&fixed).is_err() {
+      return Ok(FixOutcome {
+        source,
+        applied,
+        remaining: diagnostics,
+      });
+    }
+    source = fixed;
+    applied += count;
+    iteration += 1;
+  }
+}
+
+/// Applies the machine-applicable `suggestions` to `source`, returning the new
+/// source and the number of suggestions applied.
+///
+/// Edits are applied as minimal byte-range splices on the original text, so
+/// comments and formatting around (and within unchanged parts of) each edit
+/// are preserved.
+///
+/// Suggestions are considered in source order (ties broken by their edits),
+/// and a suggestion is skipped entirely if any of its edits overlaps an edit
+/// of a suggestion already accepted, or lies outside the source. Skipped
+/// suggestions are picked up by the next round of `fix` if they still apply.
+pub fn apply_suggestions<'a>(
+  source: &str,
+  suggestions: impl IntoIterator<Item = &'a Suggestion>,
+) -> (String, usize) {
+  let mut candidates: Vec<&Suggestion> = suggestions
+    .into_iter()
+    .filter(|s| s.applicability == Applicability::MachineApplicable)
+    .filter(|s| !s.edits.is_empty())
+    .collect();
+  candidates.sort_by(|a, b| sort_key(a).cmp(&sort_key(b)));
+
+  let source = SourceFile::new(source);
+  let mut accepted: Vec<&Edit> = vec![];
+  let mut splices = vec![];
+  let mut count = 0;
+  for suggestion in candidates {
+    let conflicts = suggestion.edits.iter().enumerate().any(|(i, edit)| {
+      accepted.iter().any(|other| overlaps(edit, other))
+        || suggestion.edits[..i]
+          .iter()
+          .any(|other| overlaps(edit, other))
+    });
+    if conflicts {
+      continue;
+    }
+    // Applying only the edits that resolve would leave a half-done rewrite.
+    let Some(resolved) = suggestion
+      .edits
+      .iter()
+      .map(|edit| {
+        Some(
+          Splice {
+            range: source.byte_range(edit.start, edit.end)?,
+            replacement: edit.replacement.clone(),
+          }
+          .minimize(source.text()),
+        )
+      })
+      .collect::<Option<Vec<_>>>()
+    else {
+      continue;
+    };
+    accepted.extend(&suggestion.edits);
+    splices.extend(resolved);
+    count += 1;
+  }
+  (crate::source::apply_splices(source.text(), splices), count)
+}
+
+fn sort_key(suggestion: &Suggestion) -> Vec<(Position, Position, &str)> {
+  let mut key: Vec<_> = suggestion
+    .edits
+    .iter()
+    .map(|edit| (edit.start, edit.end, edit.replacement.as_str()))
+    .collect();
+  key.sort();
+  key
+}
+
+/// Two edits overlap if their ranges intersect, or if they insert at the same
+/// position (where the resulting order would be ambiguous).
+fn overlaps(a: &Edit, b: &Edit) -> bool {
+  if a.start == b.start {
+    return true;
+  }
+  a.start < b.end && b.start < a.end
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Report, Rule, RuleContext, RuleEngine};
+
+  struct UnwrapOrDefaultRule;
+  impl<'ast> Rule<'ast> for UnwrapOrDefaultRule {
+    fn name(&self) -> &'static str {
+      "unwrap_or_default"
+    }
+
+    fn apply_expr_method_call(
+      &mut self,
+      context: &RuleContext,
+      node: &'ast syn::ExprMethodCall,
+    ) {
+      if node.method == "unwrap" {
+        context.emit_report(Report {
+          message: "use of `unwrap`".into(),
+          node,
+          suggestion: Some(Suggestion {
+            message: "use `unwrap_or_default`".into(),
+            applicability: Applicability::MachineApplicable,
+            edits: vec![Edit::replace(&node.method, "unwrap_or_default")],
+          }),
+        })
+      }
+    }
+  }
+
+  fn edit(
+    (start_line, start_column): (usize, usize),
+    (end_line, end_column): (usize, usize),
+    replacement: &str,
+  ) -> Edit {
+    Edit {
+      start: Position {
+        line: start_line,
+        column: start_column,
+      },
+      end: Position {
+        line: end_line,
+        column: end_column,
+      },
+      replacement: replacement.into(),
+    }
+  }
+
+  fn suggestion(edits: Vec<Edit>) -> Suggestion {
+    Suggestion {
+      message: String::new(),
+      applicability: Applicability::MachineApplicable,
+      edits,
+    }
+  }
+
+  #[test]
+  fn test_fix_reaches_fixpoint() {
+    let content = "fn main() {\n  let x = a.unwrap().b.unwrap();\n}\n";
+    let outcome =
+      fix(content, MAX_ITERATIONS, &|rule_engine: &mut RuleEngine| {
+        rule_engine.add_rule(Box::new(UnwrapOrDefaultRule))
+      })
+      .unwrap();
+    assert_eq!(
+      outcome.source,
+      "fn main() {\n  let x = a.unwrap_or_default().b.unwrap_or_default();\n}\n"
+    );
+    assert_eq!(outcome.applied, 2);
+    assert!(outcome.remaining.is_empty());
+  }
+
+  #[test]
+  fn test_fix_stops_after_max_iterations() {
+    let content = "fn main() { a.unwrap(); }";
+    let outcome = fix(content, 0, &|rule_engine: &mut RuleEngine| {
+      rule_engine.add_rule(Box::new(UnwrapOrDefaultRule))
+    })
+    .unwrap();
+    assert_eq!(outcome.source, content);
+    assert_eq!(outcome.applied, 0);
+    assert_eq!(outcome.remaining.len(), 1);
+  }
+
+  #[test]
+  fn test_fix_discards_unparseable_edits() {
+    struct BreakingRule;
+    impl<'ast> Rule<'ast> for BreakingRule {
+      fn apply_item_fn(
+        &mut self,
+        context: &RuleContext,
+        node: &'ast syn::ItemFn,
+      ) {
+        context.emit_report(Report {
+          message: "breaks the file".into(),
+          node,
+          suggestion: Some(suggestion(vec![Edit::replace(node, "fn {")])),
+        })
+      }
+    }
+
+    let content = "fn main() {}";
+    let outcome =
+      fix(content, MAX_ITERATIONS, &|rule_engine: &mut RuleEngine| {
+        rule_engine.add_rule(Box::new(BreakingRule))
+      })
+      .unwrap();
+    assert_eq!(outcome.source, content);
+    assert_eq!(outcome.remaining.len(), 1);
+  }
+
+  #[test]
+  fn test_fix_preserves_comments_and_formatting() {
+    struct BoolComparisonRule;
+    impl<'ast> Rule<'ast> for BoolComparisonRule {
+      fn apply_expr_binary(
+        &mut self,
+        context: &RuleContext,
+        node: &'ast syn::ExprBinary,
+      ) {
+        let is_true = matches!(
+          &*node.right,
+          syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Bool(b), .. }) if b.value
+        );
+        if let (syn::BinOp::Eq(_), true) = (node.op, is_true) {
+          let left = context.snippet(&node.left).unwrap();
+          context.emit_report(Report {
+            message: "equality checks against true are unnecessary".into(),
+            node,
+            suggestion: Some(suggestion(vec![Edit::replace(node, left)])),
+          })
+        }
+      }
+    }
+
+    let content = "fn main() {
+    // leading comment
+    if a /* keep */
+        .is_ok()   ==   true { // trailing
+        b();
+    }
+}
+";
+    let outcome =
+      fix(content, MAX_ITERATIONS, &|rule_engine: &mut RuleEngine| {
+        rule_engine.add_rule(Box::new(BoolComparisonRule))
+      })
+      .unwrap();
+    assert_eq!(
+      outcome.source,
+      "fn main() {
+    // leading comment
+    if a /* keep */
+        .is_ok() { // trailing
+        b();
+    }
+}
+"
+    );
+  }
+
+  #[test]
+  fn test_apply_suggestions_skips_overlapping() {
+    let content = "let x = foo(bar);";
+    let suggestions = vec![
+      suggestion(vec![edit((1, 12), (1, 15), "baz")]),
+      suggestion(vec![edit((1, 8), (1, 16), "qux")]),
+      suggestion(vec![edit((1, 4), (1, 5), "y")]),
+    ];
+    let (fixed, count) = apply_suggestions(content, &suggestions);
+    assert_eq!(fixed, "let y = qux;");
+    assert_eq!(count, 2);
+  }
+
+  #[test]
+  fn test_apply_suggestions_skips_unresolvable() {
+    let content = "let x = foo(bar);";
+    let suggestions = vec![
+      suggestion(vec![edit((1, 4), (1, 5), "y"), edit((3, 0), (3, 1), "z")]),
+      suggestion(vec![edit((1, 12), (1, 15), "baz")]),
+    ];
+    let (fixed, count) = apply_suggestions(content, &suggestions);
+    assert_eq!(fixed, "let x = foo(baz);");
+    assert_eq!(count, 1);
+  }
+
+  #[test]
+  fn test_apply_suggestions_ignores_maybe_incorrect() {
+    let mut maybe_incorrect = suggestion(vec![edit((1, 4), (1, 5), "y")]);
+    maybe_incorrect.applicability = Applicability::MaybeIncorrect;
+    let (fixed, count) = apply_suggestions("let x = 1;", &[maybe_incorrect]);
+    assert_eq!(fixed, "let x = 1;");
+    assert_eq!(count, 0);
+  }
+}
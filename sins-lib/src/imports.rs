@@ -0,0 +1,175 @@
+//! Resolution of paths through a file's `use` declarations, without knowledge
+//! of other files or crates.
+
+use std::collections::HashMap;
+
+use syn::visit::Visit;
+
+/// Names in scope in every module without being imported.
+const PRELUDE: &[(&str, &str)] = &[
+  ("Box", "std::boxed::Box"),
+  ("Err", "std::result::Result::Err"),
+  ("None", "std::option::Option::None"),
+  ("Ok", "std::result::Result::Ok"),
+  ("Option", "std::option::Option"),
+  ("Result", "std::result::Result"),
+  ("Some", "std::option::Option::Some"),
+  ("String", "std::string::String"),
+  ("Vec", "std::vec::Vec"),
+  ("drop", "std::mem::drop"),
+];
+
+/// The names brought into scope by a file's `use` declarations. Declarations
+/// in every module and block are merged, since scopes aren't tracked.
+#[derive(Clone, Debug, Default)]
+pub struct Imports {
+  /// The path each imported (or renamed) name stands for.
+  names: HashMap<String, Vec<String>>,
+  /// Paths whose items are all imported by `use path::*`.
+  globs: Vec<Vec<String>>,
+}
+
+impl Imports {
+  pub fn new(file: &syn::File) -> Self {
+    let mut imports = Self::default();
+    imports.visit_file(file);
+    imports
+  }
+
+  fn add(&mut self, tree: &syn::UseTree, prefix: &mut Vec<String>) {
+    match tree {
+      syn::UseTree::Path(path) => {
+        prefix.push(path.ident.to_string());
+        self.add(&path.tree, prefix);
+        prefix.pop();
+      }
+      syn::UseTree::Name(name) => {
+        let mut path = prefix.clone();
+        if name.ident != "self" {
+          path.push(name.ident.to_string());
+        }
+        if let Some(name) = path.last() {
+          self.names.insert(name.clone(), path);
+        }
+      }
+      syn::UseTree::Rename(rename) => {
+        let mut path = prefix.clone();
+        if rename.ident != "self" {
+          path.push(rename.ident.to_string());
+        }
+        if rename.rename != "_" {
+          self.names.insert(rename.rename.to_string(), path);
+        }
+      }
+      syn::UseTree::Glob(_) => self.globs.push(prefix.clone()),
+      syn::UseTree::Group(group) => {
+        for tree in &group.items {
+          self.add(tree, prefix);
+        }
+      }
+    }
+  }
+
+  /// The paths `path` may stand for, joined with `::`: as written, with its
+  /// first segment resolved through the imports, under each glob import, and
+  /// through the prelude. Paths into `core` and `alloc` are also given as
+  /// their `std` re-exports.
+  pub fn resolve(&self, path: &syn::Path) -> Vec<String> {
+    let segments: Vec<String> = path
+      .segments
+      .iter()
+      .map(|segment| segment.ident.to_string())
+      .collect();
+    self.resolve_segments(&segments)
+  }
+
+  pub fn resolve_segments(&self, segments: &[String]) -> Vec<String> {
+    let mut paths = vec![];
+    self.candidates(segments, &mut paths, 0);
+    for path in paths.clone() {
+      for krate in ["core::", "alloc::"] {
+        if let Some(rest) = path.strip_prefix(krate) {
+          paths.push(format!("std::{}", rest));
+        }
+      }
+    }
+    paths.sort();
+    paths.dedup();
+    paths
+  }
+
+  fn candidates(
+    &self,
+    segments: &[String],
+    paths: &mut Vec<String>,
+    depth: usize,
+  ) {
+    paths.push(segments.join("::"));
+    // Bounds chains of renames, which could otherwise be cyclic.
+    if depth > 8 {
+      return;
+    }
+    let Some((first, rest)) = segments.split_first() else {
+      return;
+    };
+    if let Some(path) = self.names.get(first) {
+      // `use a::b;` maps `b` to itself, which needn't be resolved again.
+      if path.len() > 1 || path[0] != *first {
+        let resolved: Vec<String> = path.iter().chain(rest).cloned().collect();
+        self.candidates(&resolved, paths, depth + 1);
+      }
+      return;
+    }
+    for glob in &self.globs {
+      let resolved: Vec<String> =
+        glob.iter().chain(segments).cloned().collect();
+      self.candidates(&resolved, paths, depth + 1);
+    }
+    if let Some((_, path)) = PRELUDE.iter().find(|(name, _)| name == first) {
+      let mut resolved: Vec<String> =
+        path.split("::").map(String::from).collect();
+      resolved.extend(rest.iter().cloned());
+      paths.push(resolved.join("::"));
+    }
+  }
+}
+
+impl<'ast> Visit<'ast> for Imports {
+  fn visit_item_use(&mut self, node: &'ast syn::ItemUse) {
+    self.add(&node.tree, &mut vec![]);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn resolve(imports: &Imports, path: &str) -> Vec<String> {
+    imports.resolve(&syn::parse_str(path).unwrap())
+  }
+
+  #[test]
+  fn test_resolve() {
+    let file = syn::parse_file(
+      "
+      use std::process::{self, exit as quit};
+      use std::collections as c;
+      use c::HashMap as Map;
+      use core::mem::*;
+      ",
+    )
+    .unwrap();
+    let imports = Imports::new(&file);
+    assert!(resolve(&imports, "quit").contains(&"std::process::exit".into()));
+    assert!(
+      resolve(&imports, "process::exit").contains(&"std::process::exit".into())
+    );
+    assert!(resolve(&imports, "Map::new")
+      .contains(&"std::collections::HashMap::new".into()));
+    assert!(resolve(&imports, "forget").contains(&"std::mem::forget".into()));
+    assert!(
+      resolve(&imports, "Vec::leak").contains(&"std::vec::Vec::leak".into())
+    );
+    assert!(!resolve(&imports, "exit").contains(&"std::process::exit".into()));
+  }
+}
@@ -1,5 +1,32 @@
-mod clippy;
+pub mod baseline;
+pub mod clippy;
+pub mod config;
+pub mod declarative;
+pub mod fix;
+pub mod imports;
+pub mod pattern;
+pub mod source;
+pub mod types;
+
 pub trait Rule<'ast> {
+  /// Identifier used when reporting diagnostics emitted by this rule.
+  fn name(&self) -> &'static str {
+    std::any::type_name::<Self>()
+  }
+
+  /// Bumped whenever the rule's behaviour changes, invalidating results cached
+  /// from earlier versions.
+  fn version(&self) -> u32 {
+    1
+  }
+
+  /// Kinds of node whose `apply_*` hooks this rule implements. The engine only
+  /// calls the hooks listed here, so rules should narrow it down from the
+  /// default of every kind.
+  fn node_kinds(&self) -> &'static [NodeKind] {
+    NodeKind::ALL
+  }
+
   fn apply_item_fn(
     &mut self,
     _context: &RuleContext,
@@ -7,6 +34,17 @@ pub trait Rule<'ast> {
   ) {
   }
 
+  /// Called once per file, before any other hook.
+  fn apply_file(&mut self, _context: &RuleContext, _node: &'ast syn::File) {}
+
+  /// Called for every item, before the hooks for its specific kind.
+  fn apply_item(&mut self, _context: &RuleContext, _node: &'ast syn::Item) {}
+
+  fn apply_stmt(&mut self, _context: &RuleContext, _node: &'ast syn::Stmt) {}
+
+  /// Called for every expression, before the hooks for its specific kind.
+  fn apply_expr(&mut self, _context: &RuleContext, _node: &'ast syn::Expr) {}
+
   fn apply_fn_arg(&mut self, _context: &RuleContext, _node: &'ast syn::FnArg) {}
 
   fn apply_expr_method_call(
@@ -24,34 +62,245 @@ pub trait Rule<'ast> {
   }
 }
 
+/// The kinds of node rules can hook into, one per `Rule::apply_*` method.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+  File,
+  Item,
+  ItemFn,
+  Stmt,
+  Expr,
+  FnArg,
+  ExprMethodCall,
+  ExprBinary,
+}
+
+impl NodeKind {
+  pub const ALL: &'static [NodeKind] = &[
+    NodeKind::File,
+    NodeKind::Item,
+    NodeKind::ItemFn,
+    NodeKind::Stmt,
+    NodeKind::Expr,
+    NodeKind::FnArg,
+    NodeKind::ExprMethodCall,
+    NodeKind::ExprBinary,
+  ];
+}
+
+/// Adds a fresh set of rules to an engine.
+///
+/// Rules hold per-file state and aren't required to be `Send`, so rather than
+/// sharing rule instances, every file linted gets its own engine populated by
+/// the factory. The factory itself is shared across threads.
+pub trait RuleFactory: Sync {
+  fn add_rules(&self, rule_engine: &mut RuleEngine<'_>);
+}
+
+impl<F> RuleFactory for F
+where
+  F: Fn(&mut RuleEngine<'_>) + Sync,
+{
+  fn add_rules(&self, rule_engine: &mut RuleEngine<'_>) {
+    self(rule_engine)
+  }
+}
+
+/// The kinds of item kept among the engine's ancestors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemKind {
+  Fn,
+  /// A function in an `impl` block.
+  Method,
+  Impl,
+  Mod,
+}
+
+/// The parts of a function, `impl` or `mod` the rules inside it look at,
+/// kept among the engine's ancestors in place of a copy of the whole item.
+pub struct ItemHeader {
+  pub kind: ItemKind,
+  pub attrs: Vec<syn::Attribute>,
+  /// The item's name, if it has one (`impl` blocks don't).
+  pub ident: Option<syn::Ident>,
+  pub span: proc_macro2::Span,
+}
+
 pub struct RuleContext {
   pub ancestors: Vec<Box<dyn std::any::Any>>,
+  rule: std::cell::Cell<&'static str>,
+  diagnostics: std::cell::RefCell<Vec<Diagnostic>>,
+  source: Option<source::SourceFile>,
+  types: std::cell::OnceCell<std::rc::Rc<types::LocalTypes>>,
 }
 
 impl RuleContext {
+  /// The text of the file being linted, if the engine was given it.
+  pub fn source(&self) -> Option<&source::SourceFile> {
+    self.source.as_ref()
+  }
+
+  /// The original text spanned by `node`, for building suggestions that keep
+  /// the node's comments and formatting intact.
+  pub fn snippet(&self, node: &dyn syn::spanned::Spanned) -> Option<&str> {
+    self.source.as_ref()?.snippet(node)
+  }
+
+  /// The numeric types `file`, the file being linted, spells out. Worked out
+  /// once per file and shared by every rule that asks.
+  pub fn types(&self, file: &syn::File) -> std::rc::Rc<types::LocalTypes> {
+    self
+      .types
+      .get_or_init(|| std::rc::Rc::new(types::LocalTypes::new(file)))
+      .clone()
+  }
+
   pub fn emit_report(&self, report: Report) {
-    let start = report.node.span().start();
-    println!(
-      "[foo.rs:{}:{}] {}",
-      start.line, start.column, report.message
-    )
+    let span = report.node.span();
+    self.diagnostics.borrow_mut().push(Diagnostic {
+      rule: self.rule.get().into(),
+      message: report.message,
+      start: span.start().into(),
+      end: span.end().into(),
+      suggestion: report.suggestion,
+    })
+  }
+
+  /// Emits an already located diagnostic, e.g. one produced by a rule plugin,
+  /// keeping its rule name.
+  pub fn emit_diagnostic(&self, diagnostic: Diagnostic) {
+    self.diagnostics.borrow_mut().push(diagnostic)
   }
 }
 
-struct RuleEngine<'a> {
+pub struct RuleEngine<'a> {
   rules: Vec<Box<dyn Rule<'a>>>,
+  names: Vec<&'static str>,
+  /// Indices into `rules` of the rules interested in each `NodeKind`.
+  dispatch: Vec<Vec<usize>>,
   context: RuleContext,
 }
 
 pub struct Report<'a> {
   pub message: String,
   pub node: &'a dyn syn::spanned::Spanned,
+  pub suggestion: Option<Suggestion>,
+}
+
+/// Line (1-indexed) and column (0-indexed, in chars) within a source file, as
+/// reported by `proc_macro2` span locations.
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  PartialEq,
+  Eq,
+  PartialOrd,
+  Ord,
+  Hash,
+  serde::Serialize,
+  serde::Deserialize,
+)]
+pub struct Position {
+  pub line: usize,
+  pub column: usize,
+}
+
+impl From<proc_macro2::LineColumn> for Position {
+  fn from(line_column: proc_macro2::LineColumn) -> Self {
+    Self {
+      line: line_column.line,
+      column: line_column.column,
+    }
+  }
+}
+
+/// An owned report, detached from the syntax tree it was emitted for.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Diagnostic {
+  pub rule: String,
+  pub message: String,
+  pub start: Position,
+  pub end: Position,
+  pub suggestion: Option<Suggestion>,
+}
+
+/// A file that failed to parse, detached from the `syn::Error` (whose spans
+/// can't be resolved off the thread that produced them).
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ParseError {
+  pub start: Position,
+  pub message: String,
+}
+
+impl From<syn::Error> for ParseError {
+  fn from(err: syn::Error) -> Self {
+    Self {
+      start: err.span().start().into(),
+      message: err.to_string(),
+    }
+  }
+}
+
+/// Parses `source` and runs the rules added by `factory` over it.
+pub fn lint(
+  source: &str,
+  factory: &(impl RuleFactory + ?Sized),
+) -> Result<Vec<Diagnostic>, ParseError> {
+  let syntax_tree = syn::parse_file(source)?;
+  let mut rule_engine = RuleEngine::with_source(source);
+  factory.add_rules(&mut rule_engine);
+  Ok(rule_engine.apply_rules(&syntax_tree))
+}
+
+#[derive(
+  Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize,
+)]
+pub enum Applicability {
+  /// The edits are known to be correct and can be applied by `sins fix`.
+  MachineApplicable,
+  /// The edits are a best guess and should be reviewed by a human.
+  MaybeIncorrect,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Suggestion {
+  pub message: String,
+  pub applicability: Applicability,
+  pub edits: Vec<Edit>,
+}
+
+/// Replaces the source text between `start` and `end` with `replacement`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Edit {
+  pub start: Position,
+  pub end: Position,
+  pub replacement: String,
+}
+
+impl Edit {
+  pub fn replace(
+    node: &dyn syn::spanned::Spanned,
+    replacement: impl Into<String>,
+  ) -> Self {
+    let span = node.span();
+    Self {
+      start: span.start().into(),
+      end: span.end().into(),
+      replacement: replacement.into(),
+    }
+  }
 }
 
 impl<'ast> syn::visit::Visit<'ast> for RuleEngine<'ast> {
   fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
     self.apply_item_fn_rules(node);
-    self.context.ancestors.push(Box::new(node.clone())); // todo: is this clone needed?
+    self.context.ancestors.push(Box::new(ItemHeader {
+      kind: ItemKind::Fn,
+      attrs: node.attrs.clone(),
+      ident: Some(node.sig.ident.clone()),
+      span: node.sig.fn_token.span,
+    }));
     syn::visit::visit_item_fn(self, node);
     self.context.ancestors.pop();
   }
@@ -142,8 +391,9 @@ impl<'ast> syn::visit::Visit<'ast> for RuleEngine<'ast> {
     syn::visit::visit_derive_input(self, i);
   }
 
-  fn visit_expr(&mut self, i: &'ast syn::Expr) {
-    syn::visit::visit_expr(self, i);
+  fn visit_expr(&mut self, node: &'ast syn::Expr) {
+    self.apply_expr_rules(node);
+    syn::visit::visit_expr(self, node);
   }
 
   fn visit_expr_array(&mut self, i: &'ast syn::ExprArray) {
@@ -325,8 +575,10 @@ impl<'ast> syn::visit::Visit<'ast> for RuleEngine<'ast> {
     syn::visit::visit_fields_unnamed(self, i);
   }
 
-  fn visit_file(&mut self, i: &'ast syn::File) {
-    syn::visit::visit_file(self, i);
+  fn visit_file(&mut self, node: &'ast syn::File) {
+    self.context.types.take();
+    self.apply_file_rules(node);
+    syn::visit::visit_file(self, node);
   }
 
   fn visit_foreign_item(&mut self, i: &'ast syn::ForeignItem) {
@@ -384,8 +636,15 @@ impl<'ast> syn::visit::Visit<'ast> for RuleEngine<'ast> {
     syn::visit::visit_impl_item_macro(self, i);
   }
 
-  fn visit_impl_item_method(&mut self, i: &'ast syn::ImplItemMethod) {
-    syn::visit::visit_impl_item_method(self, i);
+  fn visit_impl_item_method(&mut self, node: &'ast syn::ImplItemMethod) {
+    self.context.ancestors.push(Box::new(ItemHeader {
+      kind: ItemKind::Method,
+      attrs: node.attrs.clone(),
+      ident: Some(node.sig.ident.clone()),
+      span: node.sig.fn_token.span,
+    }));
+    syn::visit::visit_impl_item_method(self, node);
+    self.context.ancestors.pop();
   }
 
   fn visit_impl_item_type(&mut self, i: &'ast syn::ImplItemType) {
@@ -396,8 +655,9 @@ impl<'ast> syn::visit::Visit<'ast> for RuleEngine<'ast> {
     syn::visit::visit_index(self, i);
   }
 
-  fn visit_item(&mut self, i: &'ast syn::Item) {
-    syn::visit::visit_item(self, i);
+  fn visit_item(&mut self, node: &'ast syn::Item) {
+    self.apply_item_rules(node);
+    syn::visit::visit_item(self, node);
   }
 
   fn visit_item_const(&mut self, i: &'ast syn::ItemConst) {
@@ -416,8 +676,15 @@ impl<'ast> syn::visit::Visit<'ast> for RuleEngine<'ast> {
     syn::visit::visit_item_foreign_mod(self, i);
   }
 
-  fn visit_item_impl(&mut self, i: &'ast syn::ItemImpl) {
-    syn::visit::visit_item_impl(self, i);
+  fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+    self.context.ancestors.push(Box::new(ItemHeader {
+      kind: ItemKind::Impl,
+      attrs: node.attrs.clone(),
+      ident: None,
+      span: node.impl_token.span,
+    }));
+    syn::visit::visit_item_impl(self, node);
+    self.context.ancestors.pop();
   }
 
   fn visit_item_macro(&mut self, i: &'ast syn::ItemMacro) {
@@ -428,8 +695,15 @@ impl<'ast> syn::visit::Visit<'ast> for RuleEngine<'ast> {
     syn::visit::visit_item_macro2(self, i);
   }
 
-  fn visit_item_mod(&mut self, i: &'ast syn::ItemMod) {
-    syn::visit::visit_item_mod(self, i);
+  fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+    self.context.ancestors.push(Box::new(ItemHeader {
+      kind: ItemKind::Mod,
+      attrs: node.attrs.clone(),
+      ident: Some(node.ident.clone()),
+      span: node.mod_token.span,
+    }));
+    syn::visit::visit_item_mod(self, node);
+    self.context.ancestors.pop();
   }
 
   fn visit_item_static(&mut self, i: &'ast syn::ItemStatic) {
@@ -659,8 +933,9 @@ impl<'ast> syn::visit::Visit<'ast> for RuleEngine<'ast> {
     syn::visit::visit_span(self, i);
   }
 
-  fn visit_stmt(&mut self, i: &'ast syn::Stmt) {
-    syn::visit::visit_stmt(self, i);
+  fn visit_stmt(&mut self, node: &'ast syn::Stmt) {
+    self.apply_stmt_rules(node);
+    syn::visit::visit_stmt(self, node);
   }
 
   fn visit_trait_bound(&mut self, i: &'ast syn::TraitBound) {
@@ -820,64 +1095,137 @@ impl<'ast> syn::visit::Visit<'ast> for RuleEngine<'ast> {
   }
 }
 
+impl<'ast> Default for RuleEngine<'ast> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 impl<'ast> RuleEngine<'ast> {
   pub fn new() -> Self {
     Self {
       rules: vec![],
-      context: RuleContext { ancestors: vec![] },
+      names: vec![],
+      dispatch: vec![vec![]; NodeKind::ALL.len()],
+      context: RuleContext {
+        ancestors: vec![],
+        rule: std::cell::Cell::new(""),
+        diagnostics: std::cell::RefCell::new(vec![]),
+        source: None,
+        types: std::cell::OnceCell::new(),
+      },
     }
   }
 
+  /// Creates an engine whose rules can look up the original text of `source`
+  /// through `RuleContext::snippet`.
+  pub fn with_source(source: &str) -> Self {
+    let mut rule_engine = Self::new();
+    rule_engine.context.source = Some(source::SourceFile::new(source));
+    rule_engine
+  }
+
   pub fn add_rule(&mut self, rule: Box<dyn Rule<'ast>>) {
+    let index = self.rules.len();
+    for &kind in rule.node_kinds() {
+      let rules = &mut self.dispatch[kind as usize];
+      if rules.last() != Some(&index) {
+        rules.push(index);
+      }
+    }
+    self.names.push(rule.name());
     self.rules.push(rule);
   }
 
-  pub fn apply_rules(&mut self, syntax_tree: &'ast syn::File) {
+  /// Names and versions of the rules added to this engine, in order.
+  pub fn rule_versions(&self) -> Vec<(&'static str, u32)> {
+    self
+      .rules
+      .iter()
+      .map(|rule| (rule.name(), rule.version()))
+      .collect()
+  }
+
+  /// Runs every rule over `syntax_tree`, returning the reports emitted in
+  /// traversal order.
+  pub fn apply_rules(
+    &mut self,
+    syntax_tree: &'ast syn::File,
+  ) -> Vec<Diagnostic> {
     use syn::visit::Visit;
-    self.visit_file(&syntax_tree);
+    self.visit_file(syntax_tree);
+    self.context.diagnostics.take()
+  }
+
+  fn dispatch(
+    &mut self,
+    kind: NodeKind,
+    mut apply: impl FnMut(&mut dyn Rule<'ast>, &RuleContext),
+  ) {
+    for &index in &self.dispatch[kind as usize] {
+      self.context.rule.set(self.names[index]);
+      apply(self.rules[index].as_mut(), &self.context);
+    }
   }
 
   fn apply_item_fn_rules(&mut self, node: &'ast syn::ItemFn) {
-    self
-      .rules
-      .iter_mut()
-      .for_each(|rule| rule.apply_item_fn(&self.context, node))
+    self.dispatch(NodeKind::ItemFn, |rule, context| {
+      rule.apply_item_fn(context, node)
+    })
+  }
+
+  fn apply_file_rules(&mut self, node: &'ast syn::File) {
+    self.dispatch(NodeKind::File, |rule, context| {
+      rule.apply_file(context, node)
+    })
+  }
+
+  fn apply_item_rules(&mut self, node: &'ast syn::Item) {
+    self.dispatch(NodeKind::Item, |rule, context| {
+      rule.apply_item(context, node)
+    })
+  }
+
+  fn apply_stmt_rules(&mut self, node: &'ast syn::Stmt) {
+    self.dispatch(NodeKind::Stmt, |rule, context| {
+      rule.apply_stmt(context, node)
+    })
+  }
+
+  fn apply_expr_rules(&mut self, node: &'ast syn::Expr) {
+    self.dispatch(NodeKind::Expr, |rule, context| {
+      rule.apply_expr(context, node)
+    })
   }
 
   fn apply_fn_arg_rules(&mut self, node: &'ast syn::FnArg) {
-    self
-      .rules
-      .iter_mut()
-      .for_each(|rule| rule.apply_fn_arg(&self.context, node))
+    self.dispatch(NodeKind::FnArg, |rule, context| {
+      rule.apply_fn_arg(context, node)
+    })
   }
 
   fn apply_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
-    self
-      .rules
-      .iter_mut()
-      .for_each(|rule| rule.apply_expr_method_call(&self.context, node))
+    self.dispatch(NodeKind::ExprMethodCall, |rule, context| {
+      rule.apply_expr_method_call(context, node)
+    })
   }
 
   fn apply_expr_binary(&mut self, node: &'ast syn::ExprBinary) {
-    self
-      .rules
-      .iter_mut()
-      .for_each(|rule| rule.apply_expr_binary(&self.context, node))
+    self.dispatch(NodeKind::ExprBinary, |rule, context| {
+      rule.apply_expr_binary(context, node)
+    })
   }
 }
 
 #[cfg(test)]
 mod tests {
-  use syn::visit::Visit;
-
   use super::*;
 
   #[test]
   fn ok() {
-    let mut file = std::fs::File::open(
-      "/Users/paul.sastrasinh/repos/sins/sins-lib/src/lib.rs",
-    )
-    .unwrap();
+    let mut file =
+      std::fs::File::open(concat!(env!("CARGO_MANIFEST_DIR"), "/src/lib.rs"))
+        .unwrap();
     let mut content = String::new();
     std::io::Read::read_to_string(&mut file, &mut content).unwrap();
     let syntax_tree = syn::parse_file(&content).unwrap();
@@ -893,6 +1241,7 @@ mod tests {
           context.emit_report(Report {
             message: "bad usage".into(),
             node,
+            suggestion: None,
           })
         }
       }
@@ -901,6 +1250,105 @@ mod tests {
 
     let mut rule_engine = RuleEngine::new();
     rule_engine.add_rule(Box::new(foo_rule));
+    let diagnostics = rule_engine.apply_rules(&syntax_tree);
+    assert!(!diagnostics.is_empty());
+    assert!(diagnostics
+      .iter()
+      .all(|d| d.rule.ends_with("FooFunctionNameRule")));
+  }
+
+  #[test]
+  fn test_dispatch_skips_uninterested_rules() {
+    struct BinaryRule(std::rc::Rc<std::cell::Cell<usize>>);
+    impl<'ast> Rule<'ast> for BinaryRule {
+      fn node_kinds(&self) -> &'static [NodeKind] {
+        &[NodeKind::ExprBinary, NodeKind::ExprBinary]
+      }
+
+      fn apply_item_fn(&mut self, _: &RuleContext, _: &'ast syn::ItemFn) {
+        unreachable!("not interested in functions")
+      }
+
+      fn apply_expr_binary(
+        &mut self,
+        _: &RuleContext,
+        _: &'ast syn::ExprBinary,
+      ) {
+        self.0.set(self.0.get() + 1);
+      }
+    }
+
+    let syntax_tree = syn::parse_file("fn main() { a + b * c; }").unwrap();
+    let calls = std::rc::Rc::new(std::cell::Cell::new(0));
+    let mut rule_engine = RuleEngine::new();
+    rule_engine.add_rule(Box::new(BinaryRule(calls.clone())));
     rule_engine.apply_rules(&syntax_tree);
+    assert_eq!(calls.get(), 2);
+  }
+
+  #[test]
+  fn test_types_shared_between_rules() {
+    type Seen =
+      std::rc::Rc<std::cell::RefCell<Vec<std::rc::Rc<types::LocalTypes>>>>;
+    struct TypesRule(Seen);
+    impl<'ast> Rule<'ast> for TypesRule {
+      fn node_kinds(&self) -> &'static [NodeKind] {
+        &[NodeKind::File]
+      }
+
+      fn apply_file(&mut self, context: &RuleContext, node: &'ast syn::File) {
+        self.0.borrow_mut().push(context.types(node));
+      }
+    }
+
+    let syntax_tree = syn::parse_file("fn main() {}").unwrap();
+    let seen = Seen::default();
+    let mut rule_engine = RuleEngine::new();
+    rule_engine.add_rule(Box::new(TypesRule(seen.clone())));
+    rule_engine.add_rule(Box::new(TypesRule(seen.clone())));
+    rule_engine.apply_rules(&syntax_tree);
+    rule_engine.apply_rules(&syntax_tree);
+    let seen = seen.borrow();
+    assert!(std::rc::Rc::ptr_eq(&seen[0], &seen[1]));
+    assert!(!std::rc::Rc::ptr_eq(&seen[1], &seen[2]));
+  }
+
+  #[test]
+  fn test_lint_with_factory_across_threads() {
+    struct CountingRule(usize);
+    impl<'ast> Rule<'ast> for CountingRule {
+      fn apply_item_fn(
+        &mut self,
+        context: &RuleContext,
+        node: &'ast syn::ItemFn,
+      ) {
+        self.0 += 1;
+        context.emit_report(Report {
+          message: format!("fn #{}", self.0),
+          node,
+          suggestion: None,
+        })
+      }
+    }
+
+    let factory = |rule_engine: &mut RuleEngine| {
+      rule_engine.add_rule(Box::new(CountingRule(0)))
+    };
+    let sources = ["fn a() {}", "fn b() {} fn c() {}", "fn ("];
+    let results: Vec<_> = std::thread::scope(|scope| {
+      let handles: Vec<_> = sources
+        .iter()
+        .map(|source| scope.spawn(|| lint(source, &factory)))
+        .collect();
+      handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let messages = |i: usize| -> Vec<String> {
+      let diagnostics = results[i].as_ref().unwrap();
+      diagnostics.iter().map(|d| d.message.clone()).collect()
+    };
+    assert_eq!(messages(0), ["fn #1"]);
+    assert_eq!(messages(1), ["fn #1", "fn #2"]);
+    assert_eq!(results[2].as_ref().unwrap_err().start.line, 1);
   }
 }
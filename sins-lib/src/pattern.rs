@@ -0,0 +1,830 @@
+//! Structural search, with patterns written as Rust source.
+//!
+//! A pattern is an expression, item or statement in which `$name` stands for
+//! any expression (binding it to `name`) and `$...name` for any number of
+//! arguments, list elements or statements. `$_` and `$..._` match without
+//! binding, and a bare `...` is short for `$..._`. A name used twice must
+//! match the same tokens both times.
+//!
+//! Common kinds of expression are compared node by node. Anything else,
+//! including macro arguments and types, is compared token by token, where
+//! `$name` matches a single token tree.
+
+use std::collections::BTreeMap;
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::source::{apply_splices, SourceFile, Splice};
+use crate::{NodeKind, ParseError, Position, Rule, RuleContext, RuleEngine};
+
+const VAR_PREFIX: &str = "__sins_var_";
+const VARS_PREFIX: &str = "__sins_vars_";
+
+/// A compiled pattern.
+///
+/// Patterns hold `proc_macro2` spans, so they can't be sent between threads;
+/// compile them where they're used.
+#[derive(Clone, Debug)]
+pub enum Pattern {
+  Expr(syn::Expr),
+  /// Items are matched token by token, which lets `$...name` stand in for
+  /// parameters and fields where the parser wouldn't accept an expression.
+  Item(TokenStream),
+  Stmt(syn::Stmt),
+}
+
+/// A node a pattern can be matched against.
+#[derive(Clone, Copy, Debug)]
+pub enum Node<'a> {
+  Expr(&'a syn::Expr),
+  Item(&'a syn::Item),
+  Stmt(&'a syn::Stmt),
+}
+
+impl<'a> Node<'a> {
+  pub fn as_spanned(&self) -> &'a dyn Spanned {
+    match *self {
+      Node::Expr(node) => node,
+      Node::Item(node) => node,
+      Node::Stmt(node) => node,
+    }
+  }
+}
+
+/// What a metavariable matched.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Binding {
+  /// The matched tokens, printed. Used to compare repeated metavariables.
+  pub tokens: String,
+  /// Where the match lies in the source, or `None` for an empty `$...name`.
+  pub range: Option<(Position, Position)>,
+}
+
+impl Binding {
+  /// The matched source text, or the printed tokens if it can't be found.
+  pub fn text(&self, source: Option<&SourceFile>) -> String {
+    self
+      .range
+      .and_then(|(start, end)| {
+        let source = source?;
+        Some(source.text()[source.byte_range(start, end)?].to_owned())
+      })
+      .unwrap_or_else(|| self.tokens.clone())
+  }
+}
+
+/// Bindings of a successful match, by metavariable name.
+pub type Bindings = BTreeMap<String, Binding>;
+
+impl Pattern {
+  /// Parses `pattern` as an expression, or failing that as an item or a
+  /// statement.
+  pub fn parse(pattern: &str) -> Result<Self, String> {
+    let invalid = |err| format!("invalid pattern `{}`: {}", pattern, err);
+    let text = replace_metavariables(pattern);
+    let err = match syn::parse_str(&text) {
+      Ok(expr) => return Ok(Pattern::Expr(expr)),
+      Err(err) => err,
+    };
+    let tokens: TokenStream = text.parse().map_err(|_| invalid(&err))?;
+    if syn::parse2::<syn::Item>(without_ellipses(tokens.clone())).is_ok() {
+      return Ok(Pattern::Item(tokens));
+    }
+    if let Ok(stmt) = syn::parse_str(&text) {
+      return Ok(Pattern::Stmt(stmt));
+    }
+    Err(invalid(&err))
+  }
+
+  /// The kind of node the pattern matches.
+  pub fn node_kind(&self) -> NodeKind {
+    match self {
+      Pattern::Expr(_) => NodeKind::Expr,
+      Pattern::Item(_) => NodeKind::Item,
+      Pattern::Stmt(_) => NodeKind::Stmt,
+    }
+  }
+
+  /// Matches the pattern against `node` itself (not its descendants).
+  pub fn matches(&self, node: Node) -> Option<Bindings> {
+    let mut matcher = Matcher::default();
+    let matched = match (self, node) {
+      (Pattern::Expr(pattern), Node::Expr(node)) => matcher.expr(pattern, node),
+      (Pattern::Item(pattern), Node::Item(node)) => {
+        matcher.tokens(pattern, node)
+      }
+      (Pattern::Stmt(pattern), Node::Stmt(node)) => matcher.stmt(pattern, node),
+      _ => false,
+    };
+    matched.then_some(matcher.bindings)
+  }
+}
+
+/// A match found by `search`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Match {
+  pub start: Position,
+  pub end: Position,
+  /// The source text bound to each metavariable.
+  pub bindings: BTreeMap<String, String>,
+}
+
+/// Finds every node in `source` that `pattern` matches, in traversal order.
+pub fn search(
+  source: &str,
+  pattern: &Pattern,
+) -> Result<Vec<Match>, ParseError> {
+  struct SearchRule {
+    pattern: Pattern,
+    matches: Rc<RefCell<Vec<Match>>>,
+  }
+
+  impl SearchRule {
+    fn apply(&mut self, context: &RuleContext, node: Node) {
+      if let Some(bindings) = self.pattern.matches(node) {
+        let span = node.as_spanned().span();
+        self.matches.borrow_mut().push(Match {
+          start: span.start().into(),
+          end: span.end().into(),
+          bindings: texts(&bindings, context.source()),
+        });
+      }
+    }
+  }
+
+  impl<'ast> Rule<'ast> for SearchRule {
+    fn node_kinds(&self) -> &'static [NodeKind] {
+      match self.pattern.node_kind() {
+        NodeKind::Item => &[NodeKind::Item],
+        NodeKind::Stmt => &[NodeKind::Stmt],
+        _ => &[NodeKind::Expr],
+      }
+    }
+
+    fn apply_item(&mut self, context: &RuleContext, node: &'ast syn::Item) {
+      self.apply(context, Node::Item(node))
+    }
+
+    fn apply_stmt(&mut self, context: &RuleContext, node: &'ast syn::Stmt) {
+      self.apply(context, Node::Stmt(node))
+    }
+
+    fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+      self.apply(context, Node::Expr(node))
+    }
+  }
+
+  let syntax_tree = syn::parse_file(source)?;
+  let matches = Rc::new(RefCell::new(vec![]));
+  let mut rule_engine = RuleEngine::with_source(source);
+  rule_engine.add_rule(Box::new(SearchRule {
+    pattern: pattern.clone(),
+    matches: matches.clone(),
+  }));
+  rule_engine.apply_rules(&syntax_tree);
+  Ok(matches.take())
+}
+
+/// The result of `rewrite`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rewrite {
+  pub source: String,
+  pub replacements: usize,
+  /// Set if the rewritten source no longer parses.
+  pub error: Option<ParseError>,
+}
+
+/// Replaces every match of `pattern` in `source` with `template`, its
+/// metavariables substituted. Matches nested in a replaced match are left
+/// alone.
+pub fn rewrite(
+  source: &str,
+  pattern: &Pattern,
+  template: &str,
+) -> Result<Rewrite, ParseError> {
+  let matches = search(source, pattern)?;
+  let file = SourceFile::new(source);
+  let mut splices: Vec<Splice> = vec![];
+  // Matches come in traversal order, so enclosing ones come first.
+  for m in matches {
+    let range = match file.byte_range(m.start, m.end) {
+      Some(range) => range,
+      None => continue,
+    };
+    if splices.iter().any(|splice| range.start < splice.range.end) {
+      continue;
+    }
+    splices.push(Splice {
+      range,
+      replacement: substitute(template, &m.bindings),
+    });
+  }
+  let replacements = splices.len();
+  let source = apply_splices(source, splices);
+  let error = syn::parse_file(&source).err().map(ParseError::from);
+  Ok(Rewrite {
+    source,
+    replacements,
+    error,
+  })
+}
+
+/// The source text of each binding.
+pub fn texts(
+  bindings: &Bindings,
+  source: Option<&SourceFile>,
+) -> BTreeMap<String, String> {
+  bindings
+    .iter()
+    .map(|(name, binding)| (name.clone(), binding.text(source)))
+    .collect()
+}
+
+/// Replaces each `$name` in `template` with the text bound to `name`, leaving
+/// unbound metavariables as they are.
+pub fn substitute(
+  template: &str,
+  bindings: &BTreeMap<String, String>,
+) -> String {
+  let mut result = String::new();
+  let mut rest = template;
+  while let Some(index) = rest.find('$') {
+    result.push_str(&rest[..index]);
+    rest = &rest[index..];
+    let (name, len) = metavariable(rest).unwrap_or(("", 1));
+    match bindings.get(name) {
+      Some(text) => result.push_str(text),
+      None => result.push_str(&rest[..len]),
+    }
+    rest = &rest[len..];
+  }
+  result.push_str(rest);
+  result
+}
+
+/// Parses the `$name` or `$...name` at the start of `text` into its name and
+/// length in bytes.
+fn metavariable(text: &str) -> Option<(&str, usize)> {
+  let after_dollar = text.strip_prefix('$')?;
+  let after_dots = after_dollar.strip_prefix("...").unwrap_or(after_dollar);
+  let len = after_dots
+    .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+    .unwrap_or(after_dots.len());
+  let name = &after_dots[..len];
+  if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+    return None;
+  }
+  Some((name, text.len() - after_dots.len() + len))
+}
+
+/// Removes `$...name` placeholders, along with a comma following them.
+fn without_ellipses(tokens: TokenStream) -> TokenStream {
+  let mut result = vec![];
+  let mut skip_comma = false;
+  for token in tokens {
+    match &token {
+      TokenTree::Ident(ident) if matches!(var(ident), Some(Var::Many(_))) => {
+        skip_comma = true;
+        continue;
+      }
+      TokenTree::Punct(punct) if skip_comma && punct.as_char() == ',' => {}
+      TokenTree::Group(group) => {
+        let mut inner = proc_macro2::Group::new(
+          group.delimiter(),
+          without_ellipses(group.stream()),
+        );
+        inner.set_span(group.span());
+        result.push(TokenTree::Group(inner));
+      }
+      _ => result.push(token.clone()),
+    }
+    skip_comma = false;
+  }
+  result.into_iter().collect()
+}
+
+/// Rewrites metavariables into identifiers, so that the pattern parses as
+/// Rust. A bare `...` is taken as `$..._`.
+fn replace_metavariables(pattern: &str) -> String {
+  let mut result = String::new();
+  let mut rest = pattern;
+  while let Some(index) = rest.find(['$', '.', '"']) {
+    result.push_str(&rest[..index]);
+    rest = &rest[index..];
+    if rest.starts_with('"') {
+      let len = string_literal_len(rest);
+      result.push_str(&rest[..len]);
+      rest = &rest[len..];
+    } else if let Some(after_dots) = rest.strip_prefix("...") {
+      result.push_str(&format!(" {}_ ", VARS_PREFIX));
+      rest = after_dots;
+    } else if let Some((name, len)) = metavariable(rest) {
+      let prefix = if rest.starts_with("$...") {
+        VARS_PREFIX
+      } else {
+        VAR_PREFIX
+      };
+      result.push_str(prefix);
+      result.push_str(name);
+      rest = &rest[len..];
+    } else {
+      result.push_str(&rest[..1]);
+      rest = &rest[1..];
+    }
+  }
+  result.push_str(rest);
+  result
+}
+
+/// The length of the string literal `text` starts with, up to and including
+/// its closing quote.
+fn string_literal_len(text: &str) -> usize {
+  let mut escaped = false;
+  for (index, c) in text.char_indices().skip(1) {
+    match c {
+      '"' if !escaped => return index + 1,
+      '\\' => escaped = !escaped,
+      _ => escaped = false,
+    }
+  }
+  text.len()
+}
+
+enum Var {
+  One(String),
+  Many(String),
+}
+
+fn var(ident: &syn::Ident) -> Option<Var> {
+  let ident = ident.to_string();
+  if let Some(name) = ident.strip_prefix(VARS_PREFIX) {
+    Some(Var::Many(name.to_owned()))
+  } else {
+    ident
+      .strip_prefix(VAR_PREFIX)
+      .map(|name| Var::One(name.to_owned()))
+  }
+}
+
+fn expr_var(expr: &syn::Expr) -> Option<Var> {
+  match expr {
+    syn::Expr::Path(path) if path.qself.is_none() => {
+      var(path.path.get_ident()?)
+    }
+    _ => None,
+  }
+}
+
+fn pat_var(pat: &syn::Pat) -> Option<Var> {
+  match pat {
+    syn::Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => {
+      var(&pat.ident)
+    }
+    _ => None,
+  }
+}
+
+fn stmt_var(stmt: &syn::Stmt) -> Option<Var> {
+  match stmt {
+    syn::Stmt::Expr(expr) | syn::Stmt::Semi(expr, _) => expr_var(expr),
+    _ => None,
+  }
+}
+
+fn many_var<T>(var: fn(&T) -> Option<Var>) -> impl Fn(&T) -> Option<String> {
+  move |node| match var(node)? {
+    Var::Many(name) => Some(name),
+    Var::One(_) => None,
+  }
+}
+
+#[derive(Default)]
+struct Matcher {
+  bindings: Bindings,
+}
+
+impl Matcher {
+  fn bind(&mut self, name: &str, binding: Binding) -> bool {
+    if name == "_" {
+      return true;
+    }
+    match self.bindings.get(name) {
+      Some(bound) => bound.tokens == binding.tokens,
+      None => {
+        self.bindings.insert(name.to_owned(), binding);
+        true
+      }
+    }
+  }
+
+  fn bind_nodes<T: ToTokens + Spanned>(
+    &mut self,
+    name: &str,
+    nodes: &[&T],
+  ) -> bool {
+    let tokens = nodes
+      .iter()
+      .map(|node| node.to_token_stream().to_string())
+      .collect::<Vec<_>>()
+      .join(" , ");
+    let range = nodes.first().zip(nodes.last()).map(|(first, last)| {
+      (first.span().start().into(), last.span().end().into())
+    });
+    self.bind(name, Binding { tokens, range })
+  }
+
+  fn expr(&mut self, pattern: &syn::Expr, target: &syn::Expr) -> bool {
+    use syn::Expr;
+
+    // Outside of lists, `$...name` can only stand for one expression.
+    if let Some(Var::One(name) | Var::Many(name)) = expr_var(pattern) {
+      return self.bind_nodes(&name, &[target]);
+    }
+    if std::mem::discriminant(pattern) != std::mem::discriminant(target) {
+      return false;
+    }
+    match (pattern, target) {
+      (Expr::MethodCall(p), Expr::MethodCall(t)) => {
+        self.expr(&p.receiver, &t.receiver)
+          && self.ident(&p.method, &t.method)
+          && self.tokens(&p.turbofish, &t.turbofish)
+          && self.exprs(&p.args, &t.args)
+      }
+      (Expr::Call(p), Expr::Call(t)) => {
+        self.expr(&p.func, &t.func) && self.exprs(&p.args, &t.args)
+      }
+      (Expr::Binary(p), Expr::Binary(t)) => {
+        self.tokens(&p.op, &t.op)
+          && self.expr(&p.left, &t.left)
+          && self.expr(&p.right, &t.right)
+      }
+      (Expr::Unary(p), Expr::Unary(t)) => {
+        self.tokens(&p.op, &t.op) && self.expr(&p.expr, &t.expr)
+      }
+      (Expr::Field(p), Expr::Field(t)) => {
+        let member = match (&p.member, &t.member) {
+          (syn::Member::Named(p), syn::Member::Named(t)) => self.ident(p, t),
+          (p, t) => p == t,
+        };
+        member && self.expr(&p.base, &t.base)
+      }
+      (Expr::Index(p), Expr::Index(t)) => {
+        self.expr(&p.expr, &t.expr) && self.expr(&p.index, &t.index)
+      }
+      (Expr::Paren(p), Expr::Paren(t)) => self.expr(&p.expr, &t.expr),
+      (Expr::Reference(p), Expr::Reference(t)) => {
+        p.mutability.is_some() == t.mutability.is_some()
+          && self.expr(&p.expr, &t.expr)
+      }
+      (Expr::Cast(p), Expr::Cast(t)) => {
+        self.expr(&p.expr, &t.expr) && self.tokens(&p.ty, &t.ty)
+      }
+      (Expr::Try(p), Expr::Try(t)) => self.expr(&p.expr, &t.expr),
+      (Expr::Await(p), Expr::Await(t)) => self.expr(&p.base, &t.base),
+      (Expr::Tuple(p), Expr::Tuple(t)) => self.exprs(&p.elems, &t.elems),
+      (Expr::Array(p), Expr::Array(t)) => self.exprs(&p.elems, &t.elems),
+      (Expr::Assign(p), Expr::Assign(t)) => {
+        self.expr(&p.left, &t.left) && self.expr(&p.right, &t.right)
+      }
+      (Expr::AssignOp(p), Expr::AssignOp(t)) => {
+        self.tokens(&p.op, &t.op)
+          && self.expr(&p.left, &t.left)
+          && self.expr(&p.right, &t.right)
+      }
+      (Expr::Return(p), Expr::Return(t)) => match (&p.expr, &t.expr) {
+        (Some(p), Some(t)) => self.expr(p, t),
+        (p, t) => p.is_none() && t.is_none(),
+      },
+      (Expr::Block(p), Expr::Block(t)) => {
+        self.tokens(&p.label, &t.label) && self.block(&p.block, &t.block)
+      }
+      (Expr::Unsafe(p), Expr::Unsafe(t)) => self.block(&p.block, &t.block),
+      (Expr::If(p), Expr::If(t)) => {
+        self.expr(&p.cond, &t.cond)
+          && self.block(&p.then_branch, &t.then_branch)
+          && match (&p.else_branch, &t.else_branch) {
+            (Some((_, p)), Some((_, t))) => self.expr(p, t),
+            (p, t) => p.is_none() && t.is_none(),
+          }
+      }
+      (Expr::Let(p), Expr::Let(t)) => {
+        self.pat(&p.pat, &t.pat) && self.expr(&p.expr, &t.expr)
+      }
+      (Expr::Closure(p), Expr::Closure(t)) => {
+        let p_inputs: Vec<_> = p.inputs.iter().collect();
+        let t_inputs: Vec<_> = t.inputs.iter().collect();
+        p.asyncness.is_some() == t.asyncness.is_some()
+          && p.capture.is_some() == t.capture.is_some()
+          && self.list(&p_inputs, &t_inputs, &many_var(pat_var), Self::pat)
+          && self.tokens(&p.output, &t.output)
+          && self.expr(&p.body, &t.body)
+      }
+      (Expr::Macro(p), Expr::Macro(t)) => {
+        self.tokens(&p.mac.path, &t.mac.path)
+          && self.tokens(&p.mac.tokens, &t.mac.tokens)
+      }
+      _ => self.tokens(pattern, target),
+    }
+  }
+
+  fn ident(&mut self, pattern: &syn::Ident, target: &syn::Ident) -> bool {
+    match var(pattern) {
+      Some(Var::One(name)) => self.bind_nodes(&name, &[target]),
+      _ => pattern == target,
+    }
+  }
+
+  fn pat(&mut self, pattern: &syn::Pat, target: &syn::Pat) -> bool {
+    match pat_var(pattern) {
+      Some(Var::One(name)) => self.bind_nodes(&name, &[target]),
+      _ => self.tokens(pattern, target),
+    }
+  }
+
+  fn exprs<P>(
+    &mut self,
+    pattern: &Punctuated<syn::Expr, P>,
+    target: &Punctuated<syn::Expr, P>,
+  ) -> bool {
+    let pattern: Vec<_> = pattern.iter().collect();
+    let target: Vec<_> = target.iter().collect();
+    self.list(&pattern, &target, &many_var(expr_var), Self::expr)
+  }
+
+  fn block(&mut self, pattern: &syn::Block, target: &syn::Block) -> bool {
+    let pattern: Vec<_> = pattern.stmts.iter().collect();
+    let target: Vec<_> = target.stmts.iter().collect();
+    self.list(&pattern, &target, &many_var(stmt_var), Self::stmt)
+  }
+
+  fn stmt(&mut self, pattern: &syn::Stmt, target: &syn::Stmt) -> bool {
+    match (pattern, target) {
+      (syn::Stmt::Expr(p), syn::Stmt::Expr(t))
+      | (syn::Stmt::Semi(p, _), syn::Stmt::Semi(t, _)) => self.expr(p, t),
+      (syn::Stmt::Local(p), syn::Stmt::Local(t)) => {
+        self.pat(&p.pat, &t.pat)
+          && match (&p.init, &t.init) {
+            (Some((_, p)), Some((_, t))) => self.expr(p, t),
+            (p, t) => p.is_none() && t.is_none(),
+          }
+      }
+      _ => self.tokens(pattern, target),
+    }
+  }
+
+  /// Matches lists element by element, letting an element for which
+  /// `ellipsis` returns a name match any number of elements.
+  fn list<T: ToTokens + Spanned>(
+    &mut self,
+    pattern: &[&T],
+    target: &[&T],
+    ellipsis: &dyn Fn(&T) -> Option<String>,
+    element: fn(&mut Self, &T, &T) -> bool,
+  ) -> bool {
+    let (first, rest) = match pattern.split_first() {
+      Some(split) => split,
+      None => return target.is_empty(),
+    };
+    if let Some(name) = ellipsis(first) {
+      for len in 0..=target.len() {
+        let saved = self.bindings.clone();
+        if self.bind_nodes(&name, &target[..len])
+          && self.list(rest, &target[len..], ellipsis, element)
+        {
+          return true;
+        }
+        self.bindings = saved;
+      }
+      return false;
+    }
+    match target.split_first() {
+      Some((target_first, target_rest)) => {
+        element(self, first, target_first)
+          && self.list(rest, target_rest, ellipsis, element)
+      }
+      None => false,
+    }
+  }
+
+  fn tokens(&mut self, pattern: &dyn ToTokens, target: &dyn ToTokens) -> bool {
+    let pattern: Vec<_> = pattern.to_token_stream().into_iter().collect();
+    let target: Vec<_> = target.to_token_stream().into_iter().collect();
+    self.token_trees(&pattern, &target)
+  }
+
+  fn token_trees(
+    &mut self,
+    pattern: &[TokenTree],
+    target: &[TokenTree],
+  ) -> bool {
+    let (first, rest) = match pattern.split_first() {
+      Some(split) => split,
+      None => return target.is_empty(),
+    };
+    if let TokenTree::Ident(ident) = first {
+      match var(ident) {
+        Some(Var::Many(name)) => {
+          for len in 0..=target.len() {
+            let saved = self.bindings.clone();
+            if self.bind_tokens(&name, &target[..len])
+              && self.token_trees(rest, &target[len..])
+            {
+              return true;
+            }
+            self.bindings = saved;
+          }
+          return false;
+        }
+        Some(Var::One(name)) => {
+          return !target.is_empty()
+            && self.bind_tokens(&name, &target[..1])
+            && self.token_trees(rest, &target[1..]);
+        }
+        None => {}
+      }
+    }
+    let (target_first, target_rest) = match target.split_first() {
+      Some(split) => split,
+      None => return false,
+    };
+    let first_matches = match (first, target_first) {
+      (TokenTree::Group(p), TokenTree::Group(t)) => {
+        p.delimiter() == t.delimiter() && {
+          let p: Vec<_> = p.stream().into_iter().collect();
+          let t: Vec<_> = t.stream().into_iter().collect();
+          self.token_trees(&p, &t)
+        }
+      }
+      (TokenTree::Ident(p), TokenTree::Ident(t)) => p == t,
+      (TokenTree::Punct(p), TokenTree::Punct(t)) => {
+        p.as_char() == t.as_char() && p.spacing() == t.spacing()
+      }
+      (TokenTree::Literal(p), TokenTree::Literal(t)) => {
+        p.to_string() == t.to_string()
+      }
+      _ => false,
+    };
+    first_matches && self.token_trees(rest, target_rest)
+  }
+
+  fn bind_tokens(&mut self, name: &str, tokens: &[TokenTree]) -> bool {
+    let range = tokens.first().zip(tokens.last()).map(|(first, last)| {
+      (first.span().start().into(), last.span().end().into())
+    });
+    let tokens = tokens.iter().cloned().collect::<TokenStream>().to_string();
+    self.bind(name, Binding { tokens, range })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// The bindings of each match of `pattern` in `source`.
+  fn find(pattern: &str, source: &str) -> Vec<BTreeMap<String, String>> {
+    let pattern = Pattern::parse(pattern).unwrap();
+    let matches = search(source, &pattern).unwrap();
+    matches.into_iter().map(|m| m.bindings).collect()
+  }
+
+  fn bindings(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+      .iter()
+      .map(|(name, text)| (name.to_string(), text.to_string()))
+      .collect()
+  }
+
+  #[test]
+  fn test_metavariables() {
+    let source =
+      "fn main() { a.unwrap(); foo(b  +  1).unwrap(); c.expect(\"\"); }";
+    assert_eq!(
+      find("$x.unwrap()", source),
+      [bindings(&[("x", "a")]), bindings(&[("x", "foo(b  +  1)")])]
+    );
+    assert_eq!(find("$_.unwrap()", source).len(), 2);
+    assert_eq!(find("foo($y + 1)", source), [bindings(&[("y", "b")])]);
+  }
+
+  #[test]
+  fn test_repeated_metavariable() {
+    let source = "fn main() { a == a; a == b; f(x) == f(x); }";
+    assert_eq!(
+      find("$x == $x", source),
+      [bindings(&[("x", "a")]), bindings(&[("x", "f(x)")])]
+    );
+  }
+
+  #[test]
+  fn test_ellipsis() {
+    let source = "fn main() { f(); f(1); f(1, 2, 3); g(1, 2); }";
+    assert_eq!(
+      find("f($...args)", source),
+      [
+        bindings(&[("args", "")]),
+        bindings(&[("args", "1")]),
+        bindings(&[("args", "1, 2, 3")])
+      ]
+    );
+    assert_eq!(
+      find("$f($...rest, 3)", source),
+      [bindings(&[("f", "f"), ("rest", "1, 2")])]
+    );
+  }
+
+  #[test]
+  fn test_bare_ellipsis() {
+    let source = "fn main() { a(); }\nfn f(x: u8) {}\nfn g() { h(\"...\", 1) }";
+    assert_eq!(find("fn main() { ... }", source), [bindings(&[])]);
+    assert_eq!(find("fn $f(...) { ... }", source).len(), 3);
+    assert_eq!(find("h(\"...\", ...)", source), [bindings(&[])]);
+  }
+
+  #[test]
+  fn test_macro_tokens() {
+    let source = "fn main() { println!(\"{}\", x); panic!(\"{} {}\", a, b); }";
+    assert_eq!(
+      find("panic!($...args)", source),
+      [bindings(&[("args", "\"{} {}\", a, b")])]
+    );
+    assert_eq!(
+      find("println!($fmt, $x)", source),
+      [bindings(&[("fmt", "\"{}\""), ("x", "x")])]
+    );
+  }
+
+  #[test]
+  fn test_closures() {
+    let source = "fn main() { f(|a, b| a.g(b)); f(move || 1); }";
+    assert_eq!(
+      find("|$...args| $body", source),
+      [bindings(&[("args", "a, b"), ("body", "a.g(b)")])]
+    );
+    assert_eq!(
+      find("|$a, $b| $a.g($b)", source),
+      [bindings(&[("a", "a"), ("b", "b")])]
+    );
+  }
+
+  #[test]
+  fn test_block_statements() {
+    let source = "fn main() { unsafe { a(); b(); } unsafe { c(); } }";
+    assert_eq!(
+      find("unsafe { $...body; c(); }", source),
+      [bindings(&[("body", "")])]
+    );
+  }
+
+  #[test]
+  fn test_items_and_statements() {
+    let source = "fn a(x: u8) { let y = x.clone(); }\nfn b() {}\n";
+    let pattern = Pattern::parse("fn $name($...args) { $...body }").unwrap();
+    let matches = search(source, &pattern).unwrap();
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[1].start, Position { line: 2, column: 0 });
+    assert_eq!(
+      matches[1].bindings,
+      bindings(&[("args", ""), ("body", ""), ("name", "b")])
+    );
+    assert_eq!(
+      find("let $y = $x.clone();", source),
+      [bindings(&[("x", "x"), ("y", "y")])]
+    );
+  }
+
+  #[test]
+  fn test_substitute() {
+    let bindings = bindings(&[("f", "foo"), ("args", "a, b")]);
+    assert_eq!(
+      substitute("$f($...args)? costs $5 $x", &bindings),
+      "foo(a, b)? costs $5 $x"
+    );
+  }
+
+  #[test]
+  fn test_rewrite() {
+    let source = "fn main() {\n  // keep\n  a.unwrap().b(c.unwrap());\n}\n";
+    let pattern = Pattern::parse("$x.unwrap()").unwrap();
+    let rewrite = super::rewrite(source, &pattern, "$x?").unwrap();
+    assert_eq!(rewrite.source, "fn main() {\n  // keep\n  a?.b(c?);\n}\n");
+    assert_eq!(rewrite.replacements, 2);
+    assert_eq!(rewrite.error, None);
+
+    let source = "fn main() { f(g(x)); }";
+    let pattern = Pattern::parse("$f($x)").unwrap();
+    let rewrite = super::rewrite(source, &pattern, "$x(").unwrap();
+    assert_eq!(rewrite.replacements, 1);
+    assert!(rewrite.error.is_some());
+  }
+
+  #[test]
+  fn test_invalid_pattern() {
+    assert!(Pattern::parse("$x.unwrap(").is_err());
+  }
+}
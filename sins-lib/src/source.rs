@@ -0,0 +1,222 @@
+use crate::Position;
+
+/// Source text of a parsed file, indexed so that `proc_macro2` span locations
+/// can be mapped back onto byte offsets of the original text.
+///
+/// `syn` drops comments and whitespace, so rewrites built by printing syntax
+/// nodes lose formatting. Building them from `snippet`s of the original text
+/// instead, and applying them as `Splice`s, leaves everything outside the
+/// rewritten node untouched.
+#[derive(Clone, Debug)]
+pub struct SourceFile {
+  text: String,
+  line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+  pub fn new(text: impl Into<String>) -> Self {
+    let text = text.into();
+    let line_starts = std::iter::once(0)
+      .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+      .collect();
+    Self { text, line_starts }
+  }
+
+  pub fn text(&self) -> &str {
+    &self.text
+  }
+
+  /// Converts a line (1-indexed) and column (0-indexed, in chars) into a byte
+  /// offset, or `None` if the position lies outside of the text.
+  pub fn offset(&self, position: Position) -> Option<usize> {
+    let line_start = *self.line_starts.get(position.line.checked_sub(1)?)?;
+    let line_end = self
+      .line_starts
+      .get(position.line)
+      .map_or(self.text.len(), |next| next - 1);
+    let line = &self.text[line_start..line_end];
+    let column = line
+      .char_indices()
+      .map(|(i, _)| i)
+      .chain(std::iter::once(line.len()))
+      .nth(position.column)?;
+    Some(line_start + column)
+  }
+
+  pub fn byte_range(
+    &self,
+    start: Position,
+    end: Position,
+  ) -> Option<std::ops::Range<usize>> {
+    let start = self.offset(start)?;
+    let end = self.offset(end)?;
+    (start <= end).then_some(start..end)
+  }
+
+  /// The original text spanned by `node`, comments and formatting included.
+  pub fn snippet(&self, node: &dyn syn::spanned::Spanned) -> Option<&str> {
+    let span = node.span();
+    let range = self.byte_range(span.start().into(), span.end().into())?;
+    Some(&self.text[range])
+  }
+}
+
+/// Replaces a byte range of the original text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Splice {
+  pub range: std::ops::Range<usize>,
+  pub replacement: String,
+}
+
+impl Splice {
+  /// Shrinks the splice to exclude any prefix and suffix that it would leave
+  /// unchanged, so that applying it touches as little of `text` as possible.
+  pub fn minimize(self, text: &str) -> Self {
+    let original = &text[self.range.clone()];
+    let prefix: usize = original
+      .chars()
+      .zip(self.replacement.chars())
+      .take_while(|(a, b)| a == b)
+      .map(|(a, _)| a.len_utf8())
+      .sum();
+    let suffix: usize = original[prefix..]
+      .chars()
+      .rev()
+      .zip(self.replacement[prefix..].chars().rev())
+      .take_while(|(a, b)| a == b)
+      .map(|(a, _)| a.len_utf8())
+      .sum();
+    Self {
+      range: self.range.start + prefix..self.range.end - suffix,
+      replacement: self.replacement[prefix..self.replacement.len() - suffix]
+        .to_owned(),
+    }
+  }
+}
+
+/// Applies non-overlapping `splices` to `text`. An insertion sharing its
+/// start with a replacement goes before the replaced text.
+pub fn apply_splices(text: &str, mut splices: Vec<Splice>) -> String {
+  splices.sort_by_key(|splice| {
+    (
+      std::cmp::Reverse(splice.range.start),
+      std::cmp::Reverse(splice.range.end),
+    )
+  });
+  let mut text = text.to_owned();
+  for splice in splices {
+    text.replace_range(splice.range, &splice.replacement);
+  }
+  text
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn position(line: usize, column: usize) -> Position {
+    Position { line, column }
+  }
+
+  #[test]
+  fn test_offset_counts_chars() {
+    let source = SourceFile::new("// héllo\nlet x = \"ü\";\n");
+    assert_eq!(source.offset(position(1, 0)), Some(0));
+    assert_eq!(source.offset(position(1, 4)), Some(4));
+    assert_eq!(source.offset(position(1, 5)), Some(6));
+    assert_eq!(source.offset(position(2, 10)), Some(21));
+    assert_eq!(source.offset(position(2, 12)), Some(23));
+    assert_eq!(source.offset(position(2, 13)), None);
+    assert_eq!(source.offset(position(3, 0)), Some(24));
+    assert_eq!(source.offset(position(4, 0)), None);
+    assert_eq!(source.offset(position(0, 0)), None);
+  }
+
+  #[test]
+  fn test_snippet_preserves_comments() {
+    let content = "fn main() {\n  let x = a /* keep */\n    .b();\n}\n";
+    let syntax_tree = syn::parse_file(content).unwrap();
+    let source = SourceFile::new(content);
+    let syn::Item::Fn(item_fn) = &syntax_tree.items[0] else {
+      unreachable!()
+    };
+    let syn::Stmt::Local(local) = &item_fn.block.stmts[0] else {
+      unreachable!()
+    };
+    let (_, init) = local.init.as_ref().unwrap();
+    assert_eq!(source.snippet(init), Some("a /* keep */\n    .b()"));
+  }
+
+  #[test]
+  fn test_minimize() {
+    let text = "if x == true { y }";
+    let splice = Splice {
+      range: 3..12,
+      replacement: "x".into(),
+    };
+    assert_eq!(
+      splice.minimize(text),
+      Splice {
+        range: 4..12,
+        replacement: "".into()
+      }
+    );
+
+    let text = "é(a)é";
+    let splice = Splice {
+      range: 0..text.len(),
+      replacement: "é(b)é".into(),
+    };
+    assert_eq!(
+      splice.minimize(text),
+      Splice {
+        range: 3..4,
+        replacement: "b".into()
+      }
+    );
+  }
+
+  #[test]
+  fn test_apply_splices() {
+    let text = "let a = 1; // one\nlet b = 2; // two\n";
+    let splices = vec![
+      Splice {
+        range: 8..9,
+        replacement: "10".into(),
+      },
+      Splice {
+        range: 26..27,
+        replacement: "20".into(),
+      },
+    ];
+    assert_eq!(
+      apply_splices(text, splices),
+      "let a = 10; // one\nlet b = 20; // two\n"
+    );
+  }
+
+  #[test]
+  fn test_apply_adjacent_splices() {
+    // Adjacent edits, the first of which minimizes to an insertion where the
+    // second starts.
+    let text = "f(a, b)";
+    let splices = vec![
+      Splice {
+        range: 2..3,
+        replacement: "a.x".into(),
+      },
+      Splice {
+        range: 3..4,
+        replacement: ";".into(),
+      },
+    ];
+    let splices: Vec<_> =
+      splices.into_iter().map(|s| s.minimize(text)).collect();
+    assert_eq!(splices[0].range, 3..3);
+    assert_eq!(apply_splices(text, splices.clone()), "f(a.x; b)");
+    assert_eq!(
+      apply_splices(text, splices.into_iter().rev().collect()),
+      "f(a.x; b)"
+    );
+  }
+}
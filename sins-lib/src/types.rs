@@ -0,0 +1,492 @@
+//! Best-effort numeric types of expressions, from what the file spells out:
+//! literal suffixes, annotated `let`s and parameters, casts, constants, and the
+//! return types of functions declared in the same file.
+
+use std::collections::HashMap;
+
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+use crate::clippy::visitors::bindings;
+use crate::Position;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NumType {
+  I8,
+  I16,
+  I32,
+  I64,
+  I128,
+  Isize,
+  U8,
+  U16,
+  U32,
+  U64,
+  U128,
+  Usize,
+  F32,
+  F64,
+}
+
+impl NumType {
+  pub fn from_name(name: &str) -> Option<Self> {
+    Some(match name {
+      "i8" => Self::I8,
+      "i16" => Self::I16,
+      "i32" => Self::I32,
+      "i64" => Self::I64,
+      "i128" => Self::I128,
+      "isize" => Self::Isize,
+      "u8" => Self::U8,
+      "u16" => Self::U16,
+      "u32" => Self::U32,
+      "u64" => Self::U64,
+      "u128" => Self::U128,
+      "usize" => Self::Usize,
+      "f32" => Self::F32,
+      "f64" => Self::F64,
+      _ => return None,
+    })
+  }
+
+  pub fn from_type(ty: &syn::Type) -> Option<Self> {
+    match ty {
+      syn::Type::Path(path) if path.qself.is_none() => {
+        Self::from_name(&path.path.get_ident()?.to_string())
+      }
+      syn::Type::Paren(paren) => Self::from_type(&paren.elem),
+      syn::Type::Group(group) => Self::from_type(&group.elem),
+      _ => None,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Self::I8 => "i8",
+      Self::I16 => "i16",
+      Self::I32 => "i32",
+      Self::I64 => "i64",
+      Self::I128 => "i128",
+      Self::Isize => "isize",
+      Self::U8 => "u8",
+      Self::U16 => "u16",
+      Self::U32 => "u32",
+      Self::U64 => "u64",
+      Self::U128 => "u128",
+      Self::Usize => "usize",
+      Self::F32 => "f32",
+      Self::F64 => "f64",
+    }
+  }
+
+  pub fn is_float(self) -> bool {
+    matches!(self, Self::F32 | Self::F64)
+  }
+
+  pub fn is_signed(self) -> bool {
+    matches!(
+      self,
+      Self::I8 | Self::I16 | Self::I32 | Self::I64 | Self::I128 | Self::Isize
+    )
+  }
+
+  /// Whether this is `isize` or `usize`, whose width depends on the target.
+  pub fn is_pointer_sized(self) -> bool {
+    matches!(self, Self::Isize | Self::Usize)
+  }
+
+  /// The width in bits, or the narrowest and widest it can be for pointer
+  /// sized types.
+  pub fn bits(self) -> (u32, u32) {
+    match self {
+      Self::I8 | Self::U8 => (8, 8),
+      Self::I16 | Self::U16 => (16, 16),
+      Self::I32 | Self::U32 | Self::F32 => (32, 32),
+      Self::I64 | Self::U64 | Self::F64 => (64, 64),
+      Self::I128 | Self::U128 => (128, 128),
+      Self::Isize | Self::Usize => (32, 64),
+    }
+  }
+}
+
+/// The type of a numeric type's constant, such as `u8::MAX` or
+/// `std::f64::consts::PI`.
+fn associated_constant(path: &syn::Path) -> Option<NumType> {
+  let mut segments = path.segments.iter().rev();
+  let name = segments.next()?.ident.to_string();
+  if name.chars().any(|c| c.is_ascii_lowercase()) {
+    return None;
+  }
+  segments.find_map(|segment| NumType::from_name(&segment.ident.to_string()))
+}
+
+/// A variable, from its declaration to the end of its scope. Variables of
+/// unknown type are kept too, as they shadow outer ones.
+#[derive(Debug)]
+struct Binding {
+  name: String,
+  ty: Option<NumType>,
+  start: Position,
+  end: Position,
+}
+
+/// The numeric types of a file's variables and functions, where written down.
+#[derive(Debug, Default)]
+pub struct LocalTypes {
+  bindings: Vec<Binding>,
+  /// Return types of the file's functions, by name.
+  functions: HashMap<String, NumType>,
+  /// Types of the file's constants and statics, by name.
+  constants: HashMap<String, NumType>,
+  /// Ends of the blocks enclosing the node being visited, innermost last.
+  scopes: Vec<Position>,
+}
+
+impl LocalTypes {
+  pub fn new(file: &syn::File) -> Self {
+    let mut types = Self::default();
+    // Functions and constants may be used before they're declared.
+    for item in &file.items {
+      types.add_item(item);
+    }
+    types.visit_file(file);
+    types
+  }
+
+  fn add_item(&mut self, item: &syn::Item) {
+    match item {
+      syn::Item::Fn(item) => {
+        if let syn::ReturnType::Type(_, ty) = &item.sig.output {
+          if let Some(ty) = NumType::from_type(ty) {
+            self.functions.insert(item.sig.ident.to_string(), ty);
+          }
+        }
+      }
+      syn::Item::Const(item) => {
+        if let Some(ty) = NumType::from_type(&item.ty) {
+          self.constants.insert(item.ident.to_string(), ty);
+        }
+      }
+      syn::Item::Static(item) => {
+        if let Some(ty) = NumType::from_type(&item.ty) {
+          self.constants.insert(item.ident.to_string(), ty);
+        }
+      }
+      syn::Item::Mod(item) => {
+        for item in item.content.iter().flat_map(|(_, items)| items) {
+          self.add_item(item);
+        }
+      }
+      _ => {}
+    }
+  }
+
+  /// The type of `expr`, if it can be told from the file.
+  pub fn type_of(&self, expr: &syn::Expr) -> Option<NumType> {
+    match expr {
+      syn::Expr::Lit(lit) => match &lit.lit {
+        syn::Lit::Int(int) => NumType::from_name(int.suffix()),
+        // Unconstrained float literals default to `f64`, and are floats
+        // either way.
+        syn::Lit::Float(float) => {
+          NumType::from_name(float.suffix()).or(Some(NumType::F64))
+        }
+        _ => None,
+      },
+      syn::Expr::Path(path) if path.qself.is_none() => {
+        let Some(ident) = path.path.get_ident() else {
+          return associated_constant(&path.path);
+        };
+        let name = ident.to_string();
+        self
+          .variable(&name, expr.span().start().into())
+          .or_else(|| self.constants.get(&name).copied())
+      }
+      syn::Expr::Cast(cast) => NumType::from_type(&cast.ty),
+      syn::Expr::Paren(paren) => self.type_of(&paren.expr),
+      syn::Expr::Group(group) => self.type_of(&group.expr),
+      syn::Expr::Unary(unary) => match unary.op {
+        syn::UnOp::Neg(_) | syn::UnOp::Not(_) => self.type_of(&unary.expr),
+        syn::UnOp::Deref(_) => None,
+      },
+      syn::Expr::Binary(binary) => match binary.op {
+        syn::BinOp::Add(_)
+        | syn::BinOp::Sub(_)
+        | syn::BinOp::Mul(_)
+        | syn::BinOp::Div(_)
+        | syn::BinOp::Rem(_)
+        | syn::BinOp::BitAnd(_)
+        | syn::BinOp::BitOr(_)
+        | syn::BinOp::BitXor(_) => self
+          .type_of(&binary.left)
+          .or_else(|| self.type_of(&binary.right)),
+        syn::BinOp::Shl(_) | syn::BinOp::Shr(_) => self.type_of(&binary.left),
+        _ => None,
+      },
+      syn::Expr::Call(call) => match &*call.func {
+        syn::Expr::Path(path) if path.qself.is_none() => {
+          let name = path.path.get_ident()?.to_string();
+          self.functions.get(&name).copied()
+        }
+        _ => None,
+      },
+      syn::Expr::MethodCall(call) => match call.method.to_string().as_str() {
+        "len" | "count" | "capacity" => Some(NumType::Usize),
+        "count_ones" | "count_zeros" | "leading_zeros" | "trailing_zeros" => {
+          Some(NumType::U32)
+        }
+        "abs" | "pow" | "min" | "max" | "clamp" | "floor" | "ceil"
+        | "round" | "trunc" | "sqrt" | "signum" | "rem_euclid"
+        | "div_euclid" | "wrapping_add" | "wrapping_sub" | "wrapping_mul"
+        | "saturating_add" | "saturating_sub" | "saturating_mul" => {
+          self.type_of(&call.receiver)
+        }
+        _ => None,
+      },
+      _ => None,
+    }
+  }
+
+  /// The type of the variable `name` in scope at `position`.
+  fn variable(&self, name: &str, position: Position) -> Option<NumType> {
+    self
+      .bindings
+      .iter()
+      .filter(|binding| {
+        binding.name == name
+          && binding.start <= position
+          && position <= binding.end
+      })
+      .max_by_key(|binding| binding.start)
+      .and_then(|binding| binding.ty)
+  }
+
+  /// Binds the names in `pat` from `start` to `end`, a plain identifier to
+  /// `ty` and anything destructured or bound by reference to no type.
+  fn bind(
+    &mut self,
+    pat: &syn::Pat,
+    ty: Option<NumType>,
+    start: Position,
+    end: Position,
+  ) {
+    match pat {
+      syn::Pat::Ident(ident)
+        if ident.subpat.is_none() && ident.by_ref.is_none() =>
+      {
+        self.bindings.push(Binding {
+          name: ident.ident.to_string(),
+          ty,
+          start,
+          end,
+        })
+      }
+      syn::Pat::Type(pat) => self.bind(&pat.pat, ty, start, end),
+      _ => {
+        for name in bindings(pat) {
+          self.bindings.push(Binding {
+            name,
+            ty: None,
+            start,
+            end,
+          });
+        }
+      }
+    }
+  }
+
+  /// Binds the names in `pat`, typed if annotated, throughout `body`.
+  fn bind_typed(&mut self, pat: &syn::Pat, body: proc_macro2::Span) {
+    let ty = match pat {
+      syn::Pat::Type(pat) => NumType::from_type(&pat.ty),
+      _ => None,
+    };
+    self.bind(pat, ty, body.start().into(), body.end().into());
+  }
+
+  /// Binds the `let`s in the condition `cond` throughout `body`.
+  fn bind_condition(&mut self, cond: &syn::Expr, body: proc_macro2::Span) {
+    match cond {
+      syn::Expr::Let(expr) => self.bind_typed(&expr.pat, body),
+      syn::Expr::Binary(syn::ExprBinary {
+        left,
+        op: syn::BinOp::And(_),
+        right,
+        ..
+      }) => {
+        self.bind_condition(left, body);
+        self.bind_condition(right, body);
+      }
+      syn::Expr::Paren(paren) => self.bind_condition(&paren.expr, body),
+      _ => {}
+    }
+  }
+
+  fn bind_inputs<'a>(
+    &mut self,
+    inputs: impl IntoIterator<Item = &'a syn::FnArg>,
+    block: &syn::Block,
+  ) {
+    for input in inputs {
+      if let syn::FnArg::Typed(arg) = input {
+        let ty = NumType::from_type(&arg.ty);
+        let span = block.span();
+        self.bind(&arg.pat, ty, span.start().into(), span.end().into());
+      }
+    }
+  }
+}
+
+impl<'ast> Visit<'ast> for LocalTypes {
+  fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+    self.bind_inputs(&node.sig.inputs, &node.block);
+    syn::visit::visit_item_fn(self, node);
+  }
+
+  fn visit_impl_item_method(&mut self, node: &'ast syn::ImplItemMethod) {
+    self.bind_inputs(&node.sig.inputs, &node.block);
+    syn::visit::visit_impl_item_method(self, node);
+  }
+
+  fn visit_expr_closure(&mut self, node: &'ast syn::ExprClosure) {
+    for input in &node.inputs {
+      self.bind_typed(input, node.body.span());
+    }
+    syn::visit::visit_expr_closure(self, node);
+  }
+
+  fn visit_expr_for_loop(&mut self, node: &'ast syn::ExprForLoop) {
+    self.bind_typed(&node.pat, node.body.span());
+    syn::visit::visit_expr_for_loop(self, node);
+  }
+
+  fn visit_expr_if(&mut self, node: &'ast syn::ExprIf) {
+    self.bind_condition(&node.cond, node.then_branch.span());
+    syn::visit::visit_expr_if(self, node);
+  }
+
+  fn visit_expr_while(&mut self, node: &'ast syn::ExprWhile) {
+    self.bind_condition(&node.cond, node.body.span());
+    syn::visit::visit_expr_while(self, node);
+  }
+
+  fn visit_arm(&mut self, node: &'ast syn::Arm) {
+    // From the pattern's end, so that the guard sees the bindings too.
+    let start = node.pat.span().end().into();
+    self.bind(&node.pat, None, start, node.body.span().end().into());
+    syn::visit::visit_arm(self, node);
+  }
+
+  fn visit_block(&mut self, node: &'ast syn::Block) {
+    self.scopes.push(node.span().end().into());
+    syn::visit::visit_block(self, node);
+    self.scopes.pop();
+  }
+
+  fn visit_local(&mut self, node: &'ast syn::Local) {
+    // The initializer is visited first, as it can't see the new binding.
+    syn::visit::visit_local(self, node);
+    let ty = match &node.pat {
+      syn::Pat::Type(pat) => NumType::from_type(&pat.ty),
+      _ => node.init.as_ref().and_then(|(_, init)| self.type_of(init)),
+    };
+    if let Some(&end) = self.scopes.last() {
+      self.bind(&node.pat, ty, node.span().end().into(), end);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_type_of() {
+    let source = "
+fn len() -> usize { 0 }
+const LIMIT: u16 = 8;
+
+fn f(a: u8, b: f32) {
+  let c: i64 = 1;
+  let d = a as u16 + 1;
+  let e = len();
+  {
+    let a = 1.5;
+    (a, b, c, d, e, 1u32, 1);
+  }
+  (a, x, |a: i8| a, LIMIT, u8::MAX, std::f32::consts::PI, f64::max);
+}
+
+fn g(x: u8, y: u8, z: u8, w: u8, v: Vec<&u8>, o: Option<&u8>) {
+  for x in v {
+    let y = v[0];
+    (x, y, |z| z, z);
+    match o { Some(w) => (w, x), None => (w, x) };
+  }
+  if let Some(x) = o { (x, y); }
+  while let Some(y) = o { (x, y); }
+  (x, y, z, w);
+}
+";
+    let file = syn::parse_file(source).unwrap();
+    let types = LocalTypes::new(&file);
+    let mut tuples = vec![];
+    struct Tuples<'a>(&'a mut Vec<syn::ExprTuple>);
+    impl<'ast> Visit<'ast> for Tuples<'_> {
+      fn visit_expr_tuple(&mut self, node: &'ast syn::ExprTuple) {
+        self.0.push(node.clone());
+        syn::visit::visit_expr_tuple(self, node);
+      }
+    }
+    Tuples(&mut tuples).visit_file(&file);
+    let names = |tuple: &syn::ExprTuple| -> Vec<Option<&str>> {
+      tuple
+        .elems
+        .iter()
+        .map(|expr| match expr {
+          syn::Expr::Closure(closure) => types.type_of(&closure.body),
+          expr => types.type_of(expr),
+        })
+        .map(|ty| ty.map(NumType::name))
+        .collect()
+    };
+    assert_eq!(
+      names(&tuples[0]),
+      [
+        Some("f64"),
+        Some("f32"),
+        Some("i64"),
+        Some("u16"),
+        Some("usize"),
+        Some("u32"),
+        None
+      ]
+    );
+    assert_eq!(
+      names(&tuples[1]),
+      [
+        Some("u8"),
+        None,
+        Some("i8"),
+        Some("u16"),
+        Some("u8"),
+        Some("f32"),
+        None
+      ]
+    );
+    // Bindings of unknown type shadow outer ones all the same.
+    let u8 = Some("u8");
+    let shadowed: Vec<_> = tuples[2..].iter().map(names).collect();
+    assert_eq!(
+      shadowed,
+      [
+        vec![None, None, None, u8],
+        vec![None, None],
+        vec![u8, None],
+        vec![None, u8],
+        vec![u8, None],
+        vec![u8, u8, u8, u8],
+      ]
+    );
+  }
+}
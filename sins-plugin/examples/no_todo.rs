@@ -0,0 +1,26 @@
+//! A minimal plugin, built with `cargo build -p sins-plugin --example
+//! no_todo` and enabled with `plugins = ["<path to libno_todo.so>"]`.
+
+use sins_plugin::{syn, Report, Rule, RuleContext};
+
+struct NoTodo;
+
+impl<'ast> Rule<'ast> for NoTodo {
+  fn name(&self) -> &'static str {
+    "no_todo"
+  }
+
+  fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+    if let syn::Expr::Macro(node) = node {
+      if node.mac.path.is_ident("todo") {
+        context.emit_report(Report {
+          message: "`todo!()` left in code".to_owned(),
+          node,
+          suggestion: None,
+        });
+      }
+    }
+  }
+}
+
+sins_plugin::register!(NoTodo);
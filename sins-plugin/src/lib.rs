@@ -0,0 +1,244 @@
+//! API for rules that live outside of sins, in a `cdylib` or WebAssembly
+//! module that sins loads from the `plugins` listed in `sins.toml`.
+//!
+//! A plugin implements [`Rule`] as the built-in rules do and exports its rules
+//! with [`register!`]:
+//!
+//! ```ignore
+//! struct NoTodo;
+//!
+//! impl<'ast> sins_plugin::Rule<'ast> for NoTodo { ... }
+//!
+//! sins_plugin::register!(NoTodo);
+//! ```
+//!
+//! Span locations can't be shared with the host across a dynamic library or
+//! into WebAssembly, so the plugin parses and lints each file with its own copy
+//! of sins-lib, and only the source and the resulting diagnostics (as JSON)
+//! cross the boundary. That boundary is versioned by [`ABI_VERSION`], which
+//! sins checks on load.
+//!
+//! Plugins built for `wasm32-unknown-unknown` export `memory`,
+//! `sins_plugin_abi_version`, `sins_plugin_alloc(len) -> ptr` and
+//! `sins_plugin_lint(ptr, len) -> u64` instead, and are run by sins with no
+//! imports, bounded memory and a bounded number of instructions per file.
+
+use std::panic::AssertUnwindSafe;
+
+pub use sins_lib::source::SourceFile;
+pub use sins_lib::{
+  Applicability, Diagnostic, Edit, NodeKind, ParseError, Position, Report,
+  Rule, RuleContext, RuleEngine, Suggestion,
+};
+pub use syn;
+
+/// Version of the interface between sins and plugins, bumped whenever the
+/// exported functions or the JSON they exchange change incompatibly.
+pub const ABI_VERSION: u32 = 1;
+
+/// Bytes allocated by a plugin, which must be released by the same plugin's
+/// `sins_plugin_free`.
+#[repr(C)]
+pub struct Buffer {
+  pub ptr: *mut u8,
+  pub len: usize,
+  pub capacity: usize,
+}
+
+impl Buffer {
+  pub fn new(bytes: Vec<u8>) -> Self {
+    let mut bytes = std::mem::ManuallyDrop::new(bytes);
+    Self {
+      ptr: bytes.as_mut_ptr(),
+      len: bytes.len(),
+      capacity: bytes.capacity(),
+    }
+  }
+
+  /// # Safety
+  ///
+  /// The buffer must have been created by `Buffer::new` and not yet freed.
+  pub unsafe fn as_slice(&self) -> &[u8] {
+    std::slice::from_raw_parts(self.ptr, self.len)
+  }
+
+  /// # Safety
+  ///
+  /// The buffer must have been created by `Buffer::new` in this same library,
+  /// and not yet freed.
+  pub unsafe fn into_vec(self) -> Vec<u8> {
+    Vec::from_raw_parts(self.ptr, self.len, self.capacity)
+  }
+}
+
+/// What `sins_plugin_lint` returns as JSON: the file's diagnostics or the
+/// error parsing it, or a message if the plugin panicked.
+pub type Response = Result<Result<Vec<Diagnostic>, ParseError>, String>;
+
+/// Lints `source` with the rules `factory` adds, returning the JSON
+/// `Response`.
+#[doc(hidden)]
+pub fn __respond(
+  source: &str,
+  factory: impl Fn(&mut RuleEngine<'_>) + Sync,
+) -> Vec<u8> {
+  let response: Response = std::panic::catch_unwind(AssertUnwindSafe(|| {
+    sins_lib::lint(source, &factory)
+  }))
+  .map_err(|panic| {
+    let message = panic
+      .downcast_ref::<&str>()
+      .map(|message| message.to_string())
+      .or_else(|| panic.downcast_ref::<String>().cloned());
+    message.unwrap_or_else(|| "panicked".to_owned())
+  });
+  // The host may call us on the same thread for as long as it runs.
+  proc_macro2::extra::invalidate_current_thread_spans();
+  serde_json::to_vec(&response).unwrap_or_default()
+}
+
+/// Implementation of the native `sins_plugin_lint`, for `register!`.
+///
+/// # Safety
+///
+/// `source` must point to `len` bytes of UTF-8.
+#[doc(hidden)]
+pub unsafe fn __lint(
+  source: *const u8,
+  len: usize,
+  factory: impl Fn(&mut RuleEngine<'_>) + Sync,
+) -> Buffer {
+  let source =
+    std::str::from_utf8_unchecked(std::slice::from_raw_parts(source, len));
+  Buffer::new(__respond(source, factory))
+}
+
+/// Implementation of the WebAssembly `sins_plugin_alloc`, for `register!`.
+/// A WebAssembly instance only lints a single file, so nothing is freed.
+#[doc(hidden)]
+pub fn __alloc(len: usize) -> *mut u8 {
+  Box::leak(vec![0; len].into_boxed_slice()).as_mut_ptr()
+}
+
+/// Implementation of the WebAssembly `sins_plugin_lint`, for `register!`,
+/// returning the address of the response in the high 32 bits and its length
+/// in the low ones.
+///
+/// # Safety
+///
+/// `source` must point to `len` bytes of UTF-8.
+#[doc(hidden)]
+pub unsafe fn __lint_wasm(
+  source: *const u8,
+  len: usize,
+  factory: impl Fn(&mut RuleEngine<'_>) + Sync,
+) -> u64 {
+  let source =
+    std::str::from_utf8_unchecked(std::slice::from_raw_parts(source, len));
+  let response = Box::leak(__respond(source, factory).into_boxed_slice());
+  ((response.as_ptr() as usize as u64) << 32) | response.len() as u64
+}
+
+/// Exports the given rules from a plugin, built either as a `cdylib` for the
+/// host platform or as a WebAssembly module (`--target wasm32-unknown-unknown`)
+/// that sins runs sandboxed. Each argument is an expression creating a rule,
+/// evaluated once per linted file.
+#[macro_export]
+macro_rules! register {
+  ($($rule:expr),+ $(,)?) => {
+    #[no_mangle]
+    pub extern "C" fn sins_plugin_abi_version() -> u32 {
+      $crate::ABI_VERSION
+    }
+
+    /// # Safety
+    ///
+    /// `source` must point to `len` bytes of UTF-8.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[no_mangle]
+    pub unsafe extern "C" fn sins_plugin_lint(
+      source: *const u8,
+      len: usize,
+    ) -> $crate::Buffer {
+      $crate::__lint(source, len, |rule_engine: &mut $crate::RuleEngine| {
+        $(rule_engine.add_rule(Box::new($rule));)+
+      })
+    }
+
+    /// # Safety
+    ///
+    /// `buffer` must have been returned by `sins_plugin_lint`.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[no_mangle]
+    pub unsafe extern "C" fn sins_plugin_free(buffer: $crate::Buffer) {
+      drop($crate::Buffer::into_vec(buffer));
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[no_mangle]
+    pub extern "C" fn sins_plugin_alloc(len: usize) -> *mut u8 {
+      $crate::__alloc(len)
+    }
+
+    /// # Safety
+    ///
+    /// `source` must point to `len` bytes of UTF-8.
+    #[cfg(target_arch = "wasm32")]
+    #[no_mangle]
+    pub unsafe extern "C" fn sins_plugin_lint(
+      source: *const u8,
+      len: usize,
+    ) -> u64 {
+      $crate::__lint_wasm(source, len, |rule_engine: &mut $crate::RuleEngine| {
+        $(rule_engine.add_rule(Box::new($rule));)+
+      })
+    }
+  };
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  struct NoTodo;
+
+  impl<'ast> Rule<'ast> for NoTodo {
+    fn name(&self) -> &'static str {
+      "no_todo"
+    }
+
+    fn apply_expr(&mut self, context: &RuleContext, node: &'ast syn::Expr) {
+      if let syn::Expr::Macro(node) = node {
+        if node.mac.path.is_ident("todo") {
+          context.emit_report(Report {
+            message: "unfinished code".to_owned(),
+            node,
+            suggestion: None,
+          });
+        }
+      }
+    }
+  }
+
+  register!(NoTodo);
+
+  fn call(source: &str) -> Response {
+    assert_eq!(sins_plugin_abi_version(), ABI_VERSION);
+    unsafe {
+      let buffer = sins_plugin_lint(source.as_ptr(), source.len());
+      let response = serde_json::from_slice(buffer.as_slice()).unwrap();
+      sins_plugin_free(buffer);
+      response
+    }
+  }
+
+  #[test]
+  fn test_register() {
+    let diagnostics = call("fn f() { todo!() }").unwrap().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].rule, "no_todo");
+    assert_eq!(diagnostics[0].start, Position { line: 1, column: 9 });
+
+    assert!(call("fn f() {").unwrap().is_err());
+  }
+}